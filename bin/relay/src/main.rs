@@ -2,19 +2,18 @@
 //!
 //! A relay service that sponsors transactions for EIP-7702 accounts.
 
-use alloy_provider::{network::EthereumWallet, Provider, ProviderBuilder};
-use alloy_rpc_client::RpcClient;
+use alloy_provider::{network::EthereumWallet, Provider};
 use alloy_signer_local::PrivateKeySigner;
 use clap::Parser;
 use eyre::Context;
 use hyper::Method;
 use jsonrpsee::server::Server;
-use traverse_wallet::{AlloyUpstream, TraverseWallet, TraverseWalletApiServer};
 use reth_tracing::Tracer;
 use std::net::{IpAddr, Ipv4Addr};
 use tower::ServiceBuilder;
 use tower_http::cors::{Any, CorsLayer};
 use tracing::info;
+use traverse_wallet::{AlloyUpstream, HttpClientConfig, TraverseWallet, TraverseWalletApiServer};
 use url::Url;
 
 /// The Traverse relayer service sponsors transactions for EIP-7702 accounts.
@@ -44,15 +43,14 @@ impl Args {
         // construct provider
         let signer: PrivateKeySigner = self.secret_key.parse().wrap_err("Invalid signing key")?;
         let wallet = EthereumWallet::from(signer);
-        let rpc_client = RpcClient::new_http(self.upstream).boxed();
-        let provider =
-            ProviderBuilder::new().with_recommended_fillers().wallet(wallet).on_client(rpc_client);
+        let upstream =
+            AlloyUpstream::with_client_config(self.upstream, wallet, HttpClientConfig::default())?;
 
         // get chain id
-        let chain_id = provider.get_chain_id().await?;
+        let chain_id = upstream.provider().get_chain_id().await?;
 
         // construct rpc module
-        let rpc = TraverseWallet::new(AlloyUpstream::new(provider), chain_id).into_rpc();
+        let rpc = TraverseWallet::new(upstream, chain_id).into_rpc();
 
         // start server
         let cors = CorsLayer::new()
@@ -27,22 +27,16 @@ use alloy_network::{Ethereum, EthereumWallet, NetworkWallet};
 use alloy_signer_local::PrivateKeySigner;
 use clap::Parser;
 use eyre::Context;
-use traverse_node::{
-    broadcaster::periodic_broadcaster,
-    chainspec::TraverseChainSpecParser,
-    delayed_resolve::{DelayedResolver, MAX_DELAY_INTO_SLOT},
-    forwarder::forward_raw_transactions,
-    node::TraverseNode,
-    rpc::{EthApiExt, EthApiOverrideServer},
-};
-use traverse_wallet::{TraverseWallet, TraverseWalletApiServer, RethUpstream};
-use traverse_walltime::{TraverseWallTime, TraverseWallTimeRpcApiServer};
-use reth_node_builder::{engine_tree_config::TreeConfig, EngineNodeLauncher, NodeComponents};
+use reth_node_builder::{engine_tree_config::TreeConfig, EngineNodeLauncher, Node, NodeComponents};
 use reth_optimism_cli::Cli;
-use reth_optimism_node::{args::RollupArgs, node::OpAddOnsBuilder};
-use reth_provider::{providers::BlockchainProvider2, CanonStateSubscriptions};
+use reth_optimism_node::args::RollupArgs;
+use reth_provider::providers::BlockchainProvider2;
 use std::time::Duration;
 use tracing::{info, warn};
+use traverse_node::{
+    broadcaster::periodic_broadcaster, chainspec::TraverseChainSpecParser,
+    forwarder::forward_raw_transactions, node::TraverseNode,
+};
 
 #[global_allocator]
 static ALLOC: reth_cli_util::allocator::Allocator = reth_cli_util::allocator::new_allocator();
@@ -63,12 +57,32 @@ fn main() {
                 .as_ref()
                 .map(<EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address);
 
+            let node = TraverseNode::new(rollup_args.clone()).with_sponsor(wallet);
+            let node = match payload_delay() {
+                Some(delay) => node.with_payload_delay(delay),
+                None => node,
+            };
+            let node = match payload_deadline() {
+                Some(deadline) => node.with_payload_deadline(deadline),
+                None => node,
+            };
+            let node = node.with_storage_only_proofs(storage_only_proofs());
+            let node = node.with_warm_up_precompiles(warm_up_precompiles());
+            let node = node.with_block_production(block_production());
+            let node = node.with_trusted_nodes_only(trusted_nodes_only());
+            let node = match max_proof_keys() {
+                Some(max_proof_keys) => node.with_max_proof_keys(max_proof_keys),
+                None => node,
+            };
+            let node = match std::env::var_os("WALLTIME_PERSISTENCE_PATH") {
+                Some(path) => node.with_walltime_persistence(path),
+                None => node,
+            };
+
             let handle = builder
                 .with_types_and_provider::<TraverseNode, BlockchainProvider2<_>>()
-                .with_components(TraverseNode::components(&rollup_args))
-                .with_add_ons(
-                    OpAddOnsBuilder::default().with_sequencer(rollup_args.sequencer_http).build(),
-                )
+                .with_components(node.components_builder())
+                .with_add_ons(node.add_ons())
                 .on_component_initialized(move |ctx| {
                     if let Some(address) = address {
                         ctx.task_executor.spawn(async move {
@@ -87,45 +101,6 @@ fn main() {
 
                     Ok(())
                 })
-                .extend_rpc_modules(move |ctx| {
-                    // override eth namespace
-                    ctx.modules.replace_configured(
-                        EthApiExt::new(ctx.registry.eth_api().clone()).into_rpc(),
-                    )?;
-
-                    // register traverse wallet namespace
-                    if let Some(wallet) = wallet {
-                        ctx.modules.merge_configured(
-                            TraverseWallet::new(
-                                RethUpstream::new(
-                                    ctx.provider().clone(),
-                                    ctx.registry.eth_api().clone(),
-                                    wallet,
-                                ),
-                                ctx.config().chain.chain().id(),
-                            )
-                            .into_rpc(),
-                        )?;
-                    }
-
-                    let walltime = TraverseWallTime::spawn(ctx.provider().canonical_state_stream());
-                    ctx.modules.merge_configured(walltime.into_rpc())?;
-                    info!(target: "reth::cli", "Walltime configured");
-
-                    // wrap the getPayloadV3 method in a delay
-                    let engine_module = ctx.auth_module.module_mut().clone();
-                    let delay_into_slot = std::env::var("MAX_PAYLOAD_DELAY")
-                        .ok()
-                        .and_then(|val| val.parse::<u64>().map(Duration::from_millis).ok())
-                        .unwrap_or(MAX_DELAY_INTO_SLOT);
-
-                    let delayed_payload = DelayedResolver::new(engine_module, delay_into_slot);
-                    delayed_payload.clone().spawn(ctx.provider().canonical_state_stream());
-                    ctx.auth_module.replace_auth_methods(delayed_payload.into_rpc_module())?;
-                    info!(target: "reth::cli", "Configured payload delay");
-
-                    Ok(())
-                })
                 .launch_with_fn(|builder| {
                     let engine_tree_config = TreeConfig::default()
                         .with_persistence_threshold(rollup_args.persistence_threshold)
@@ -153,6 +128,50 @@ fn main() {
     }
 }
 
+/// Returns the `engine_getPayloadV3` delay configured via `MAX_PAYLOAD_DELAY`, if any.
+fn payload_delay() -> Option<Duration> {
+    std::env::var("MAX_PAYLOAD_DELAY")
+        .ok()
+        .and_then(|val| val.parse::<u64>().map(Duration::from_millis).ok())
+}
+
+/// Returns the maximum duration of building a payload configured via `PAYLOAD_DEADLINE` in
+/// milliseconds, if any.
+fn payload_deadline() -> Option<Duration> {
+    std::env::var("PAYLOAD_DEADLINE")
+        .ok()
+        .and_then(|val| val.parse::<u64>().map(Duration::from_millis).ok())
+}
+
+/// Returns whether `eth_getProof` returns storage-only proofs for the withdrawal contract, which
+/// is disabled by setting `STORAGE_ONLY_PROOFS=false`.
+fn storage_only_proofs() -> bool {
+    !std::env::var("STORAGE_ONLY_PROOFS").is_ok_and(|val| val == "false")
+}
+
+/// Returns the maximum number of storage keys of a proof configured via `MAX_PROOF_KEYS`, if any.
+fn max_proof_keys() -> Option<usize> {
+    std::env::var("MAX_PROOF_KEYS").ok().and_then(|val| val.parse().ok())
+}
+
+/// Returns whether the precompiles of the spec at the head are built on startup, which is
+/// enabled by setting `WARM_UP_PRECOMPILES=true`.
+fn warm_up_precompiles() -> bool {
+    std::env::var("WARM_UP_PRECOMPILES").is_ok_and(|val| val == "true")
+}
+
+/// Returns whether the node builds payloads, which is disabled by setting
+/// `BLOCK_PRODUCTION=false` on nodes that do not sequence.
+fn block_production() -> bool {
+    !std::env::var("BLOCK_PRODUCTION").is_ok_and(|val| val == "false")
+}
+
+/// Returns whether only trusted peers are connected to, which is enabled by setting
+/// `TRUSTED_NODES_ONLY=true`.
+fn trusted_nodes_only() -> bool {
+    std::env::var("TRUSTED_NODES_ONLY").is_ok_and(|val| val == "true")
+}
+
 /// Returns a [`EthereumWallet`] with the sponsor private key.
 fn sponsor() -> eyre::Result<Option<EthereumWallet>> {
     std::env::var("EXP1_SK")
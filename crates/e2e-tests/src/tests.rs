@@ -100,6 +100,57 @@ async fn test_wallet_api() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Tests that sponsored transactions are included at the top of the built payload
+#[tokio::test]
+async fn test_sponsored_transactions_top_of_block() -> Result<(), Box<dyn std::error::Error>> {
+    if !ci_info::is_ci() {
+        return Ok(());
+    }
+
+    let provider = ProviderBuilder::new().on_http(REPLICA_RPC.clone());
+    let signer = PrivateKeySigner::from_bytes(&TEST_PRIVATE_KEY)?;
+
+    let delegation_address = Address::from_str(
+        &std::env::var("DELEGATION_ADDRESS")
+            .unwrap_or_else(|_| DEFAULT_DELEGATION_ADDRESS.to_string()),
+    )?;
+
+    let auth = Authorization {
+        chain_id: provider.get_chain_id().await?,
+        address: delegation_address,
+        nonce: provider.get_transaction_count(signer.address()).await?,
+    };
+    let signature = signer.sign_hash_sync(&auth.signature_hash())?;
+    let tx = TransactionRequest::default()
+        .with_authorization_list(vec![auth.into_signed(signature)])
+        .with_to(signer.address());
+
+    let tx_hash: B256 = provider.client().request("wallet_sendTransaction", vec![tx]).await?;
+    let receipt = PendingTransactionBuilder::new(provider.clone(), tx_hash).get_receipt().await?;
+    assert!(receipt.status(), "Transaction failed");
+
+    // only deposits and other sponsored transactions are included ahead of it
+    let block: serde_json::Value = provider
+        .client()
+        .request(
+            "eth_getBlockByNumber",
+            (BlockNumberOrTag::Number(receipt.block_number.unwrap()), true),
+        )
+        .await?;
+    let transactions = block["transactions"].as_array().unwrap();
+    let index = receipt.transaction_index.unwrap() as usize;
+    assert_eq!(transactions[index]["hash"], serde_json::json!(tx_hash));
+    for tx in &transactions[..index] {
+        assert!(
+            tx["type"] == "0x7e" || tx["from"] == serde_json::json!(receipt.from),
+            "Transaction {} is included ahead of the sponsored transaction",
+            tx["hash"]
+        );
+    }
+
+    Ok(())
+}
+
 // This is new endpoint `traverse_sendTransaction`, upper test will be deprecate in the future.
 #[tokio::test]
 async fn test_new_wallet_api() -> Result<(), Box<dyn std::error::Error>> {
@@ -201,3 +252,99 @@ async fn test_withdrawal_proof_with_fallback() -> Result<(), Box<dyn std::error:
 
     Ok(())
 }
+
+/// Tests that the full proof of the withdrawal contract includes the account proof
+#[tokio::test]
+async fn test_full_withdrawal_proof() -> Result<(), Box<dyn std::error::Error>> {
+    if !ci_info::is_ci() {
+        return Ok(());
+    }
+
+    let provider = ProviderBuilder::new().on_http(REPLICA_RPC.clone());
+
+    let block: Block = provider
+        .client()
+        .request("eth_getBlockByNumber", (BlockNumberOrTag::Latest, false))
+        .await?;
+    let block_number = BlockNumberOrTag::Number(block.header.number);
+
+    let response: EIP1186AccountProofResponse = provider
+        .client()
+        .request(
+            "traverse_getFullProof",
+            (traverse_common::WITHDRAWAL_CONTRACT, vec![B256::ZERO], block_number),
+        )
+        .await?;
+
+    assert!(!response.account_proof.is_empty());
+    assert_eq!(response.storage_proof.len(), 1);
+
+    let storage_root = response.storage_hash;
+    for proof in &response.storage_proof {
+        StorageProof::new(proof.key.as_b256())
+            .with_proof(proof.proof.clone())
+            .verify(storage_root)?
+    }
+    AccountProof {
+        address: traverse_common::WITHDRAWAL_CONTRACT,
+        info: Some(Account {
+            nonce: response.nonce,
+            balance: response.balance,
+            bytecode_hash: Some(response.code_hash),
+        }),
+        storage_root,
+        proof: response.account_proof,
+        ..Default::default()
+    }
+    .verify(block.header.state_root)?;
+
+    Ok(())
+}
+
+/// Tests that the node installs the walltime endpoint as part of its add-ons
+#[tokio::test]
+async fn test_walltime_data() -> Result<(), Box<dyn std::error::Error>> {
+    if !ci_info::is_ci() {
+        return Ok(());
+    }
+
+    let provider = ProviderBuilder::new().on_http(REPLICA_RPC.clone());
+
+    let walltime: serde_json::Value =
+        provider.client().request("traverse_getWallTimeData", ()).await?;
+
+    assert!(walltime["current_wall_time_ms"].as_u64().is_some_and(|ms| ms > 0));
+    assert!(walltime["last_block_timestamp"].as_u64().is_some());
+
+    Ok(())
+}
+
+/// Tests that multiple proofs can be fetched in a single call
+#[tokio::test]
+async fn test_batch_proofs() -> Result<(), Box<dyn std::error::Error>> {
+    if !ci_info::is_ci() {
+        return Ok(());
+    }
+
+    let provider = ProviderBuilder::new().on_http(REPLICA_RPC.clone());
+    let signer = PrivateKeySigner::from_bytes(&TEST_PRIVATE_KEY)?;
+    let block_number = BlockNumberOrTag::Number(provider.get_block_number().await?);
+
+    let proofs: Vec<EIP1186AccountProofResponse> = provider
+        .client()
+        .request(
+            "traverse_getProofs",
+            (vec![
+                (traverse_common::WITHDRAWAL_CONTRACT, vec![B256::ZERO], block_number),
+                (signer.address(), vec![], block_number),
+            ],),
+        )
+        .await?;
+
+    assert_eq!(proofs.len(), 2);
+    assert!(proofs[0].account_proof.is_empty());
+    assert_eq!(proofs[0].storage_proof.len(), 1);
+    assert!(!proofs[1].account_proof.is_empty());
+
+    Ok(())
+}
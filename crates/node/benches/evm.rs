@@ -0,0 +1,81 @@
+//! Benchmarks the construction of EVMs with the Traverse precompiles, and the resolution of spec
+//! ids.
+
+#![allow(missing_docs)]
+
+use alloy_consensus::Header;
+use criterion::{criterion_group, criterion_main, Criterion};
+use reth_chainspec::{Chain, ChainSpecBuilder, EthereumHardfork};
+use reth_node_api::ConfigureEvm;
+use reth_optimism_chainspec::OpChainSpec;
+use reth_primitives::ForkCondition;
+use reth_revm::{
+    db::EmptyDB, handler::register::EvmHandler, precompile::PrecompileSpecId, ContextPrecompiles,
+    Database, EvmBuilder,
+};
+use revm_precompile::secp256r1::P256VERIFY as REVM_P256VERIFY;
+use std::{hint::black_box, sync::Arc};
+use traverse_node::evm::{revm_spec_by_header, TraverseEvmConfig, P256VERIFY};
+
+/// Sets the precompiles by extending the base precompiles of the spec on every EVM creation.
+fn set_uncached_precompiles<EXT, DB: Database>(handler: &mut EvmHandler<'_, EXT, DB>) {
+    let spec_id = handler.cfg.spec_id;
+    handler.pre_execution.load_precompiles = Arc::new(move || {
+        let mut precompiles: ContextPrecompiles<DB> =
+            ContextPrecompiles::new(PrecompileSpecId::from_spec_id(spec_id));
+        precompiles.extend([P256VERIFY, REVM_P256VERIFY]);
+        precompiles
+    });
+}
+
+fn evm_construction(c: &mut Criterion) {
+    let chain_spec = Arc::new(OpChainSpec::new(
+        ChainSpecBuilder::default()
+            .chain(Chain::optimism_mainnet())
+            .genesis(Default::default())
+            .with_fork(EthereumHardfork::Frontier, ForkCondition::Block(0))
+            .build(),
+    ));
+    let config = TraverseEvmConfig::new(chain_spec);
+
+    let mut group = c.benchmark_group("evm_construction");
+    group.bench_function("uncached_precompiles", |b| {
+        b.iter(|| {
+            let evm = EvmBuilder::default()
+                .with_empty_db()
+                .optimism()
+                .append_handler_register(set_uncached_precompiles)
+                .build();
+            black_box(evm.handler.pre_execution().load_precompiles())
+        })
+    });
+    group.bench_function("cached_precompiles", |b| {
+        b.iter(|| {
+            let evm = config.evm(EmptyDB::default());
+            black_box(evm.handler.pre_execution().load_precompiles())
+        })
+    });
+    group.finish();
+}
+
+fn spec_id(c: &mut Criterion) {
+    let chain_spec = Arc::new(OpChainSpec::new(
+        ChainSpecBuilder::default()
+            .chain(Chain::optimism_mainnet())
+            .genesis(Default::default())
+            .with_fork(EthereumHardfork::Frontier, ForkCondition::Block(0))
+            .build(),
+    ));
+    let config = TraverseEvmConfig::new(chain_spec.clone());
+    let header = Header { timestamp: 1, number: 1, ..Default::default() };
+
+    let mut group = c.benchmark_group("spec_id");
+    group.bench_function("uncached", |b| {
+        b.iter(|| black_box(revm_spec_by_header(&chain_spec, &header)))
+    });
+    group.bench_function("cached", |b| b.iter(|| black_box(config.spec_id(&header))));
+    group.finish();
+}
+
+criterion_group!(benches, evm_construction, spec_id);
+criterion_main!(benches);
@@ -0,0 +1,47 @@
+//! Benchmarks storage-only proofs of key sets of different sizes, in a single multiproof and in
+//! batches.
+//!
+//! Run with `cargo bench -p traverse-node --bench proof --features test-utils`.
+
+#![allow(missing_docs)]
+
+use alloy_primitives::B256;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::{collections::BTreeMap, hint::black_box};
+use traverse_common::WITHDRAWAL_CONTRACT;
+use traverse_node::rpc::{
+    batched_storage_only_proof,
+    test_utils::{storage_leaves, storage_multiproof},
+};
+
+/// The number of slots of the storage trie.
+const STORAGE_SIZE: u64 = 10_000;
+
+fn storage_only_proofs(c: &mut Criterion) {
+    let slots: Vec<_> =
+        (0..STORAGE_SIZE).map(|i| B256::left_padding_from(&i.to_be_bytes())).collect();
+    let leaves = storage_leaves(&slots.iter().map(|slot| (*slot, 1)).collect::<BTreeMap<_, _>>());
+
+    let batch_sizes = [("single", None), ("batch_16", Some(16)), ("batch_128", Some(128))];
+
+    let mut group = c.benchmark_group("storage_only_proof");
+    for size in [1, 16, 128, 1024] {
+        let keys = &slots[..size];
+        for (name, batch_size) in batch_sizes {
+            group.bench_with_input(BenchmarkId::new(name, size), keys, |b, keys| {
+                b.iter(|| {
+                    black_box(
+                        batched_storage_only_proof(WITHDRAWAL_CONTRACT, keys, batch_size, |keys| {
+                            Ok(storage_multiproof(&leaves, keys))
+                        })
+                        .unwrap(),
+                    )
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, storage_only_proofs);
+criterion_main!(benches);
@@ -13,7 +13,8 @@
 use alloy_consensus::Header;
 use alloy_primitives::{Address, Bytes, TxKind, U256};
 use op_alloy_consensus::EIP1559ParamError;
-use reth_chainspec::{ChainSpec, EthereumHardfork};
+use parking_lot::RwLock;
+use reth_chainspec::{ChainSpec, EthereumHardfork, ForkCondition};
 use reth_evm::env::EvmEnv;
 use reth_node_api::{ConfigureEvm, ConfigureEvmEnv, NextBlockEnvAttributes};
 use reth_optimism_chainspec::OpChainSpec;
@@ -21,21 +22,37 @@ use reth_optimism_forks::OpHardfork;
 use reth_optimism_primitives::OpTransactionSigned;
 use reth_primitives::transaction::FillTxEnv;
 use reth_revm::{
+    builder::HandlerStage,
     handler::register::EvmHandler,
     inspector_handle_register,
+    interpreter::{Host, InstructionResult, Interpreter},
     precompile::PrecompileSpecId,
     primitives::{
         AnalysisKind, BlobExcessGasAndPrice, BlockEnv, CfgEnv, Env, HandlerCfg, OptimismFields,
         SpecId,
     },
-    ContextPrecompiles, Database, Evm, EvmBuilder, GetInspector,
+    ContextPrecompile, ContextPrecompiles, Database, Evm, EvmBuilder, GetInspector,
 };
 use revm_precompile::{
     secp256r1::{p256_verify, P256VERIFY as REVM_P256VERIFY},
-    u64_to_address, PrecompileWithAddress,
+    u64_to_address, PrecompileWithAddress, Precompiles,
 };
-use revm_primitives::{CfgEnvWithHandlerCfg, Precompile, TxEnv};
-use std::sync::Arc;
+use revm_primitives::{
+    CfgEnvWithHandlerCfg, Precompile, PrecompileError, PrecompileOutput, PrecompileResult,
+    StatefulPrecompile, TxEnv,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, LazyLock},
+};
+
+/// The gas limit of system calls, see [`ConfigureEvmEnv::fill_tx_env_system_contract_call`].
+pub const SYSTEM_CALL_GAS_LIMIT: u64 = 30_000_000;
+
+/// The bytecode analysis of the EVM.
+pub const ANALYSIS_KIND: AnalysisKind = AnalysisKind::Analyse;
 
 /// P256 verify precompile address.
 pub const P256VERIFY_ADDRESS: u64 = 0x14;
@@ -44,69 +61,381 @@ pub const P256VERIFY_ADDRESS: u64 = 0x14;
 pub const P256VERIFY: PrecompileWithAddress =
     PrecompileWithAddress(u64_to_address(P256VERIFY_ADDRESS), Precompile::Standard(p256_verify));
 
+/// The precompiles of each spec, keyed by whether the additional precompiles are active.
+///
+/// The sets are shared by every [`TraverseEvmConfig`], so that creating an EVM borrows the
+/// precompiles instead of copying the base precompiles of the spec to extend them. Each set is
+/// built once and leaked, which is bounded by the number of [`PrecompileSpecId`]s.
+static PRECOMPILES: LazyLock<RwLock<HashMap<(PrecompileSpecId, bool), &'static Precompiles>>> =
+    LazyLock::new(Default::default);
+
+/// The maximum number of blocks whose spec id is cached by a [`TraverseEvmConfig`].
+///
+/// The cache is cleared once it is full, which keeps it bounded without tracking recency.
+pub const SPEC_ID_CACHE_SIZE: usize = 256;
+
+/// A P256 verify precompile that charges a fixed, overridden gas cost.
+///
+/// This wraps [`p256_verify`], and only replaces the gas it charges.
+#[derive(Debug, Clone, Copy)]
+struct P256VerifyWithGas(u64);
+
+impl StatefulPrecompile for P256VerifyWithGas {
+    fn call(&self, bytes: &Bytes, gas_limit: u64, _env: &Env) -> PrecompileResult {
+        if self.0 > gas_limit {
+            return Err(PrecompileError::OutOfGas.into());
+        }
+        // the standard precompile checks the gas limit against the upstream cost
+        let output = p256_verify(bytes, u64::MAX)?;
+        Ok(PrecompileOutput::new(self.0, output.bytes))
+    }
+}
+
+/// A sink receiving the inputs of precompile calls, see
+/// [`TraverseEvmConfig::with_p256_verify_capture`].
+#[derive(Clone)]
+pub struct PrecompileInputSink(Arc<dyn Fn(&Bytes) + Send + Sync>);
+
+impl PrecompileInputSink {
+    /// Creates a new sink calling the given function with the input of every precompile call.
+    pub fn new(sink: impl Fn(&Bytes) + Send + Sync + 'static) -> Self {
+        Self(Arc::new(sink))
+    }
+}
+
+impl fmt::Debug for PrecompileInputSink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PrecompileInputSink").finish_non_exhaustive()
+    }
+}
+
+/// A precompile that records its inputs in a [`PrecompileInputSink`] before calling the wrapped
+/// precompile.
+#[derive(Debug)]
+struct CapturedPrecompile {
+    inner: Precompile,
+    sink: PrecompileInputSink,
+}
+
+impl StatefulPrecompile for CapturedPrecompile {
+    fn call(&self, bytes: &Bytes, gas_limit: u64, env: &Env) -> PrecompileResult {
+        (self.sink.0)(bytes);
+        self.inner.call_ref(bytes, gas_limit, env)
+    }
+}
+
+/// The share of the priority fees of a [`BeneficiarySplit`] that credits all of them to the
+/// recipient, in basis points.
+pub const MAX_BENEFICIARY_SHARE_BPS: u16 = 10_000;
+
+/// Splits the priority fees credited to the block beneficiary with an additional recipient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BeneficiarySplit {
+    /// The address receiving a share of the priority fees.
+    pub recipient: Address,
+    /// The share of the priority fees credited to `recipient`, in basis points.
+    ///
+    /// This is at most [`MAX_BENEFICIARY_SHARE_BPS`].
+    pub share_bps: u16,
+}
+
+/// The effective configuration of a [`TraverseEvmConfig`] at a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvmConfigInfo {
+    /// The number of the block.
+    pub block_number: u64,
+    /// The revm spec id resolved for the block.
+    pub spec_id: String,
+    /// The addresses of the precompiles active in the block, in ascending order.
+    pub precompiles: Vec<Address>,
+    /// The bytecode analysis of the EVM.
+    pub analysis_kind: String,
+    /// The gas limit of system calls.
+    pub system_call_gas_limit: u64,
+    /// The split of the beneficiary reward, if any.
+    pub beneficiary_split: Option<BeneficiarySplit>,
+    /// The opcodes that halt execution instead of being executed.
+    pub disabled_opcodes: Vec<u8>,
+    /// The precompiles removed from the default set.
+    pub removed_precompiles: Vec<Address>,
+    /// The overridden gas cost of the P256 verify precompiles, if any.
+    pub p256_verify_gas: Option<u64>,
+    /// The fork activating the P256 verify precompiles, if any.
+    pub p256_verify_fork: Option<String>,
+    /// Whether the EVM is configured with the Optimism handler and environment.
+    pub is_optimism: bool,
+    /// The coinbase replacing the beneficiary of the header, if any.
+    pub coinbase: Option<Address>,
+    /// Whether the base fee check is disabled for all calls.
+    pub disable_base_fee: bool,
+}
+
 /// Custom EVM configuration
 #[derive(Debug, Clone)]
 pub struct TraverseEvmConfig {
     chain_spec: Arc<OpChainSpec>,
+    /// An optional split of the beneficiary reward.
+    beneficiary_split: Option<BeneficiarySplit>,
+    /// Opcodes that halt execution instead of being executed.
+    disabled_opcodes: Vec<u8>,
+    /// Precompiles that are removed from the default set, unless the active fork requires them.
+    removed_precompiles: Vec<Address>,
+    /// An optional gas cost of the P256 verify precompiles, replacing the upstream cost.
+    p256_verify_gas: Option<u64>,
+    /// The fork activating the P256 verify precompiles.
+    ///
+    /// If this is not set, the precompiles are active in every block.
+    p256_verify_fork: Option<OpHardfork>,
+    /// An optional sink receiving the inputs of the P256 verify precompiles.
+    p256_verify_capture: Option<PrecompileInputSink>,
+    /// Whether the EVM is configured with the Optimism handler and environment.
+    is_optimism: bool,
+    /// An optional coinbase of the block environment, replacing the beneficiary of the header.
+    coinbase: Option<Address>,
+    /// Whether the base fee check, and the related checks relaxed by `eth_call`, are disabled
+    /// for all calls.
+    disable_base_fee: bool,
+    /// An optional spec id of every block, replacing the spec of the fork schedule.
+    spec_id_override: Option<SpecId>,
+    /// The spec ids resolved for blocks, keyed by the timestamp and number of the block.
+    ///
+    /// This is shared by the clones of the configuration.
+    spec_ids: Arc<RwLock<HashMap<(u64, u64), SpecId>>>,
 }
 
 impl TraverseEvmConfig {
     /// Creates a new Traverse EVM configuration with the given chain spec.
-    pub const fn new(chain_spec: Arc<OpChainSpec>) -> Self {
-        Self { chain_spec }
+    pub fn new(chain_spec: Arc<OpChainSpec>) -> Self {
+        Self {
+            chain_spec,
+            beneficiary_split: None,
+            disabled_opcodes: Vec::new(),
+            removed_precompiles: Vec::new(),
+            p256_verify_gas: None,
+            p256_verify_fork: None,
+            p256_verify_capture: None,
+            is_optimism: true,
+            coinbase: None,
+            disable_base_fee: false,
+            spec_id_override: None,
+            spec_ids: Default::default(),
+        }
     }
 
-    fn precompiles() -> impl Iterator<Item = PrecompileWithAddress> {
-        [P256VERIFY, REVM_P256VERIFY].into_iter()
+    /// Configures whether the EVM uses the Optimism handler and environment.
+    ///
+    /// This is enabled by default. Disabling it is intended for replaying non-Optimism chains and
+    /// testing Ethereum semantics.
+    pub const fn with_optimism(mut self, is_optimism: bool) -> Self {
+        self.is_optimism = is_optimism;
+        self
     }
 
-    /// Sets the precompiles to the EVM handler
+    /// Overrides the coinbase of block environments filled from a header.
     ///
-    /// This will be invoked when the EVM is created via [`ConfigureEvm::evm`] or
-    /// [`ConfigureEvm::evm_with_inspector`]
+    /// This is intended for simulations, e.g. to model a different fee recipient in `eth_call`.
     ///
-    /// This will use the default mainnet precompiles and add additional precompiles.
-    fn set_precompiles<EXT, DB>(handler: &mut EvmHandler<'_, EXT, DB>)
-    where
-        DB: Database,
-    {
-        // first we need the evm spec id, which determines the precompiles
-        let spec_id = handler.cfg.spec_id;
+    /// **This is not for nodes following the chain**: the environments filled from a header are
+    /// also used to execute canonical blocks, so a node configured with this option credits the
+    /// fees of every block to the wrong account, and computes state roots that differ from the
+    /// rest of the network. Only the environments of blocks built by this node, see
+    /// [`ConfigureEvmEnv::next_cfg_and_block_env`], keep the suggested fee recipient.
+    pub const fn with_coinbase(mut self, coinbase: Address) -> Self {
+        self.coinbase = Some(coinbase);
+        self
+    }
 
-        // install the precompiles
-        handler.pre_execution.load_precompiles = Arc::new(move || {
-            let mut loaded_precompiles: ContextPrecompiles<DB> =
-                ContextPrecompiles::new(PrecompileSpecId::from_spec_id(spec_id));
+    /// Disables the base fee check for all calls of environments filled from a header.
+    ///
+    /// Along with the base fee check, the rejection of transactions from senders with deployed
+    /// code ([EIP-3607]) is disabled, like for `eth_call`. This is disabled by default, and is
+    /// intended for simulations and the replay of historical blocks with relaxed checks.
+    ///
+    /// **This is not for nodes following the chain**: the environments filled from a header are
+    /// also used to execute canonical blocks, so a node configured with this option accepts blocks
+    /// that the rest of the network rejects. Only the environments of blocks built by this node,
+    /// see [`ConfigureEvmEnv::next_cfg_and_block_env`], keep the checks.
+    ///
+    /// [EIP-3607]: https://eips.ethereum.org/EIPS/eip-3607
+    pub const fn with_disable_base_fee(mut self, disable_base_fee: bool) -> Self {
+        self.disable_base_fee = disable_base_fee;
+        self
+    }
 
-            loaded_precompiles.extend(Self::precompiles());
+    /// Forces the given spec id for every block, regardless of the fork schedule of the chain spec.
+    ///
+    /// **This is for protocol testing only**, e.g. to test the behavior of a fork that is not yet
+    /// scheduled. The environments of replayed and new blocks use the spec, so a node configured
+    /// with an override executes blocks differently than the rest of the network and will diverge
+    /// from it.
+    pub const fn with_spec_id_override(mut self, spec_id: SpecId) -> Self {
+        self.spec_id_override = Some(spec_id);
+        self
+    }
 
-            loaded_precompiles
-        });
+    /// Returns the handler configuration of EVMs built by this configuration.
+    ///
+    /// The spec id is replaced by the spec of the environment the EVM is used with.
+    const fn handler_cfg(&self) -> HandlerCfg {
+        HandlerCfg { spec_id: SpecId::LATEST, is_optimism: self.is_optimism }
     }
-}
 
-impl ConfigureEvmEnv for TraverseEvmConfig {
-    type Header = Header;
-    type Transaction = OpTransactionSigned;
-    type Error = EIP1559ParamError;
+    /// Splits the priority fees credited to the block beneficiary with another recipient.
+    ///
+    /// # Panics
+    ///
+    /// If the share of the split exceeds [`MAX_BENEFICIARY_SHARE_BPS`], since the recipient would
+    /// be credited more than the fees that were paid.
+    pub const fn with_beneficiary_split(mut self, split: BeneficiarySplit) -> Self {
+        assert!(
+            split.share_bps <= MAX_BENEFICIARY_SHARE_BPS,
+            "beneficiary share exceeds 10_000 basis points"
+        );
+        self.beneficiary_split = Some(split);
+        self
+    }
 
-    fn fill_tx_env(&self, tx_env: &mut TxEnv, transaction: &OpTransactionSigned, sender: Address) {
-        transaction.fill_tx_env(tx_env, sender);
+    /// Disables the given opcodes.
+    ///
+    /// Executing a disabled opcode halts execution with [`InstructionResult::NotActivated`].
+    pub fn with_disabled_opcodes(mut self, opcodes: impl IntoIterator<Item = u8>) -> Self {
+        self.disabled_opcodes.extend(opcodes);
+        self
     }
 
-    fn fill_tx_env_system_contract_call(
+    /// Removes the given precompiles from the default set, e.g. to disable RIPEMD-160 on a
+    /// restricted chain.
+    ///
+    /// Precompiles that the active fork requires are never removed, see
+    /// [`required_precompiles`].
+    pub fn with_removed_precompiles(
+        mut self,
+        addresses: impl IntoIterator<Item = Address>,
+    ) -> Self {
+        self.removed_precompiles.extend(addresses);
+        self
+    }
+
+    /// Overrides the gas cost of the P256 verify precompiles.
+    ///
+    /// This is intended for measuring the impact of cheaper or more expensive P256 verification.
+    pub const fn with_p256_verify_gas(mut self, gas: u64) -> Self {
+        self.p256_verify_gas = Some(gas);
+        self
+    }
+
+    /// Only activates the P256 verify precompiles once the given fork is active.
+    ///
+    /// The activation follows the schedule of the fork in the chain spec. Forks without a revm
+    /// spec id in the pinned revm version, e.g. Isthmus, never activate the precompiles, see
+    /// [`op_hardfork_spec_id`].
+    pub const fn with_p256_verify_fork(mut self, fork: OpHardfork) -> Self {
+        self.p256_verify_fork = Some(fork);
+        self
+    }
+
+    /// Records the input of every call to the P256 verify precompiles in the given sink, before
+    /// the call is executed.
+    ///
+    /// This is intended for building a corpus for fuzzing, e.g. by replaying blocks. The sink is
+    /// called from the EVM, so it should not block.
+    pub fn with_p256_verify_capture(mut self, sink: PrecompileInputSink) -> Self {
+        self.p256_verify_capture = Some(sink);
+        self
+    }
+
+    /// Returns the base fee of the block following `parent` with the given timestamp.
+    ///
+    /// This uses the base fee params of the chain spec that are active at the timestamp of the
+    /// new block. The params that Holocene reads from the extra data of the parent are not
+    /// considered.
+    pub fn next_block_base_fee(&self, parent: &Header, timestamp: u64) -> u64 {
+        parent
+            .next_block_base_fee(self.chain_spec.base_fee_params_at_timestamp(timestamp))
+            .unwrap_or_default()
+    }
+
+    /// Returns the revm spec id of the block of the given header.
+    ///
+    /// The spec id is resolved with [`revm_spec_by_header`] on first use, and cached afterwards.
+    /// If a spec id override is set, it is returned instead, see
+    /// [`TraverseEvmConfig::with_spec_id_override`].
+    pub fn spec_id(&self, header: &Header) -> SpecId {
+        if let Some(spec_id) = self.spec_id_override {
+            return spec_id;
+        }
+
+        let key = (header.timestamp, header.number);
+        if let Some(spec_id) = self.spec_ids.read().get(&key) {
+            return *spec_id;
+        }
+
+        let spec_id = revm_spec_by_header(&self.chain_spec, header);
+        let mut spec_ids = self.spec_ids.write();
+        if spec_ids.len() >= SPEC_ID_CACHE_SIZE {
+            spec_ids.clear();
+        }
+        spec_ids.insert(key, spec_id);
+        spec_id
+    }
+
+    /// Builds the precompiles of the given spec ahead of time, so that the first EVM created with
+    /// the spec borrows them from [`PRECOMPILES`] instead of building them.
+    pub fn warm_up(&self, spec_id: SpecId) {
+        Self::cached_precompiles(spec_id, self.p256_verify_fork);
+    }
+
+    /// Returns the effective configuration at the block of the given header.
+    pub fn info(&self, header: &Header) -> EvmConfigInfo {
+        let spec_id = self.spec_id(header);
+        let removed =
+            removable_precompiles(spec_id, self.p256_verify_fork, &self.removed_precompiles)
+                .collect::<Vec<_>>();
+        let mut precompiles = Self::cached_precompiles(spec_id, self.p256_verify_fork)
+            .addresses()
+            .filter(|address| !removed.contains(address))
+            .copied()
+            .collect::<Vec<_>>();
+        precompiles.sort_unstable();
+
+        EvmConfigInfo {
+            block_number: header.number,
+            spec_id: format!("{spec_id:?}"),
+            precompiles,
+            analysis_kind: format!("{ANALYSIS_KIND:?}"),
+            system_call_gas_limit: SYSTEM_CALL_GAS_LIMIT,
+            beneficiary_split: self.beneficiary_split,
+            disabled_opcodes: self.disabled_opcodes.clone(),
+            removed_precompiles: removed,
+            p256_verify_gas: self.p256_verify_gas,
+            p256_verify_fork: self.p256_verify_fork.map(|fork| fork.to_string()),
+            is_optimism: self.is_optimism,
+            coinbase: self.coinbase,
+            disable_base_fee: self.disable_base_fee,
+        }
+    }
+
+    /// Fills the transaction environment for a system contract call with the given
+    /// [`OptimismFields`].
+    ///
+    /// This is equivalent to [`ConfigureEvmEnv::fill_tx_env_system_contract_call`], which uses
+    /// the fields of the EIP-4788 system call, see [`system_call_optimism_fields`].
+    pub fn fill_tx_env_system_call_with_optimism_fields(
         &self,
         env: &mut Env,
         caller: Address,
         contract: Address,
         data: Bytes,
+        optimism: OptimismFields,
     ) {
         env.tx = TxEnv {
             caller,
             transact_to: TxKind::Call(contract),
             // Explicitly set nonce to None so revm does not do any nonce checks
             nonce: None,
-            gas_limit: 30_000_000,
+            gas_limit: SYSTEM_CALL_GAS_LIMIT,
             value: U256::ZERO,
             data,
             // Setting the gas price to zero enforces that no value is transferred as part of the
@@ -122,14 +451,7 @@ impl ConfigureEvmEnv for TraverseEvmConfig {
             blob_hashes: Vec::new(),
             max_fee_per_blob_gas: None,
             authorization_list: None,
-            optimism: OptimismFields {
-                source_hash: None,
-                mint: None,
-                is_system_transaction: Some(false),
-                // The L1 fee is not charged for the EIP-4788 transaction, submit zero bytes for the
-                // enveloped tx size.
-                enveloped_tx: Some(Bytes::default()),
-            },
+            optimism,
         };
 
         // ensure the block gas limit is >= the tx
@@ -139,19 +461,397 @@ impl ConfigureEvmEnv for TraverseEvmConfig {
         env.block.basefee = U256::ZERO;
     }
 
+    /// Fills the environment for a read-only call with the given gas limit, e.g. to serve
+    /// `eth_call` with a gas cap distinct from the block gas limit.
+    ///
+    /// Like a system contract call, the call is free and the nonce is not set, so neither the
+    /// balance nor the nonce of the caller is checked. The block gas limit is raised to the gas
+    /// limit of the call if it is lower.
+    pub fn fill_tx_env_read_only_call(
+        &self,
+        env: &mut Env,
+        caller: Address,
+        transact_to: TxKind,
+        data: Bytes,
+        gas_limit: u64,
+    ) {
+        env.tx = TxEnv {
+            caller,
+            transact_to,
+            nonce: None,
+            gas_limit,
+            value: U256::ZERO,
+            data,
+            gas_price: U256::ZERO,
+            chain_id: None,
+            gas_priority_fee: None,
+            access_list: Vec::new(),
+            blob_hashes: Vec::new(),
+            max_fee_per_blob_gas: None,
+            authorization_list: None,
+            // the L1 fee is not charged for read-only calls either
+            optimism: system_call_optimism_fields(),
+        };
+
+        env.block.gas_limit = env.block.gas_limit.max(U256::from(gas_limit));
+        env.block.basefee = U256::ZERO;
+    }
+
+    fn precompiles() -> impl Iterator<Item = PrecompileWithAddress> {
+        [P256VERIFY, REVM_P256VERIFY].into_iter()
+    }
+
+    /// Returns the additional precompiles that are active in the given spec.
+    ///
+    /// The precompiles are active from the spec of the given activation fork, if any.
+    fn active_precompiles(
+        spec_id: SpecId,
+        activation: Option<OpHardfork>,
+    ) -> impl Iterator<Item = PrecompileWithAddress> {
+        let active = Self::precompiles_active(spec_id, activation);
+        Self::precompiles().filter(move |_| active)
+    }
+
+    /// Returns whether the additional precompiles are active in the given spec.
+    fn precompiles_active(spec_id: SpecId, activation: Option<OpHardfork>) -> bool {
+        activation.is_none_or(|fork| {
+            op_hardfork_spec_id(fork).is_some_and(|fork| SpecId::enabled(spec_id, fork))
+        })
+    }
+
+    /// Returns the precompiles of the given spec, including the additional precompiles if they
+    /// are active.
+    ///
+    /// The precompiles are built on first use, and cached in [`PRECOMPILES`] afterwards.
+    fn cached_precompiles(spec_id: SpecId, activation: Option<OpHardfork>) -> &'static Precompiles {
+        let active = Self::precompiles_active(spec_id, activation);
+        let key = (PrecompileSpecId::from_spec_id(spec_id), active);
+        if let Some(precompiles) = PRECOMPILES.read().get(&key) {
+            return precompiles;
+        }
+
+        PRECOMPILES.write().entry(key).or_insert_with(|| {
+            let mut precompiles = Precompiles::new(key.0).clone();
+            precompiles.extend(Self::active_precompiles(spec_id, activation));
+            Box::leak(Box::new(precompiles))
+        })
+    }
+
+    /// Sets the precompiles to the EVM handler
+    ///
+    /// This will be invoked when the EVM is created via [`ConfigureEvm::evm`] or
+    /// [`ConfigureEvm::evm_with_inspector`]
+    ///
+    /// This will use the default mainnet precompiles and add additional precompiles, only once the
+    /// given fork is active if any.
+    fn set_precompiles_from<EXT, DB>(
+        handler: &mut EvmHandler<'_, EXT, DB>,
+        activation: Option<OpHardfork>,
+    ) where
+        DB: Database,
+    {
+        // the evm spec id determines the precompiles, which are only built once per spec
+        let precompiles = Self::cached_precompiles(handler.cfg.spec_id, activation);
+
+        // install the precompiles
+        handler.pre_execution.load_precompiles =
+            Arc::new(move || ContextPrecompiles::from_static_precompiles(precompiles));
+    }
+
+    /// Wraps the precompiles of the EVM handler to charge the given gas for P256 verification.
+    ///
+    /// This must be registered after [`Self::set_precompiles_from`]. Only the P256 verify
+    /// precompiles that are loaded are re-priced, so that they are not callable before their
+    /// activation.
+    fn set_p256_verify_gas<'a, EXT, DB>(handler: &mut EvmHandler<'a, EXT, DB>, gas: u64)
+    where
+        EXT: 'a,
+        DB: Database + 'a,
+    {
+        let load_precompiles = handler.pre_execution.load_precompiles.clone();
+        handler.pre_execution.load_precompiles = Arc::new(move || {
+            let mut loaded_precompiles = load_precompiles();
+
+            let repriced = Precompile::Stateful(Arc::new(P256VerifyWithGas(gas)));
+            let precompiles = loaded_precompiles.to_mut();
+            for PrecompileWithAddress(address, _) in Self::precompiles() {
+                if let Some(precompile) = precompiles.get_mut(&address) {
+                    *precompile = ContextPrecompile::Ordinary(repriced.clone());
+                }
+            }
+
+            loaded_precompiles
+        });
+    }
+
+    /// Removes the given precompiles from the precompiles of the EVM handler, except for the
+    /// precompiles required by the spec of the handler.
+    ///
+    /// This must be registered after [`Self::set_precompiles_from`].
+    fn remove_precompiles<'a, EXT, DB>(
+        handler: &mut EvmHandler<'a, EXT, DB>,
+        activation: Option<OpHardfork>,
+        addresses: &[Address],
+    ) where
+        EXT: 'a,
+        DB: Database + 'a,
+    {
+        let removed =
+            removable_precompiles(handler.cfg.spec_id, activation, addresses).collect::<Vec<_>>();
+        let load_precompiles = handler.pre_execution.load_precompiles.clone();
+        handler.pre_execution.load_precompiles = Arc::new(move || {
+            let mut loaded_precompiles = load_precompiles();
+
+            let precompiles = loaded_precompiles.to_mut();
+            for address in &removed {
+                precompiles.remove(address);
+            }
+
+            loaded_precompiles
+        });
+    }
+
+    /// Wraps the P256 verify precompiles of the EVM handler to record their inputs in the given
+    /// sink.
+    ///
+    /// This must be registered after all other registers modifying the precompiles. Precompiles
+    /// that are not active are not added.
+    fn set_p256_verify_capture<'a, EXT, DB>(
+        handler: &mut EvmHandler<'a, EXT, DB>,
+        sink: PrecompileInputSink,
+    ) where
+        EXT: 'a,
+        DB: Database + 'a,
+    {
+        let load_precompiles = handler.pre_execution.load_precompiles.clone();
+        handler.pre_execution.load_precompiles = Arc::new(move || {
+            let mut loaded_precompiles = load_precompiles();
+
+            let precompiles = loaded_precompiles.to_mut();
+            for PrecompileWithAddress(address, _) in Self::precompiles() {
+                if let Some(ContextPrecompile::Ordinary(precompile)) = precompiles.get_mut(&address)
+                {
+                    let inner = precompile.clone();
+                    *precompile = Precompile::Stateful(Arc::new(CapturedPrecompile {
+                        inner,
+                        sink: sink.clone(),
+                    }));
+                }
+            }
+
+            loaded_precompiles
+        });
+    }
+
+    /// Wraps the beneficiary reward of the EVM handler to credit a share of the priority fees to
+    /// the recipient of the given [`BeneficiarySplit`].
+    ///
+    /// The default Optimism reward logic is executed first, after which the share is moved from
+    /// the block beneficiary to the recipient.
+    fn set_reward_beneficiary<'a, EXT, DB>(
+        handler: &mut EvmHandler<'a, EXT, DB>,
+        split: BeneficiarySplit,
+    ) where
+        EXT: 'a,
+        DB: Database + 'a,
+    {
+        let reward_beneficiary = handler.post_execution.reward_beneficiary.clone();
+        handler.post_execution.reward_beneficiary = Arc::new(move |context, gas| {
+            reward_beneficiary(context, gas)?;
+
+            // deposit transactions do not pay any priority fees
+            if context.evm.env.tx.optimism.source_hash.is_some() {
+                return Ok(());
+            }
+
+            let env = &context.evm.env;
+            let priority_fee = env.effective_gas_price().saturating_sub(env.block.basefee);
+            let fees = priority_fee * U256::from(gas.spent() - gas.refunded() as u64);
+            let share = fees * U256::from(split.share_bps) / U256::from(MAX_BENEFICIARY_SHARE_BPS);
+            if share.is_zero() {
+                return Ok(());
+            }
+
+            let coinbase = env.block.coinbase;
+            let coinbase_account = context
+                .evm
+                .inner
+                .journaled_state
+                .load_account(coinbase, &mut context.evm.inner.db)?;
+            coinbase_account.data.mark_touch();
+            coinbase_account.data.info.balance =
+                coinbase_account.data.info.balance.saturating_sub(share);
+
+            let recipient_account = context
+                .evm
+                .inner
+                .journaled_state
+                .load_account(split.recipient, &mut context.evm.inner.db)?;
+            recipient_account.data.mark_touch();
+            recipient_account.data.info.balance =
+                recipient_account.data.info.balance.saturating_add(share);
+
+            Ok(())
+        });
+    }
+
+    /// Replaces the given opcodes in the instruction table with an instruction that halts.
+    fn disable_opcodes<EXT, DB>(handler: &mut EvmHandler<'_, EXT, DB>, opcodes: &[u8])
+    where
+        DB: Database,
+    {
+        for opcode in opcodes {
+            handler.instruction_table.insert(*opcode, opcode_disabled);
+        }
+    }
+
+    /// Appends the Traverse handler registers to the given EVM builder.
+    fn append_handler_registers<'a, EXT, DB>(
+        &self,
+        builder: EvmBuilder<'a, HandlerStage, EXT, DB>,
+    ) -> EvmBuilder<'a, HandlerStage, EXT, DB>
+    where
+        EXT: 'a,
+        DB: Database + 'a,
+    {
+        // add additional precompiles
+        let activation = self.p256_verify_fork;
+        let mut builder = builder.append_handler_register_box(Box::new(move |handler| {
+            Self::set_precompiles_from(handler, activation)
+        }));
+
+        if let Some(gas) = self.p256_verify_gas {
+            builder = builder.append_handler_register_box(Box::new(move |handler| {
+                Self::set_p256_verify_gas(handler, gas)
+            }));
+        }
+
+        if !self.removed_precompiles.is_empty() {
+            let addresses = self.removed_precompiles.clone();
+            builder = builder.append_handler_register_box(Box::new(move |handler| {
+                Self::remove_precompiles(handler, activation, &addresses)
+            }));
+        }
+
+        if let Some(sink) = self.p256_verify_capture.clone() {
+            builder = builder.append_handler_register_box(Box::new(move |handler| {
+                Self::set_p256_verify_capture(handler, sink.clone())
+            }));
+        }
+
+        if let Some(split) = self.beneficiary_split {
+            builder = builder.append_handler_register_box(Box::new(move |handler| {
+                Self::set_reward_beneficiary(handler, split)
+            }));
+        }
+
+        if !self.disabled_opcodes.is_empty() {
+            let opcodes = self.disabled_opcodes.clone();
+            builder = builder.append_handler_register_box(Box::new(move |handler| {
+                Self::disable_opcodes(handler, &opcodes)
+            }));
+        }
+
+        builder
+    }
+}
+
+/// Returns the precompiles that the given spec requires, which are never removed from the
+/// default set.
+///
+/// These are the precompiles introduced by protocol upgrades that rely on them: the KZG point
+/// evaluation precompile of [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844) since Cancun, and
+/// the P256 verify precompile of RIP-7212 once the given P256 verify fork is active, see
+/// [`TraverseEvmConfig::with_p256_verify_fork`].
+pub fn required_precompiles(
+    spec_id: SpecId,
+    p256_verify_fork: Option<OpHardfork>,
+) -> impl Iterator<Item = Address> {
+    let point_evaluation = spec_id.is_enabled_in(SpecId::CANCUN).then_some(u64_to_address(0x0a));
+    let p256_verify = TraverseEvmConfig::precompiles_active(spec_id, p256_verify_fork)
+        .then_some(REVM_P256VERIFY.0);
+    point_evaluation.into_iter().chain(p256_verify)
+}
+
+/// Returns the given precompiles that can be removed in the given spec, i.e. those not required
+/// by the spec.
+fn removable_precompiles(
+    spec_id: SpecId,
+    p256_verify_fork: Option<OpHardfork>,
+    addresses: &[Address],
+) -> impl Iterator<Item = Address> + '_ {
+    addresses.iter().copied().filter(move |address| {
+        !required_precompiles(spec_id, p256_verify_fork).any(|required| required == *address)
+    })
+}
+
+/// The [`OptimismFields`] of the EIP-4788 system call.
+pub fn system_call_optimism_fields() -> OptimismFields {
+    OptimismFields {
+        source_hash: None,
+        mint: None,
+        is_system_transaction: Some(false),
+        // The L1 fee is not charged for the EIP-4788 transaction, submit zero bytes for the
+        // enveloped tx size.
+        enveloped_tx: Some(Bytes::default()),
+    }
+}
+
+/// An instruction that halts execution, used in place of disabled opcodes.
+fn opcode_disabled<H: Host + ?Sized>(interpreter: &mut Interpreter, _host: &mut H) {
+    interpreter.instruction_result = InstructionResult::NotActivated;
+}
+
+impl ConfigureEvmEnv for TraverseEvmConfig {
+    type Header = Header;
+    type Transaction = OpTransactionSigned;
+    type Error = EIP1559ParamError;
+
+    fn fill_tx_env(&self, tx_env: &mut TxEnv, transaction: &OpTransactionSigned, sender: Address) {
+        transaction.fill_tx_env(tx_env, sender);
+    }
+
+    fn fill_tx_env_system_contract_call(
+        &self,
+        env: &mut Env,
+        caller: Address,
+        contract: Address,
+        data: Bytes,
+    ) {
+        self.fill_tx_env_system_call_with_optimism_fields(
+            env,
+            caller,
+            contract,
+            data,
+            system_call_optimism_fields(),
+        );
+    }
+
     fn fill_cfg_env(&self, cfg_env: &mut CfgEnvWithHandlerCfg, header: &Header) {
-        let spec_id = revm_spec(&self.chain_spec, header);
+        let spec_id = self.spec_id(header);
 
         cfg_env.chain_id = self.chain_spec.chain().id();
-        cfg_env.perf_analyse_created_bytecodes = AnalysisKind::Analyse;
+        cfg_env.perf_analyse_created_bytecodes = ANALYSIS_KIND;
+        if self.disable_base_fee {
+            cfg_env.disable_base_fee = true;
+            cfg_env.disable_eip3607 = true;
+        }
 
         cfg_env.handler_cfg.spec_id = spec_id;
-        cfg_env.handler_cfg.is_optimism = true;
+        cfg_env.handler_cfg.is_optimism = self.is_optimism;
     }
 
+    /// Fills the block environment from the header.
+    ///
+    /// The base fee of the header is authoritative, and is never recomputed: it was validated when
+    /// the block was imported. Before Holocene, it equals
+    /// [`TraverseEvmConfig::next_block_base_fee`] of the parent, with the base fee params of the
+    /// chain spec at the timestamp of the block. From Holocene, the params are read from the extra
+    /// data of the parent, which is not available here. The base fee is only computed for blocks
+    /// that are not built yet, see [`ConfigureEvmEnv::next_cfg_and_block_env`].
     fn fill_block_env(&self, block_env: &mut BlockEnv, header: &Self::Header, after_merge: bool) {
         block_env.number = U256::from(header.number);
-        block_env.coinbase = header.beneficiary;
+        block_env.coinbase = self.coinbase.unwrap_or(header.beneficiary);
         block_env.timestamp = U256::from(header.timestamp);
         if after_merge {
             block_env.prevrandao = Some(header.mix_hash);
@@ -178,7 +878,7 @@ impl ConfigureEvmEnv for TraverseEvmConfig {
         let cfg_env = CfgEnv::default().with_chain_id(self.chain_spec.chain().id());
 
         // ensure we're not missing any timestamp based hardforks
-        let spec_id = revm_spec(&self.chain_spec, parent);
+        let spec_id = self.spec_id(parent);
 
         // if the parent block did not have excess blob gas (i.e. it was pre-cancun), but it is
         // cancun now, we need to set the excess blob gas to the default value
@@ -195,13 +895,7 @@ impl ConfigureEvmEnv for TraverseEvmConfig {
             prevrandao: Some(attributes.prev_randao),
             gas_limit: U256::from(parent.gas_limit),
             // calculate basefee based on parent block's gas usage
-            basefee: U256::from(
-                parent
-                    .next_block_base_fee(
-                        self.chain_spec.base_fee_params_at_timestamp(attributes.timestamp),
-                    )
-                    .unwrap_or_default(),
-            ),
+            basefee: U256::from(self.next_block_base_fee(parent, attributes.timestamp)),
             // calculate excess gas based on parent block's blob gas usage
             blob_excess_gas_and_price,
         };
@@ -209,7 +903,7 @@ impl ConfigureEvmEnv for TraverseEvmConfig {
         Ok((
             CfgEnvWithHandlerCfg {
                 cfg_env,
-                handler_cfg: HandlerCfg { spec_id, is_optimism: true },
+                handler_cfg: HandlerCfg { spec_id, is_optimism: self.is_optimism },
             },
             block_env,
         )
@@ -221,12 +915,10 @@ impl ConfigureEvm for TraverseEvmConfig {
     type DefaultExternalContext<'a> = ();
 
     fn evm<DB: Database>(&self, db: DB) -> Evm<'_, Self::DefaultExternalContext<'_>, DB> {
-        EvmBuilder::default()
-            .with_db(db)
-            .optimism()
-            // add additional precompiles
-            .append_handler_register(Self::set_precompiles)
-            .build()
+        self.append_handler_registers(
+            EvmBuilder::default().with_db(db).with_handler_cfg(self.handler_cfg()),
+        )
+        .build()
     }
 
     fn evm_with_inspector<DB, I>(&self, db: DB, inspector: I) -> Evm<'_, I, DB>
@@ -234,141 +926,535 @@ impl ConfigureEvm for TraverseEvmConfig {
         DB: Database,
         I: GetInspector<DB>,
     {
-        EvmBuilder::default()
-            .with_db(db)
-            .with_external_context(inspector)
-            .optimism()
-            // add additional precompiles
-            .append_handler_register(Self::set_precompiles)
-            .append_handler_register(inspector_handle_register)
-            .build()
+        self.append_handler_registers(
+            EvmBuilder::default()
+                .with_db(db)
+                .with_external_context(inspector)
+                .with_handler_cfg(self.handler_cfg()),
+        )
+        // the inspector must be registered last to observe the configured instructions
+        .append_handler_register(inspector_handle_register)
+        .build()
     }
 
     fn default_external_context<'a>(&self) -> Self::DefaultExternalContext<'a> {}
 }
 
-/// Determine the revm spec ID from the current block and reth chainspec.
-fn revm_spec(chain_spec: &ChainSpec, header: &Header) -> reth_revm::primitives::SpecId {
-    let timestamp = header.timestamp;
-    let number = header.number;
-    if chain_spec.fork(EthereumHardfork::Prague).active_at_timestamp_or_number(timestamp, number) {
-        reth_revm::primitives::OSAKA
-    } else if chain_spec.fork(OpHardfork::Granite).active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::GRANITE
-    } else if chain_spec.fork(OpHardfork::Fjord).active_at_timestamp_or_number(timestamp, number) {
-        reth_revm::primitives::FJORD
-    } else if chain_spec.fork(OpHardfork::Ecotone).active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::ECOTONE
-    } else if chain_spec.fork(OpHardfork::Canyon).active_at_timestamp_or_number(timestamp, number) {
-        reth_revm::primitives::CANYON
-    } else if chain_spec.fork(OpHardfork::Regolith).active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::REGOLITH
-    } else if chain_spec.fork(OpHardfork::Bedrock).active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::BEDROCK
-    } else if chain_spec
-        .fork(EthereumHardfork::Prague)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::PRAGUE
-    } else if chain_spec
-        .fork(EthereumHardfork::Cancun)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::CANCUN
-    } else if chain_spec
-        .fork(EthereumHardfork::Shanghai)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::SHANGHAI
-    } else if chain_spec
-        .fork(EthereumHardfork::Paris)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::MERGE
-    } else if chain_spec
-        .fork(EthereumHardfork::London)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::LONDON
-    } else if chain_spec
-        .fork(EthereumHardfork::Berlin)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::BERLIN
-    } else if chain_spec
-        .fork(EthereumHardfork::Istanbul)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::ISTANBUL
-    } else if chain_spec
-        .fork(EthereumHardfork::Petersburg)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::PETERSBURG
-    } else if chain_spec
-        .fork(EthereumHardfork::Byzantium)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::BYZANTIUM
-    } else if chain_spec
-        .fork(EthereumHardfork::SpuriousDragon)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::SPURIOUS_DRAGON
-    } else if chain_spec
-        .fork(EthereumHardfork::Tangerine)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::TANGERINE
-    } else if chain_spec
-        .fork(EthereumHardfork::Homestead)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::HOMESTEAD
-    } else if chain_spec
-        .fork(EthereumHardfork::Frontier)
-        .active_at_timestamp_or_number(timestamp, number)
-    {
-        reth_revm::primitives::FRONTIER
-    } else {
-        panic!(
-            "invalid hardfork chainspec: expected at least one hardfork, got {:?}",
-            chain_spec.hardforks
-        )
+/// Returns the revm spec ID of the given Optimism hardfork.
+///
+/// Returns `None` for Isthmus, since the pinned revm version does not define a spec ID for it.
+/// Unlike in [`revm_spec_by_header`], it does not resolve to [`OpHardfork::Holocene`], which
+/// would activate features of Isthmus at Holocene.
+const fn op_hardfork_spec_id(fork: OpHardfork) -> Option<SpecId> {
+    match fork {
+        OpHardfork::Bedrock => Some(SpecId::BEDROCK),
+        OpHardfork::Regolith => Some(SpecId::REGOLITH),
+        OpHardfork::Canyon => Some(SpecId::CANYON),
+        OpHardfork::Ecotone => Some(SpecId::ECOTONE),
+        OpHardfork::Fjord => Some(SpecId::FJORD),
+        OpHardfork::Granite => Some(SpecId::GRANITE),
+        OpHardfork::Holocene => Some(SpecId::HOLOCENE),
+        OpHardfork::Isthmus => None,
     }
 }
 
+/// Returns whether a fork with the given activation condition is active at the block of the given
+/// header.
+///
+/// Unlike [`ForkCondition::active_at_timestamp_or_number`], only the field the condition is defined
+/// over is checked: the number for block-based forks, the timestamp for timestamp-based forks, and
+/// the activation block for the merge.
+const fn active_at_header(condition: ForkCondition, header: &Header) -> bool {
+    match condition {
+        ForkCondition::Block(block) => header.number >= block,
+        ForkCondition::Timestamp(timestamp) => header.timestamp >= timestamp,
+        ForkCondition::TTD { activation_block_number, .. } => {
+            header.number >= activation_block_number
+        }
+        ForkCondition::Never => false,
+    }
+}
+
+/// Determine the revm spec ID of the block of the given header.
+///
+/// Each fork is checked only against the field of the header its activation condition is defined
+/// over, which avoids ambiguity at the boundaries of chains that mix block-based and
+/// timestamp-based forks.
+///
+/// Note: Isthmus is not mapped yet, since the pinned revm version does not define a spec ID for
+/// it. Blocks after Isthmus resolve to the latest known spec, [`OpHardfork::Holocene`].
+pub fn revm_spec_by_header(chain_spec: &ChainSpec, header: &Header) -> SpecId {
+    let forks = [
+        (chain_spec.fork(EthereumHardfork::Prague), SpecId::OSAKA),
+        (chain_spec.fork(OpHardfork::Holocene), SpecId::HOLOCENE),
+        (chain_spec.fork(OpHardfork::Granite), SpecId::GRANITE),
+        (chain_spec.fork(OpHardfork::Fjord), SpecId::FJORD),
+        (chain_spec.fork(OpHardfork::Ecotone), SpecId::ECOTONE),
+        (chain_spec.fork(OpHardfork::Canyon), SpecId::CANYON),
+        (chain_spec.fork(OpHardfork::Regolith), SpecId::REGOLITH),
+        (chain_spec.fork(OpHardfork::Bedrock), SpecId::BEDROCK),
+        (chain_spec.fork(EthereumHardfork::Cancun), SpecId::CANCUN),
+        (chain_spec.fork(EthereumHardfork::Shanghai), SpecId::SHANGHAI),
+        (chain_spec.fork(EthereumHardfork::Paris), SpecId::MERGE),
+        (chain_spec.fork(EthereumHardfork::London), SpecId::LONDON),
+        (chain_spec.fork(EthereumHardfork::Berlin), SpecId::BERLIN),
+        (chain_spec.fork(EthereumHardfork::Istanbul), SpecId::ISTANBUL),
+        (chain_spec.fork(EthereumHardfork::Petersburg), SpecId::PETERSBURG),
+        (chain_spec.fork(EthereumHardfork::Byzantium), SpecId::BYZANTIUM),
+        (chain_spec.fork(EthereumHardfork::SpuriousDragon), SpecId::SPURIOUS_DRAGON),
+        (chain_spec.fork(EthereumHardfork::Tangerine), SpecId::TANGERINE),
+        (chain_spec.fork(EthereumHardfork::Homestead), SpecId::HOMESTEAD),
+        (chain_spec.fork(EthereumHardfork::Frontier), SpecId::FRONTIER),
+    ];
+
+    forks
+        .into_iter()
+        .find_map(|(condition, spec_id)| active_at_header(condition, header).then_some(spec_id))
+        .unwrap_or_else(|| {
+            panic!(
+                "invalid hardfork chainspec: expected at least one hardfork, got {:?}",
+                chain_spec.hardforks
+            )
+        })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use reth_chainspec::{Chain, ChainSpecBuilder, EthereumHardfork};
+    use alloy_eips::calc_next_block_base_fee;
+    use alloy_primitives::B256;
+    use reth_chainspec::{
+        BaseFeeParams, BaseFeeParamsKind, Chain, ChainSpecBuilder, EthereumHardfork,
+    };
     use reth_primitives::ForkCondition;
-    use revm_primitives::{BlockEnv, CfgEnv, SpecId};
+    use reth_revm::db::{CacheDB, EmptyDB};
+    use revm_primitives::{
+        AccountInfo, BlockEnv, Bytecode, CfgEnv, ExecutionResult, HaltReason, SpecId,
+    };
+
+    fn test_chain_spec() -> Arc<OpChainSpec> {
+        Arc::new(OpChainSpec::new(
+            ChainSpecBuilder::default()
+                .chain(Chain::optimism_mainnet())
+                .genesis(Default::default())
+                .with_fork(EthereumHardfork::Frontier, ForkCondition::Block(0))
+                .build(),
+        ))
+    }
 
     #[test]
     fn test_fill_cfg_and_block_env() {
         let mut cfg_env = CfgEnvWithHandlerCfg::new_with_spec_id(CfgEnv::default(), SpecId::LATEST);
         let mut block_env = BlockEnv::default();
         let header = Header::default();
+        let chain_spec = test_chain_spec();
+
+        TraverseEvmConfig::new(chain_spec.clone()).fill_cfg_and_block_env(
+            &mut cfg_env,
+            &mut block_env,
+            &header,
+        );
+
+        assert_eq!(cfg_env.chain_id, chain_spec.chain().id());
+    }
+
+    #[test]
+    fn test_spec_id_override() {
+        let header = Header { number: 1, timestamp: 1, ..Default::default() };
+        let attributes = NextBlockEnvAttributes {
+            timestamp: 2,
+            suggested_fee_recipient: Address::ZERO,
+            prev_randao: B256::ZERO,
+        };
+        let evm_config = TraverseEvmConfig::new(test_chain_spec());
+        let computed = evm_config.spec_id(&header);
+        assert_ne!(computed, SpecId::OSAKA);
+
+        // the override replaces the spec of the fork schedule of replayed and new blocks
+        let evm_config = evm_config.with_spec_id_override(SpecId::OSAKA);
+        let mut cfg_env = CfgEnvWithHandlerCfg::new_with_spec_id(CfgEnv::default(), computed);
+        evm_config.fill_cfg_env(&mut cfg_env, &header);
+        assert_eq!(cfg_env.handler_cfg.spec_id, SpecId::OSAKA);
+
+        let next = evm_config.next_cfg_and_block_env(&header, attributes).unwrap();
+        assert_eq!(next.cfg_env_with_handler_cfg.handler_cfg.spec_id, SpecId::OSAKA);
+    }
+
+    #[test]
+    fn test_base_fee_of_replayed_and_next_block() {
+        // a custom chain with the optimism base fee params instead of the ethereum ones
+        let mut chain_spec = ChainSpecBuilder::default()
+            .chain(Chain::optimism_mainnet())
+            .genesis(Default::default())
+            .with_fork(EthereumHardfork::Frontier, ForkCondition::Block(0))
+            .build();
+        chain_spec.base_fee_params = BaseFeeParamsKind::Constant(BaseFeeParams::optimism());
+        let evm_config = TraverseEvmConfig::new(Arc::new(OpChainSpec::new(chain_spec)));
+        let parent = Header {
+            number: 1,
+            timestamp: 1,
+            gas_limit: 30_000_000,
+            gas_used: 20_000_000,
+            base_fee_per_gas: Some(1_000_000_000),
+            ..Default::default()
+        };
+        let expected = calc_next_block_base_fee(
+            parent.gas_used,
+            parent.gas_limit,
+            1_000_000_000,
+            BaseFeeParams::optimism(),
+        );
+        assert_ne!(
+            expected,
+            calc_next_block_base_fee(
+                parent.gas_used,
+                parent.gas_limit,
+                1_000_000_000,
+                BaseFeeParams::ethereum(),
+            )
+        );
+
+        // the next block, as simulated on top of the parent, uses the params of the chain spec
+        let attributes = NextBlockEnvAttributes {
+            timestamp: 2,
+            suggested_fee_recipient: Address::ZERO,
+            prev_randao: B256::ZERO,
+        };
+        let next = evm_config.next_cfg_and_block_env(&parent, attributes).unwrap();
+        assert_eq!(next.block_env.basefee, U256::from(expected));
+
+        // the same block, as replayed from its header, has the same base fee
+        let header = Header {
+            number: 2,
+            timestamp: 2,
+            base_fee_per_gas: Some(expected),
+            ..Default::default()
+        };
+        let mut block_env = BlockEnv::default();
+        evm_config.fill_block_env(&mut block_env, &header, true);
+        assert_eq!(block_env.basefee, next.block_env.basefee);
+
+        // the base fee of a replayed header is authoritative, and is not recomputed
+        let header = Header { base_fee_per_gas: Some(7), ..header };
+        evm_config.fill_block_env(&mut block_env, &header, true);
+        assert_eq!(block_env.basefee, U256::from(7));
+    }
+
+    #[test]
+    fn test_system_call_optimism_fields() {
+        let evm_config = TraverseEvmConfig::new(test_chain_spec());
+        let mut env = Env::default();
+
+        evm_config.fill_tx_env_system_contract_call(
+            &mut env,
+            Address::ZERO,
+            Address::with_last_byte(1),
+            Bytes::default(),
+        );
+        assert_eq!(env.tx.optimism, system_call_optimism_fields());
+
+        let optimism = OptimismFields {
+            source_hash: Some(B256::with_last_byte(1)),
+            mint: Some(1),
+            is_system_transaction: Some(true),
+            enveloped_tx: None,
+        };
+        evm_config.fill_tx_env_system_call_with_optimism_fields(
+            &mut env,
+            Address::ZERO,
+            Address::with_last_byte(1),
+            Bytes::default(),
+            optimism.clone(),
+        );
+        assert_eq!(env.tx.optimism, optimism);
+        assert_eq!(env.block.basefee, U256::ZERO);
+    }
+
+    #[test]
+    fn test_read_only_call_env() {
+        let evm_config = TraverseEvmConfig::new(test_chain_spec());
+        let mut env = Env::default();
+        env.block.gas_limit = U256::from(30_000_000);
+        env.block.basefee = U256::from(1_000_000_000);
+
+        let to = TxKind::Call(Address::with_last_byte(1));
+        evm_config.fill_tx_env_read_only_call(
+            &mut env,
+            Address::with_last_byte(2),
+            to,
+            Bytes::from_static(&[1]),
+            100_000_000,
+        );
+        assert_eq!(env.tx.caller, Address::with_last_byte(2));
+        assert_eq!(env.tx.transact_to, to);
+        assert_eq!(env.tx.gas_limit, 100_000_000);
+        // the balance and nonce checks are disabled
+        assert_eq!(env.tx.nonce, None);
+        assert_eq!(env.tx.gas_price, U256::ZERO);
+        assert_eq!(env.tx.value, U256::ZERO);
+        assert_eq!(env.block.basefee, U256::ZERO);
+        // the block gas limit fits the call
+        assert_eq!(env.block.gas_limit, U256::from(100_000_000));
+
+        // a lower cap keeps the block gas limit
+        env.block.gas_limit = U256::from(30_000_000);
+        evm_config.fill_tx_env_read_only_call(&mut env, Address::ZERO, to, Bytes::new(), 50_000);
+        assert_eq!(env.tx.gas_limit, 50_000);
+        assert_eq!(env.block.gas_limit, U256::from(30_000_000));
+    }
+
+    #[test]
+    fn test_revm_spec_op_forks() {
+        let spec_with = |fork: OpHardfork| {
+            ChainSpecBuilder::default()
+                .chain(Chain::optimism_mainnet())
+                .genesis(Default::default())
+                .with_fork(EthereumHardfork::Frontier, ForkCondition::Block(0))
+                .with_fork(fork, ForkCondition::Timestamp(0))
+                .build()
+        };
+
+        assert_eq!(
+            revm_spec_by_header(&spec_with(OpHardfork::Holocene), &Header::default()),
+            SpecId::HOLOCENE
+        );
+        assert_eq!(
+            revm_spec_by_header(&spec_with(OpHardfork::Granite), &Header::default()),
+            SpecId::GRANITE
+        );
+
+        // holocene takes precedence over granite
+        let chain_spec = ChainSpecBuilder::default()
+            .chain(Chain::optimism_mainnet())
+            .genesis(Default::default())
+            .with_fork(OpHardfork::Granite, ForkCondition::Timestamp(0))
+            .with_fork(OpHardfork::Holocene, ForkCondition::Timestamp(10))
+            .build();
+        assert_eq!(revm_spec_by_header(&chain_spec, &Header::default()), SpecId::GRANITE);
+        assert_eq!(
+            revm_spec_by_header(&chain_spec, &Header { timestamp: 10, ..Default::default() }),
+            SpecId::HOLOCENE
+        );
+    }
+
+    #[test]
+    fn test_revm_spec_by_header_boundaries() {
+        let chain_spec = ChainSpecBuilder::default()
+            .chain(Chain::optimism_mainnet())
+            .genesis(Default::default())
+            .with_fork(EthereumHardfork::Frontier, ForkCondition::Block(0))
+            .with_fork(EthereumHardfork::London, ForkCondition::Block(0))
+            .with_fork(
+                EthereumHardfork::Paris,
+                ForkCondition::TTD {
+                    fork_block: None,
+                    total_difficulty: U256::ZERO,
+                    activation_block_number: 5,
+                },
+            )
+            .with_fork(OpHardfork::Bedrock, ForkCondition::Block(10))
+            .with_fork(OpHardfork::Regolith, ForkCondition::Timestamp(50))
+            .with_fork(OpHardfork::Canyon, ForkCondition::Timestamp(100))
+            .build();
+        let spec_at = |number, timestamp| {
+            revm_spec_by_header(&chain_spec, &Header { number, timestamp, ..Default::default() })
+        };
+
+        // the merge activates at its activation block
+        assert_eq!(spec_at(4, 0), SpecId::LONDON);
+        assert_eq!(spec_at(5, 0), SpecId::MERGE);
+
+        // block-based forks activate at their block
+        assert_eq!(spec_at(9, 0), SpecId::MERGE);
+        assert_eq!(spec_at(10, 0), SpecId::BEDROCK);
+
+        // timestamp-based forks activate at their timestamp
+        assert_eq!(spec_at(10, 49), SpecId::BEDROCK);
+        assert_eq!(spec_at(10, 50), SpecId::REGOLITH);
+        assert_eq!(spec_at(11, 99), SpecId::REGOLITH);
+        assert_eq!(spec_at(11, 100), SpecId::CANYON);
+    }
+
+    #[test]
+    fn test_spec_id_cache() {
+        let chain_spec = Arc::new(OpChainSpec::new(
+            ChainSpecBuilder::default()
+                .chain(Chain::optimism_mainnet())
+                .genesis(Default::default())
+                .with_fork(OpHardfork::Granite, ForkCondition::Timestamp(0))
+                .with_fork(OpHardfork::Holocene, ForkCondition::Timestamp(10))
+                .build(),
+        ));
+        let config = TraverseEvmConfig::new(chain_spec.clone());
+        let clone = config.clone();
+
+        let headers = (0..20u64)
+            .map(|timestamp| Header { timestamp, number: timestamp, ..Default::default() })
+            .collect::<Vec<_>>();
+        for _ in 0..2 {
+            for header in &headers {
+                // cache hits return the same spec as recomputation, and are shared by clones
+                assert_eq!(config.spec_id(header), revm_spec_by_header(&chain_spec, header));
+                assert_eq!(clone.spec_id(header), revm_spec_by_header(&chain_spec, header));
+            }
+        }
+        assert_eq!(config.spec_ids.read().len(), headers.len());
+
+        // the cache is cleared once full
+        for number in 0..SPEC_ID_CACHE_SIZE as u64 {
+            config.spec_id(&Header { number, timestamp: 100, ..Default::default() });
+        }
+        assert!(config.spec_ids.read().len() <= SPEC_ID_CACHE_SIZE);
+        assert_eq!(config.spec_id(&headers[0]), SpecId::GRANITE);
+        assert_eq!(config.spec_id(&headers[10]), SpecId::HOLOCENE);
+    }
+
+    #[test]
+    fn test_p256verify_precompile_activation() {
         let chain_spec = Arc::new(OpChainSpec::new(
             ChainSpecBuilder::default()
                 .chain(Chain::optimism_mainnet())
                 .genesis(Default::default())
                 .with_fork(EthereumHardfork::Frontier, ForkCondition::Block(0))
+                .with_fork(OpHardfork::Bedrock, ForkCondition::Block(0))
+                .with_fork(OpHardfork::Ecotone, ForkCondition::Timestamp(0))
+                .with_fork(OpHardfork::Fjord, ForkCondition::Timestamp(100))
                 .build(),
         ));
+        let config =
+            TraverseEvmConfig::new(chain_spec.clone()).with_p256_verify_fork(OpHardfork::Fjord);
 
-        TraverseEvmConfig::new(chain_spec.clone()).fill_cfg_and_block_env(
-            &mut cfg_env,
-            &mut block_env,
-            &header,
-        );
+        let has_p256verify = |timestamp| {
+            let spec_id = revm_spec_by_header(
+                &chain_spec,
+                &Header { timestamp, number: 1, ..Default::default() },
+            );
+            let evm = config
+                .append_handler_registers(
+                    EvmBuilder::default().with_empty_db().optimism().with_spec_id(spec_id),
+                )
+                .build();
+            evm.handler.pre_execution().load_precompiles().contains(&u64_to_address(0x14))
+        };
 
-        assert_eq!(cfg_env.chain_id, chain_spec.chain().id());
+        assert!(!has_p256verify(99));
+        assert!(has_p256verify(100));
+    }
+
+    #[test]
+    fn test_p256verify_gas_override_before_activation() {
+        let chain_spec = Arc::new(OpChainSpec::new(
+            ChainSpecBuilder::default()
+                .chain(Chain::optimism_mainnet())
+                .genesis(Default::default())
+                .with_fork(EthereumHardfork::Frontier, ForkCondition::Block(0))
+                .with_fork(OpHardfork::Bedrock, ForkCondition::Block(0))
+                .with_fork(OpHardfork::Ecotone, ForkCondition::Timestamp(0))
+                .with_fork(OpHardfork::Fjord, ForkCondition::Timestamp(100))
+                .build(),
+        ));
+        let has_p256verify = |config: &TraverseEvmConfig, timestamp| {
+            let spec_id = revm_spec_by_header(
+                &chain_spec,
+                &Header { timestamp, number: 1, ..Default::default() },
+            );
+            let evm = config
+                .append_handler_registers(
+                    EvmBuilder::default().with_empty_db().optimism().with_spec_id(spec_id),
+                )
+                .build();
+            let precompiles = evm.handler.pre_execution().load_precompiles();
+            [0x14, 0x100].map(|address| precompiles.contains(&u64_to_address(address)))
+        };
+
+        // the gas override does not make the precompiles callable before their fork
+        let config = TraverseEvmConfig::new(chain_spec.clone())
+            .with_p256_verify_fork(OpHardfork::Fjord)
+            .with_p256_verify_gas(1_000);
+        assert_eq!(has_p256verify(&config, 99), [false, false]);
+        assert_eq!(has_p256verify(&config, 100), [true, true]);
+
+        // a fork without a spec id never activates the precompiles
+        let config = TraverseEvmConfig::new(chain_spec.clone())
+            .with_p256_verify_fork(OpHardfork::Isthmus)
+            .with_p256_verify_gas(1_000);
+        assert_eq!(has_p256verify(&config, 100), [false, false]);
+        assert_eq!(op_hardfork_spec_id(OpHardfork::Holocene), Some(SpecId::HOLOCENE));
+        assert_eq!(op_hardfork_spec_id(OpHardfork::Isthmus), None);
+    }
+
+    #[test]
+    fn test_coinbase_override() {
+        let header = Header { beneficiary: Address::with_last_byte(1), ..Default::default() };
+
+        let mut block_env = BlockEnv::default();
+        TraverseEvmConfig::new(test_chain_spec()).fill_block_env(&mut block_env, &header, true);
+        assert_eq!(block_env.coinbase, header.beneficiary);
+
+        let coinbase = Address::with_last_byte(2);
+        let mut block_env = BlockEnv::default();
+        let evm_config = TraverseEvmConfig::new(test_chain_spec()).with_coinbase(coinbase);
+        evm_config.fill_block_env(&mut block_env, &header, true);
+        assert_eq!(block_env.coinbase, coinbase);
+    }
+
+    #[test]
+    fn test_is_optimism() {
+        let op = TraverseEvmConfig::new(test_chain_spec());
+        let non_op = TraverseEvmConfig::new(test_chain_spec()).with_optimism(false);
+
+        assert!(op.evm(EmptyDB::default()).handler.cfg.is_optimism);
+        assert!(!non_op.evm(EmptyDB::default()).handler.cfg.is_optimism);
+        assert!(!non_op.evm_with_inspector(EmptyDB::default(), ()).handler.cfg.is_optimism);
+
+        let header = Header::default();
+        for (config, is_optimism) in [(op, true), (non_op, false)] {
+            let mut cfg_env =
+                CfgEnvWithHandlerCfg::new_with_spec_id(CfgEnv::default(), SpecId::LATEST);
+            config.fill_cfg_env(&mut cfg_env, &header);
+            assert_eq!(cfg_env.handler_cfg.is_optimism, is_optimism);
+
+            let attributes = NextBlockEnvAttributes {
+                timestamp: 1,
+                suggested_fee_recipient: Address::ZERO,
+                prev_randao: B256::ZERO,
+            };
+            let env = config.next_cfg_and_block_env(&header, attributes).unwrap();
+            assert_eq!(env.cfg_env_with_handler_cfg.handler_cfg.is_optimism, is_optimism);
+        }
+    }
+
+    #[test]
+    fn test_disable_base_fee() {
+        let header = Header::default();
+        let fill = |config: &TraverseEvmConfig| {
+            let mut cfg_env =
+                CfgEnvWithHandlerCfg::new_with_spec_id(CfgEnv::default(), SpecId::LATEST);
+            config.fill_cfg_env(&mut cfg_env, &header);
+            cfg_env
+        };
+
+        // the checks are enabled by default
+        let config = TraverseEvmConfig::new(test_chain_spec());
+        let cfg_env = fill(&config);
+        assert!(!cfg_env.disable_base_fee);
+        assert!(!cfg_env.disable_eip3607);
+        assert!(!config.info(&header).disable_base_fee);
+
+        let config = config.with_disable_base_fee(true);
+        let cfg_env = fill(&config);
+        assert!(cfg_env.disable_base_fee);
+        assert!(cfg_env.disable_eip3607);
+        assert!(config.info(&header).disable_base_fee);
+
+        // the environment of new blocks is not relaxed
+        let attributes = NextBlockEnvAttributes {
+            timestamp: 1,
+            suggested_fee_recipient: Address::ZERO,
+            prev_randao: B256::ZERO,
+        };
+        let env = config.next_cfg_and_block_env(&header, attributes).unwrap();
+        assert!(!env.cfg_env_with_handler_cfg.disable_base_fee);
     }
 
     #[test]
@@ -377,7 +1463,9 @@ mod tests {
             .with_empty_db()
             .optimism()
             // add additional precompiles
-            .append_handler_register(TraverseEvmConfig::set_precompiles)
+            .append_handler_register_box(Box::new(|handler| {
+                TraverseEvmConfig::set_precompiles_from(handler, None)
+            }))
             .build();
 
         // loading the precompiles from pre execution instead of the evm context directly, as they
@@ -386,4 +1474,274 @@ mod tests {
         assert!(precompiles.contains(&u64_to_address(0x14)));
         assert!(precompiles.contains(&u64_to_address(0x100)));
     }
+
+    #[test]
+    fn test_evm_config_info() {
+        let evm_config = TraverseEvmConfig::new(test_chain_spec())
+            .with_p256_verify_gas(1_000)
+            .with_disabled_opcodes([0x5c]);
+        let info = evm_config.info(&Header::default());
+
+        assert!(info.precompiles.contains(&u64_to_address(0x14)));
+        assert!(info.precompiles.contains(&u64_to_address(0x100)));
+        assert!(info.precompiles.is_sorted());
+        assert_eq!(info.system_call_gas_limit, SYSTEM_CALL_GAS_LIMIT);
+        assert_eq!(info.p256_verify_gas, Some(1_000));
+        assert_eq!(info.disabled_opcodes, vec![0x5c]);
+        assert!(info.is_optimism);
+        assert_eq!(info.coinbase, None);
+
+        let json = serde_json::to_value(&info).unwrap();
+        let precompiles = json["precompiles"].as_array().unwrap();
+        assert!(precompiles.contains(&serde_json::json!(u64_to_address(0x14))));
+        assert!(precompiles.contains(&serde_json::json!(u64_to_address(0x100))));
+        assert_eq!(json["isOptimism"], serde_json::json!(true));
+
+        let coinbase = Address::with_last_byte(1);
+        let info = evm_config.with_optimism(false).with_coinbase(coinbase).info(&Header::default());
+        assert!(!info.is_optimism);
+        assert_eq!(info.coinbase, Some(coinbase));
+    }
+
+    #[test]
+    fn test_removed_precompiles() {
+        let ripemd = u64_to_address(0x03);
+        let point_evaluation = u64_to_address(0x0a);
+        let evm_config = TraverseEvmConfig::new(test_chain_spec())
+            .with_removed_precompiles([ripemd, point_evaluation]);
+
+        let precompiles = |spec_id| {
+            let evm = evm_config
+                .append_handler_registers(
+                    EvmBuilder::default().with_empty_db().optimism().with_spec_id(spec_id),
+                )
+                .build();
+            evm.handler.pre_execution().load_precompiles().addresses().copied().collect::<Vec<_>>()
+        };
+
+        let holocene = precompiles(SpecId::HOLOCENE);
+        assert!(!holocene.contains(&ripemd));
+        assert!(holocene.contains(&u64_to_address(0x02)));
+        // the point evaluation precompile is required since cancun
+        assert!(holocene.contains(&point_evaluation));
+
+        // the point evaluation precompile does not exist before cancun
+        let shanghai = precompiles(SpecId::CANYON);
+        assert!(!shanghai.contains(&ripemd));
+        assert!(!shanghai.contains(&point_evaluation));
+        assert!(shanghai.contains(&u64_to_address(0x04)));
+
+        let info = evm_config.info(&Header::default());
+        assert!(!info.precompiles.contains(&ripemd));
+        assert!(info.precompiles.contains(&point_evaluation));
+        assert_eq!(info.removed_precompiles, vec![ripemd]);
+
+        // the P256 verify precompile is required once the configured fork is active
+        let p256_verify = u64_to_address(0x100);
+        let precompiles = |evm_config: &TraverseEvmConfig, spec_id| {
+            let evm = evm_config
+                .append_handler_registers(
+                    EvmBuilder::default().with_empty_db().optimism().with_spec_id(spec_id),
+                )
+                .build();
+            evm.handler.pre_execution().load_precompiles().addresses().copied().collect::<Vec<_>>()
+        };
+        let evm_config = TraverseEvmConfig::new(test_chain_spec())
+            .with_p256_verify_fork(OpHardfork::Ecotone)
+            .with_removed_precompiles([p256_verify]);
+        assert!(precompiles(&evm_config, SpecId::ECOTONE).contains(&p256_verify));
+        assert!(!precompiles(&evm_config, SpecId::CANYON).contains(&p256_verify));
+
+        // without a fork, the precompile is active, and required, in every block
+        let evm_config =
+            TraverseEvmConfig::new(test_chain_spec()).with_removed_precompiles([p256_verify]);
+        assert!(precompiles(&evm_config, SpecId::CANYON).contains(&p256_verify));
+        assert!(evm_config.info(&Header::default()).removed_precompiles.is_empty());
+    }
+
+    #[test]
+    fn test_cached_precompiles() {
+        let precompiles = TraverseEvmConfig::cached_precompiles(SpecId::HOLOCENE, None);
+        assert!(precompiles.contains(&u64_to_address(0x14)));
+        assert!(precompiles.contains(&u64_to_address(0x100)));
+
+        // the same set is shared across EVMs of the same spec
+        assert!(std::ptr::eq(
+            precompiles,
+            TraverseEvmConfig::cached_precompiles(SpecId::HOLOCENE, None)
+        ));
+
+        // the additional precompiles are not active before the activation fork
+        let precompiles =
+            TraverseEvmConfig::cached_precompiles(SpecId::ECOTONE, Some(OpHardfork::Fjord));
+        assert!(!precompiles.contains(&u64_to_address(0x14)));
+        assert!(!precompiles.contains(&u64_to_address(0x100)));
+    }
+
+    #[test]
+    fn test_warm_up() {
+        let config =
+            TraverseEvmConfig::new(test_chain_spec()).with_p256_verify_fork(OpHardfork::Granite);
+        let key = (PrecompileSpecId::from_spec_id(SpecId::CANCUN), false);
+
+        config.warm_up(SpecId::CANCUN);
+        let warm = *PRECOMPILES.read().get(&key).expect("precompiles are cached");
+        assert!(!warm.contains(&u64_to_address(P256VERIFY_ADDRESS)));
+
+        // EVMs of the spec borrow the warmed up precompiles
+        assert!(std::ptr::eq(
+            warm,
+            TraverseEvmConfig::cached_precompiles(SpecId::CANCUN, config.p256_verify_fork)
+        ));
+    }
+
+    #[test]
+    fn test_p256verify_gas_override() {
+        let caller = Address::with_last_byte(1);
+
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            caller,
+            AccountInfo { balance: U256::from(u64::MAX), ..Default::default() },
+        );
+
+        let mut evm = EvmBuilder::default()
+            .with_db(db)
+            .modify_tx_env(|tx| {
+                tx.caller = caller;
+                tx.transact_to = TxKind::Call(u64_to_address(P256VERIFY_ADDRESS));
+                tx.gas_limit = 100_000;
+                tx.optimism.enveloped_tx = Some(Bytes::default());
+            })
+            .optimism()
+            .append_handler_register_box(Box::new(|handler| {
+                TraverseEvmConfig::set_precompiles_from(handler, None)
+            }))
+            .append_handler_register_box(Box::new(|handler| {
+                TraverseEvmConfig::set_p256_verify_gas(handler, 1_000)
+            }))
+            .build();
+
+        // the precompile charges the overridden gas, even if the signature is invalid
+        let result = evm.transact().unwrap();
+        assert!(result.result.is_success());
+        assert_eq!(result.result.gas_used(), 21_000 + 1_000);
+    }
+
+    #[test]
+    fn test_beneficiary_split() {
+        let caller = Address::with_last_byte(1);
+        let coinbase = Address::with_last_byte(2);
+        let recipient = Address::with_last_byte(3);
+        let split = BeneficiarySplit { recipient, share_bps: 2_500 };
+
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            caller,
+            AccountInfo { balance: U256::from(u64::MAX), ..Default::default() },
+        );
+
+        let mut evm = EvmBuilder::default()
+            .with_db(db)
+            .modify_block_env(|block| block.coinbase = coinbase)
+            .modify_tx_env(|tx| {
+                tx.caller = caller;
+                tx.transact_to = TxKind::Call(Address::with_last_byte(4));
+                tx.gas_limit = 21_000;
+                tx.gas_price = U256::from(100);
+                tx.optimism.enveloped_tx = Some(Bytes::default());
+            })
+            .optimism()
+            .append_handler_register_box(Box::new(move |handler| {
+                TraverseEvmConfig::set_reward_beneficiary(handler, split)
+            }))
+            .build();
+
+        let result = evm.transact().unwrap();
+        assert!(result.result.is_success());
+
+        // base fee is zero, so the entire gas price is paid as priority fee
+        let fees = U256::from(21_000 * 100);
+        assert_eq!(result.state[&recipient].info.balance, fees / U256::from(4));
+        assert_eq!(result.state[&coinbase].info.balance, fees - fees / U256::from(4));
+    }
+
+    #[test]
+    fn test_beneficiary_split_share_bounds() {
+        let recipient = Address::with_last_byte(3);
+        let split = BeneficiarySplit { recipient, share_bps: MAX_BENEFICIARY_SHARE_BPS };
+        let config = TraverseEvmConfig::new(test_chain_spec()).with_beneficiary_split(split);
+        assert_eq!(config.beneficiary_split, Some(split));
+    }
+
+    #[test]
+    #[should_panic(expected = "beneficiary share exceeds 10_000 basis points")]
+    fn test_beneficiary_split_share_above_fees() {
+        let split = BeneficiarySplit {
+            recipient: Address::with_last_byte(3),
+            share_bps: MAX_BENEFICIARY_SHARE_BPS + 1,
+        };
+        let _ = TraverseEvmConfig::new(test_chain_spec()).with_beneficiary_split(split);
+    }
+
+    #[test]
+    fn test_disabled_opcode_halts() {
+        let caller = Address::with_last_byte(1);
+        let contract = Address::with_last_byte(2);
+
+        // PUSH0 SELFDESTRUCT
+        let code = Bytecode::new_raw(Bytes::from_static(&[0x5f, 0xff]));
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            contract,
+            AccountInfo { code_hash: code.hash_slow(), code: Some(code), ..Default::default() },
+        );
+
+        let evm_config = TraverseEvmConfig::new(test_chain_spec()).with_disabled_opcodes([0xff]);
+        let mut evm = evm_config.evm(db);
+        let tx = evm.tx_mut();
+        tx.caller = caller;
+        tx.transact_to = TxKind::Call(contract);
+        tx.gas_limit = 100_000;
+        tx.optimism.enveloped_tx = Some(Bytes::default());
+
+        let result = evm.transact().unwrap();
+        assert!(matches!(
+            result.result,
+            ExecutionResult::Halt { reason: HaltReason::NotActivated, .. }
+        ));
+    }
+
+    #[test]
+    fn test_p256verify_capture() {
+        let caller = Address::with_last_byte(1);
+        let input = Bytes::from_static(&[0xab; 160]);
+
+        let captured = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let sink = {
+            let captured = captured.clone();
+            PrecompileInputSink::new(move |input| captured.lock().push(input.clone()))
+        };
+
+        let mut db = CacheDB::new(EmptyDB::default());
+        db.insert_account_info(
+            caller,
+            AccountInfo { balance: U256::from(u64::MAX), ..Default::default() },
+        );
+
+        let evm_config = TraverseEvmConfig::new(test_chain_spec()).with_p256_verify_capture(sink);
+        let mut evm = evm_config.evm(db);
+        let tx = evm.tx_mut();
+        tx.caller = caller;
+        tx.transact_to = TxKind::Call(u64_to_address(P256VERIFY_ADDRESS));
+        tx.data = input.clone();
+        tx.gas_limit = 100_000;
+        tx.optimism.enveloped_tx = Some(Bytes::default());
+
+        // the invalid signature is still verified by the wrapped precompile
+        let result = evm.transact().unwrap();
+        assert!(result.result.is_success());
+        assert!(result.result.output().unwrap().is_empty());
+        assert_eq!(*captured.lock(), vec![input]);
+    }
 }
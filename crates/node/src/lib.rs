@@ -15,10 +15,16 @@
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 #![warn(unused_crate_dependencies)]
 
+// used in benchmarks
+#[cfg(test)]
+use criterion as _;
+
 pub mod broadcaster;
 pub mod chainspec;
 pub mod delayed_resolve;
 pub mod evm;
 pub mod forwarder;
 pub mod node;
+pub mod payload;
 pub mod rpc;
+pub mod status;
@@ -2,22 +2,41 @@
 //!
 //! The [`TraverseNode`] type implements the [`NodeTypes`] trait, and configures the engine types
 //! required for the optimism engine API.
+//!
+//! The state commitment of the node is [`MerklePatriciaTrie`] by default, but can be swapped for
+//! research into alternative commitment schemes, see [`TraverseNode::with_state_commitment`].
 
-use crate::evm::TraverseEvmConfig;
+use crate::{
+    delayed_resolve::{DelayedResolver, MAX_DELAY_INTO_SLOT},
+    evm::TraverseEvmConfig,
+    payload::ServiceTransactions,
+    rpc::{
+        EthApiExt, EthApiOverrideServer, TraverseEvmConfigApiServer, TraverseEvmConfigExt,
+        TraversePoolConfigApiServer, TraversePoolConfigExt, TraverseProofApiServer,
+        DEFAULT_MAX_PROOF_KEYS,
+    },
+    status::{SponsoredTxStatusApiServer, SponsoredTxStatusTracker},
+};
+use alloy_consensus::Header;
+use alloy_network::EthereumWallet;
+use futures::{Stream, StreamExt};
 use op_alloy_consensus::OpPooledTransaction;
+use reth_basic_payload_builder::{BasicPayloadJobGenerator, BasicPayloadJobGeneratorConfig};
+use reth_chain_state::{CanonStateNotification, CanonStateSubscriptions};
 use reth_evm::execute::BasicBlockExecutorProvider;
 use reth_network::{
     transactions::{TransactionPropagationMode, TransactionsManagerConfig},
     NetworkHandle, NetworkManager, PeersInfo,
 };
-use reth_network_types::ReputationChangeWeights;
-use reth_node_api::{FullNodeTypes, NodeTypesWithEngine, TxTy};
+use reth_network_types::{PeersConfig, ReputationChangeWeights};
+use reth_node_api::{FullNodeComponents, FullNodeTypes, NodePrimitives, NodeTypesWithEngine, TxTy};
 use reth_node_builder::{
     components::{
         ComponentsBuilder, ExecutorBuilder, NetworkBuilder, PayloadServiceBuilder,
         PoolBuilderConfigOverrides,
     },
-    BuilderContext, Node, NodeAdapter, NodeComponentsBuilder, NodeTypes,
+    rpc::{RethRpcAddOns, RpcContext},
+    BuilderContext, Node, NodeAdapter, NodeComponentsBuilder, NodeTypes, PayloadBuilderConfig,
 };
 use reth_optimism_chainspec::OpChainSpec;
 use reth_optimism_node::{
@@ -27,31 +46,123 @@ use reth_optimism_node::{
     },
     OpEngineTypes, OpExecutionStrategyFactory, OpNetworkPrimitives,
 };
-use reth_optimism_primitives::OpPrimitives;
-use reth_payload_builder::PayloadBuilderHandle;
+use reth_optimism_primitives::{OpPrimitives, OpTransactionSigned};
+use reth_optimism_rpc::OpEthApi;
+use reth_payload_builder::{
+    noop::NoopPayloadBuilderService, PayloadBuilderHandle, PayloadBuilderService,
+};
+use reth_rpc_eth_api::helpers::FullEthApi;
+use reth_storage_api::{AccountReader, StateProviderFactory};
 use reth_transaction_pool::{
-    PoolTransaction, SubPoolLimit, TransactionPool, TXPOOL_MAX_ACCOUNT_SLOTS_PER_SENDER,
+    FullTransactionEvent, PoolTransaction, SubPoolLimit, TransactionPool,
+    TXPOOL_MAX_ACCOUNT_SLOTS_PER_SENDER,
 };
-use reth_trie_db::MerklePatriciaTrie;
-use std::time::Duration;
-use tracing::info;
+use reth_trie_db::{MerklePatriciaTrie, StateCommitment};
+use std::{marker::PhantomData, path::PathBuf, time::Duration};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{info, warn};
+use traverse_wallet::{
+    RethUpstream, RotatingSigner, SponsoredTxEvent, TraverseWallet, TraverseWalletAdminApiServer,
+    TraverseWalletApiServer, TraverseWalletConfig,
+};
+use traverse_walltime::{TraverseWallTime, TraverseWallTimeRpcApiServer};
 
 /// Type configuration for a regular Traverse node.
-#[derive(Debug, Clone, Default)]
-pub struct TraverseNode {
+///
+/// The node is generic over its [`StateCommitment`], which defaults to [`MerklePatriciaTrie`].
+#[derive(Debug, Clone)]
+pub struct TraverseNode<SC = MerklePatriciaTrie> {
     /// Additional Optimism args
     pub args: RollupArgs,
+    /// The wallet used to sponsor transactions.
+    ///
+    /// If this is not set, the `wallet_` namespace is not installed.
+    pub sponsor: Option<EthereumWallet>,
+    /// The configuration of the `wallet_` namespace, if a sponsor is set.
+    pub wallet_config: TraverseWalletConfig,
+    /// By how much `engine_getPayloadV3` is delayed into the slot.
+    ///
+    /// Defaults to [`MAX_DELAY_INTO_SLOT`].
+    pub payload_delay: Option<Duration>,
+    /// The maximum duration of building a payload.
+    ///
+    /// If this is not set, the `--builder.deadline` of the node applies, see
+    /// [`TraversePayloadBuilder::with_deadline`].
+    pub payload_deadline: Option<Duration>,
+    /// The service transactions included ahead of the pool in built payloads.
+    ///
+    /// If a sponsor is set, the sponsored transactions are queued here.
+    pub service_transactions: ServiceTransactions,
+    /// Whether the `eth_getProof` override returns storage-only proofs for the withdrawal
+    /// contract.
+    ///
+    /// Defaults to `true`. If disabled, `eth_getProof` behaves like the standard endpoint.
+    pub storage_only_proofs: bool,
+    /// The maximum number of storage keys of a single proof.
+    ///
+    /// Defaults to [`DEFAULT_MAX_PROOF_KEYS`].
+    pub max_proof_keys: usize,
+    /// The path the walltime samples are persisted to, if any.
+    pub walltime_persistence: Option<PathBuf>,
+    /// Whether the precompiles of the spec at the head are built when the EVM is configured.
+    pub warm_up_precompiles: bool,
+    /// Whether the node builds payloads.
+    ///
+    /// Defaults to `true`. If disabled, a no-op payload service is spawned, see
+    /// [`TraverseNode::with_block_production`].
+    pub block_production: bool,
+    /// Whether only trusted peers are connected to.
+    ///
+    /// Defaults to `false`, in which case the `--trusted-only` flag of the node still applies.
+    pub trusted_nodes_only: bool,
+    /// The state commitment of the node.
+    _state_commitment: PhantomData<SC>,
+}
+
+impl<SC> Default for TraverseNode<SC> {
+    fn default() -> Self {
+        Self {
+            args: Default::default(),
+            sponsor: None,
+            wallet_config: Default::default(),
+            payload_delay: None,
+            payload_deadline: None,
+            service_transactions: Default::default(),
+            storage_only_proofs: true,
+            max_proof_keys: DEFAULT_MAX_PROOF_KEYS,
+            walltime_persistence: None,
+            warm_up_precompiles: false,
+            block_production: true,
+            trusted_nodes_only: false,
+            _state_commitment: PhantomData,
+        }
+    }
 }
 
 impl TraverseNode {
     /// Creates a new instance of the Optimism node type.
-    pub const fn new(args: RollupArgs) -> Self {
-        Self { args }
+    pub fn new(args: RollupArgs) -> Self {
+        Self { args, ..Default::default() }
+    }
+
+    /// Returns the overrides of the transaction pool configuration, which double the subpool
+    /// limits and the account slots.
+    pub fn pool_config_overrides() -> PoolBuilderConfigOverrides {
+        PoolBuilderConfigOverrides {
+            queued_limit: Some(SubPoolLimit::default() * 2),
+            pending_limit: Some(SubPoolLimit::default() * 2),
+            basefee_limit: Some(SubPoolLimit::default() * 2),
+            max_account_slots: Some(TXPOOL_MAX_ACCOUNT_SLOTS_PER_SENDER * 2),
+            ..Default::default()
+        }
     }
 
     /// Returns the components for the given [`RollupArgs`].
+    ///
+    /// The payload builder includes the given service transactions ahead of the pool.
     pub fn components<Node>(
         args: &RollupArgs,
+        service_transactions: ServiceTransactions,
     ) -> ComponentsBuilder<
         Node,
         OpPoolBuilder,
@@ -71,16 +182,11 @@ impl TraverseNode {
     {
         ComponentsBuilder::default()
             .node_types::<Node>()
-            .pool(OpPoolBuilder {
-                pool_config_overrides: PoolBuilderConfigOverrides {
-                    queued_limit: Some(SubPoolLimit::default() * 2),
-                    pending_limit: Some(SubPoolLimit::default() * 2),
-                    basefee_limit: Some(SubPoolLimit::default() * 2),
-                    max_account_slots: Some(TXPOOL_MAX_ACCOUNT_SLOTS_PER_SENDER * 2),
-                    ..Default::default()
-                },
-            })
-            .payload(TraversePayloadBuilder::new(args.compute_pending_block))
+            .pool(OpPoolBuilder { pool_config_overrides: Self::pool_config_overrides() })
+            .payload(
+                TraversePayloadBuilder::new(args.compute_pending_block)
+                    .with_service_transactions(service_transactions),
+            )
             .network(TraverseNetworkBuilder::new(OpNetworkBuilder {
                 disable_txpool_gossip: args.disable_txpool_gossip,
                 disable_discovery_v4: !args.discovery_v4,
@@ -90,20 +196,138 @@ impl TraverseNode {
     }
 }
 
+impl<SC> TraverseNode<SC> {
+    /// Swaps the state commitment of the node, keeping the rest of the configuration.
+    ///
+    /// The state commitment determines how state roots, storage roots and proofs are computed
+    /// from the hashed state tables, and how the keys of those tables are hashed. The storage
+    /// layout is shared between all commitments, so a database must only ever be used with the
+    /// commitment it was initialized with.
+    pub const fn with_state_commitment<C>(self) -> TraverseNode<C> {
+        TraverseNode {
+            args: self.args,
+            sponsor: self.sponsor,
+            wallet_config: self.wallet_config,
+            payload_delay: self.payload_delay,
+            payload_deadline: self.payload_deadline,
+            service_transactions: self.service_transactions,
+            storage_only_proofs: self.storage_only_proofs,
+            max_proof_keys: self.max_proof_keys,
+            walltime_persistence: self.walltime_persistence,
+            warm_up_precompiles: self.warm_up_precompiles,
+            block_production: self.block_production,
+            trusted_nodes_only: self.trusted_nodes_only,
+            _state_commitment: PhantomData,
+        }
+    }
+
+    /// Sets the wallet used to sponsor transactions in the `wallet_` namespace.
+    pub fn with_sponsor(mut self, sponsor: Option<EthereumWallet>) -> Self {
+        self.sponsor = sponsor;
+        self
+    }
+
+    /// Sets the configuration of the `wallet_` namespace, which is only installed if a sponsor is
+    /// set, see [`Self::with_sponsor`].
+    pub fn with_wallet_config(mut self, wallet_config: TraverseWalletConfig) -> Self {
+        self.wallet_config = wallet_config;
+        self
+    }
+
+    /// Sets by how much `engine_getPayloadV3` is delayed into the slot.
+    pub const fn with_payload_delay(mut self, delay: Duration) -> Self {
+        self.payload_delay = Some(delay);
+        self
+    }
+
+    /// Sets the maximum duration of building a payload, overriding the `--builder.deadline` of
+    /// the node.
+    pub const fn with_payload_deadline(mut self, deadline: Duration) -> Self {
+        self.payload_deadline = Some(deadline);
+        self
+    }
+
+    /// Sets whether the `eth_getProof` override returns storage-only proofs for the withdrawal
+    /// contract.
+    pub const fn with_storage_only_proofs(mut self, storage_only_proofs: bool) -> Self {
+        self.storage_only_proofs = storage_only_proofs;
+        self
+    }
+
+    /// Sets the maximum number of storage keys of a single proof served by `eth_getProof`,
+    /// `traverse_getProofs` and `traverse_getFullProof`.
+    pub const fn with_max_proof_keys(mut self, max_proof_keys: usize) -> Self {
+        self.max_proof_keys = max_proof_keys;
+        self
+    }
+
+    /// Persists the walltime samples to the log at the given path, restoring them on startup.
+    pub fn with_walltime_persistence(mut self, path: impl Into<PathBuf>) -> Self {
+        self.walltime_persistence = Some(path.into());
+        self
+    }
+
+    /// Sets whether the precompiles of the spec at the head are built when the EVM is configured,
+    /// instead of when the first block is executed.
+    pub const fn with_warm_up_precompiles(mut self, warm_up: bool) -> Self {
+        self.warm_up_precompiles = warm_up;
+        self
+    }
+
+    /// Sets whether the node builds payloads.
+    ///
+    /// Disabling block production saves the resources of the payload builder on nodes that only
+    /// follow the chain, e.g. archive or RPC nodes. Such a node can not sequence: every
+    /// `engine_forkchoiceUpdated` call with payload attributes fails to start a payload job, and
+    /// the sponsored transactions queued as service transactions are never included by it.
+    pub const fn with_block_production(mut self, block_production: bool) -> Self {
+        self.block_production = block_production;
+        self
+    }
+
+    /// Sets whether only trusted peers are connected to, rejecting unknown inbound peers, see
+    /// [`TraverseNetworkBuilder::with_trusted_nodes_only`].
+    pub const fn with_trusted_nodes_only(mut self, trusted_nodes_only: bool) -> Self {
+        self.trusted_nodes_only = trusted_nodes_only;
+        self
+    }
+
+    /// Returns the network builder configured by this node.
+    fn network_builder(&self) -> TraverseNetworkBuilder {
+        TraverseNetworkBuilder::new(OpNetworkBuilder {
+            disable_txpool_gossip: self.args.disable_txpool_gossip,
+            disable_discovery_v4: !self.args.discovery_v4,
+        })
+        .with_trusted_nodes_only(self.trusted_nodes_only)
+    }
+
+    /// Returns the payload builder configured by this node.
+    fn payload_builder(&self) -> TraversePayloadBuilder {
+        let payload = TraversePayloadBuilder::new(self.args.compute_pending_block)
+            .with_service_transactions(self.service_transactions.clone())
+            .with_block_production(self.block_production);
+        match self.payload_deadline {
+            Some(deadline) => payload.with_deadline(deadline),
+            None => payload,
+        }
+    }
+}
+
 /// Configure the node types
-impl NodeTypes for TraverseNode {
+impl<SC: StateCommitment> NodeTypes for TraverseNode<SC> {
     type Primitives = OpPrimitives;
     type ChainSpec = OpChainSpec;
-    type StateCommitment = MerklePatriciaTrie;
+    type StateCommitment = SC;
     type Storage = OpStorage;
 }
 
-impl NodeTypesWithEngine for TraverseNode {
+impl<SC: StateCommitment> NodeTypesWithEngine for TraverseNode<SC> {
     type Engine = OpEngineTypes;
 }
 
-impl<N> Node<N> for TraverseNode
+impl<N, SC> Node<N> for TraverseNode<SC>
 where
+    SC: StateCommitment,
     N: FullNodeTypes<
         Types: NodeTypesWithEngine<
             Engine = OpEngineTypes,
@@ -126,19 +350,241 @@ where
         OpAddOns<NodeAdapter<N, <Self::ComponentsBuilder as NodeComponentsBuilder<N>>::Components>>;
 
     fn components_builder(&self) -> Self::ComponentsBuilder {
-        let Self { args } = self;
-        Self::components(args)
+        TraverseNode::components(&self.args, self.service_transactions.clone())
+            .payload(self.payload_builder())
+            .network(self.network_builder())
+            .executor(TraverseExecutorBuilder::default().with_warm_up(self.warm_up_precompiles))
     }
 
     fn add_ons(&self) -> Self::AddOns {
-        Self::AddOns::builder().with_sequencer(self.args.sequencer_http.clone()).build()
+        let mut add_ons =
+            Self::AddOns::builder().with_sequencer(self.args.sequencer_http.clone()).build();
+
+        let sponsor = self.sponsor.clone().map(|sponsor| (sponsor, self.wallet_config.clone()));
+        let payload_delay = self.payload_delay.unwrap_or(MAX_DELAY_INTO_SLOT);
+        let service_transactions = self.service_transactions.clone();
+        let storage_only_proofs = self.storage_only_proofs;
+        let max_proof_keys = self.max_proof_keys;
+        let walltime_persistence = self.walltime_persistence.clone();
+        add_ons.hooks_mut().set_extend_rpc_modules(move |ctx| {
+            install_rpc_modules(
+                ctx,
+                sponsor,
+                payload_delay,
+                service_transactions,
+                storage_only_proofs,
+                max_proof_keys,
+                walltime_persistence,
+            )
+        });
+
+        add_ons
+    }
+}
+
+/// Installs the Traverse RPC extensions:
+///
+/// - the `eth_getProof` override, returning storage-only proofs if `storage_only_proofs` is set
+/// - the `traverse_getProofs` and `traverse_getFullProof` endpoints
+/// - rejecting proofs of more than `max_proof_keys` storage keys on all proof endpoints
+/// - the `traverse_getEvmConfig` endpoint
+/// - the `traverse_getPoolConfig` endpoint
+/// - the `wallet_` namespace with the [`TraverseWalletConfig`] of the sponsor, if a sponsor is
+///   configured, queueing the sponsored transactions in the given [`ServiceTransactions`]
+/// - the `walletAdmin_` namespace over IPC, if a sponsor is configured
+/// - the `traverse_getSponsoredTransactionStatus` endpoint, if a sponsor is configured
+/// - the `traverse_getWallTimeData` endpoint, persisting the samples to `walltime_persistence` if
+///   set
+/// - the delayed `engine_getPayloadV3` resolver
+fn install_rpc_modules<N>(
+    ctx: RpcContext<'_, N, OpEthApi<N>>,
+    sponsor: Option<(EthereumWallet, TraverseWalletConfig)>,
+    payload_delay: Duration,
+    service_transactions: ServiceTransactions,
+    storage_only_proofs: bool,
+    max_proof_keys: usize,
+    walltime_persistence: Option<PathBuf>,
+) -> eyre::Result<()>
+where
+    N: FullNodeComponents<
+        Types: NodeTypes<ChainSpec = OpChainSpec, Primitives = OpPrimitives>,
+        Evm = TraverseEvmConfig,
+    >,
+    OpEthApi<N>: FullEthApi + Send + Sync + 'static,
+{
+    // override eth namespace
+    ctx.modules.replace_configured(EthApiOverrideServer::into_rpc(
+        EthApiExt::new(ctx.registry.eth_api().clone())
+            .with_storage_only_proofs(storage_only_proofs)
+            .with_max_proof_keys(max_proof_keys),
+    ))?;
+
+    // register traverse proofs namespace
+    ctx.modules.merge_configured(TraverseProofApiServer::into_rpc(
+        EthApiExt::new(ctx.registry.eth_api().clone())
+            .with_storage_only_proofs(storage_only_proofs)
+            .with_max_proof_keys(max_proof_keys),
+    ))?;
+
+    // register the evm config introspection
+    ctx.modules.merge_configured(
+        TraverseEvmConfigExt::new(ctx.provider().clone(), ctx.node().evm_config().clone())
+            .into_rpc(),
+    )?;
+
+    // register the pool config introspection, with the overrides applied by the pool builder
+    let pool_config =
+        TraverseNode::pool_config_overrides().apply(ctx.config().txpool.pool_config());
+    ctx.modules.merge_configured(TraversePoolConfigExt::new(&pool_config).into_rpc())?;
+
+    // register traverse wallet namespace
+    if let Some((sponsor, wallet_config)) = sponsor {
+        let wallet = TraverseWallet::with_config(
+            RethUpstream::new(
+                ctx.provider().clone(),
+                ctx.registry.eth_api().clone(),
+                RotatingSigner::new(sponsor),
+            ),
+            ctx.config().chain.chain().id(),
+            wallet_config,
+        );
+        ctx.node().task_executor().spawn(settle_sponsored_transactions(
+            wallet.clone(),
+            service_transactions.clone(),
+            ctx.node().pool().all_transactions_event_listener(),
+        ));
+        ctx.node().task_executor().spawn(queue_sponsored_transactions(
+            service_transactions.clone(),
+            ctx.node().pool().clone(),
+            wallet.sponsored_transactions(),
+        ));
+        ctx.node().task_executor().spawn(evict_stale_service_transactions(
+            service_transactions,
+            ctx.provider().clone(),
+            ctx.provider().canonical_state_stream(),
+        ));
+        let status = SponsoredTxStatusTracker::new();
+        status
+            .clone()
+            .spawn(wallet.sponsored_transactions(), ctx.provider().canonical_state_stream());
+        ctx.modules.merge_configured(status.into_rpc())?;
+        // the admin namespace is only exposed over IPC
+        ctx.modules.merge_ipc(TraverseWalletAdminApiServer::into_rpc(wallet.clone()))?;
+        ctx.modules.merge_configured(TraverseWalletApiServer::into_rpc(wallet))?;
+    }
+
+    let walltime =
+        TraverseWallTime::spawn(ctx.provider().canonical_state_stream(), walltime_persistence);
+    ctx.modules.merge_configured(walltime.into_rpc())?;
+    info!(target: "reth::cli", "Walltime configured");
+
+    // wrap the getPayloadV3 method in a delay
+    let engine_module = ctx.auth_module.module_mut().clone();
+    let delayed_payload = DelayedResolver::new(engine_module, payload_delay);
+    delayed_payload.clone().spawn(ctx.provider().canonical_state_stream());
+    ctx.auth_module.replace_auth_methods(delayed_payload.into_rpc_module())?;
+    info!(target: "reth::cli", "Configured payload delay");
+
+    Ok(())
+}
+
+/// Settles sponsored transactions in the wallet once they are mined or dropped from the pool, and
+/// removes them from the service transactions.
+async fn settle_sponsored_transactions<U, St, T>(
+    wallet: TraverseWallet<U>,
+    service_transactions: ServiceTransactions,
+    mut events: St,
+) where
+    St: Stream<Item = FullTransactionEvent<T>> + Unpin,
+    T: PoolTransaction,
+{
+    while let Some(event) = events.next().await {
+        let tx_hash = match event {
+            FullTransactionEvent::Mined { tx_hash, .. }
+            | FullTransactionEvent::Discarded(tx_hash)
+            | FullTransactionEvent::Invalid(tx_hash) => tx_hash,
+            FullTransactionEvent::Replaced { transaction, .. } => *transaction.hash(),
+            _ => continue,
+        };
+        wallet.settle_transactions([tx_hash]);
+        service_transactions.remove([&tx_hash]);
+    }
+}
+
+/// Queues the sponsored transactions of the wallet in the service transactions, so they are
+/// included ahead of the pool in the next payload.
+async fn queue_sponsored_transactions<P>(
+    service_transactions: ServiceTransactions,
+    pool: P,
+    mut events: broadcast::Receiver<SponsoredTxEvent>,
+) where
+    P: TransactionPool<Transaction: PoolTransaction<Consensus = OpTransactionSigned>>,
+{
+    loop {
+        match events.recv().await {
+            Ok(SponsoredTxEvent { tx_hash, .. }) => {
+                // the transaction is in the pool once it is sent
+                let Some(tx) = pool.get(&tx_hash) else { continue };
+                if !service_transactions.push(tx.transaction.clone_into_consensus()) {
+                    warn!(target: "reth::cli", %tx_hash, "Failed to queue service transaction");
+                }
+            }
+            Err(RecvError::Lagged(skipped)) => {
+                warn!(target: "reth::cli", skipped, "Missed sponsored transactions");
+            }
+            Err(RecvError::Closed) => break,
+        }
+    }
+}
+
+/// Removes the service transactions that were included in the canonical chain, by the nonces of
+/// their senders in the latest state, whenever the canonical chain changes.
+///
+/// This also removes the transactions that were queued after their pool events were handled by
+/// [`settle_sponsored_transactions`], which would otherwise stay queued.
+async fn evict_stale_service_transactions<P, St, N>(
+    service_transactions: ServiceTransactions,
+    provider: P,
+    mut notifications: St,
+) where
+    P: StateProviderFactory,
+    St: Stream<Item = CanonStateNotification<N>> + Unpin,
+    N: NodePrimitives,
+{
+    while notifications.next().await.is_some() {
+        if service_transactions.is_empty() {
+            continue;
+        }
+        let state = match provider.latest() {
+            Ok(state) => state,
+            Err(err) => {
+                warn!(target: "reth::cli", %err, "Failed to evict stale service transactions");
+                continue;
+            }
+        };
+        service_transactions.remove_stale(|sender| {
+            state.basic_account(sender).ok().flatten().map_or(0, |account| account.nonce)
+        });
     }
 }
 
 /// The Traverse evm and executor builder.
 #[derive(Debug, Default, Clone, Copy)]
 #[non_exhaustive]
-pub struct TraverseExecutorBuilder;
+pub struct TraverseExecutorBuilder {
+    /// Whether the precompiles of the spec at the head are built ahead of execution.
+    warm_up: bool,
+}
+
+impl TraverseExecutorBuilder {
+    /// Sets whether the precompiles of the spec at the head are built when the EVM is configured.
+    ///
+    /// See [`TraverseEvmConfig::warm_up`].
+    pub const fn with_warm_up(mut self, warm_up: bool) -> Self {
+        self.warm_up = warm_up;
+        self
+    }
+}
 
 impl<Node> ExecutorBuilder<Node> for TraverseExecutorBuilder
 where
@@ -153,6 +599,12 @@ where
     ) -> eyre::Result<(Self::EVM, Self::Executor)> {
         let chain_spec = ctx.chain_spec();
         let evm_config = TraverseEvmConfig::new(chain_spec);
+        if self.warm_up {
+            let head = ctx.head();
+            let header =
+                Header { number: head.number, timestamp: head.timestamp, ..Default::default() };
+            evm_config.warm_up(evm_config.spec_id(&header));
+        }
         let strategy_factory =
             OpExecutionStrategyFactory::new(ctx.chain_spec(), evm_config.clone());
         let executor = BasicBlockExecutorProvider::new(strategy_factory);
@@ -164,17 +616,63 @@ where
 /// The Traverse payload service builder.
 ///
 /// This service wraps the default Optimism payload builder, but replaces the default evm config
-/// with Traverse's own.
+/// with Traverse's own, and includes the [`ServiceTransactions`] ahead of the pool.
+///
+/// If block production is disabled, a no-op payload service is spawned instead, see
+/// [`TraversePayloadBuilder::with_block_production`].
 #[derive(Debug, Default, Clone)]
 pub struct TraversePayloadBuilder {
     /// Inner Optimism payload builder service.
-    inner: OpPayloadBuilder,
+    inner: OpPayloadBuilder<ServiceTransactions>,
+    /// The maximum duration of building a payload, overriding the `--builder.deadline` of the
+    /// node.
+    deadline: Option<Duration>,
+    /// Whether block production is disabled, in which case no payloads are built.
+    noop: bool,
 }
 
 impl TraversePayloadBuilder {
     /// Create a new instance with the given `compute_pending_block` flag.
     pub fn new(compute_pending_block: bool) -> Self {
-        Self { inner: OpPayloadBuilder::new(compute_pending_block) }
+        Self {
+            inner: OpPayloadBuilder::new(compute_pending_block)
+                .with_transactions(ServiceTransactions::default()),
+            deadline: None,
+            noop: false,
+        }
+    }
+
+    /// Sets the service transactions that are included ahead of the pool.
+    pub fn with_service_transactions(self, service_transactions: ServiceTransactions) -> Self {
+        Self { inner: self.inner.with_transactions(service_transactions), ..self }
+    }
+
+    /// Sets the maximum duration of building a payload.
+    ///
+    /// Once the deadline is reached, the payload job stops improving the payload, and resolves to
+    /// the best payload built so far. This overrides the `--builder.deadline` of the node.
+    pub const fn with_deadline(mut self, deadline: Duration) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets whether payloads are built.
+    ///
+    /// This is on by default. If disabled, a no-op payload service is spawned, which rejects
+    /// every payload job, see [`TraverseNode::with_block_production`].
+    pub const fn with_block_production(mut self, block_production: bool) -> Self {
+        self.noop = !block_production;
+        self
+    }
+
+    /// Returns whether the no-op payload service is spawned instead of the payload builder.
+    const fn is_noop(&self) -> bool {
+        self.noop
+    }
+
+    /// Returns the deadline of payload jobs, given the deadline configured for the node.
+    fn deadline_or(&self, default: Duration) -> Duration {
+        self.deadline.unwrap_or(default)
     }
 }
 
@@ -196,7 +694,43 @@ where
         ctx: &BuilderContext<Node>,
         pool: Pool,
     ) -> eyre::Result<PayloadBuilderHandle<OpEngineTypes>> {
-        self.inner.spawn(TraverseEvmConfig::new(ctx.chain_spec()), ctx, pool)
+        if self.is_noop() {
+            info!(target: "reth::cli", "Block production disabled, payloads are not built");
+            let (payload_service, payload_builder) = NoopPayloadBuilderService::new();
+            ctx.task_executor()
+                .spawn_critical("payload builder service", Box::pin(payload_service));
+            return Ok(payload_builder);
+        }
+
+        let evm_config = TraverseEvmConfig::new(ctx.chain_spec());
+        if self.deadline.is_none() {
+            return self.inner.spawn(evm_config, ctx, pool);
+        }
+
+        // this mirrors `OpPayloadBuilder::spawn`, with the deadline of the payload jobs replaced
+        let conf = ctx.payload_builder_config();
+        let payload_job_config = BasicPayloadJobGeneratorConfig::default()
+            .interval(conf.interval())
+            .deadline(self.deadline_or(conf.deadline()))
+            .max_payload_tasks(conf.max_payload_tasks())
+            .extradata(conf.extradata_bytes());
+
+        let payload_builder = reth_optimism_payload_builder::OpPayloadBuilder::new(evm_config)
+            .with_transactions(self.inner.best_transactions)
+            .set_compute_pending_block(self.inner.compute_pending_block);
+        let payload_generator = BasicPayloadJobGenerator::with_builder(
+            ctx.provider().clone(),
+            pool,
+            ctx.task_executor().clone(),
+            payload_job_config,
+            payload_builder,
+        );
+        let (payload_service, payload_builder) =
+            PayloadBuilderService::new(payload_generator, ctx.provider().canonical_state_stream());
+
+        ctx.task_executor().spawn_critical("payload builder service", Box::pin(payload_service));
+
+        Ok(payload_builder)
     }
 }
 
@@ -204,12 +738,33 @@ where
 #[derive(Debug, Default, Clone)]
 pub struct TraverseNetworkBuilder {
     inner: OpNetworkBuilder,
+    /// Whether only trusted peers are connected to, rejecting unknown inbound peers.
+    trusted_nodes_only: bool,
 }
 
 impl TraverseNetworkBuilder {
     /// Create a new instance based on the given op builder
     pub const fn new(network: OpNetworkBuilder) -> Self {
-        Self { inner: network }
+        Self { inner: network, trusted_nodes_only: false }
+    }
+
+    /// Sets whether only trusted peers are connected to.
+    ///
+    /// This is off by default, in which case the `--trusted-only` flag of the node still applies.
+    pub const fn with_trusted_nodes_only(mut self, trusted_nodes_only: bool) -> Self {
+        self.trusted_nodes_only = trusted_nodes_only;
+        self
+    }
+
+    /// Configures the peers for the limited set of trusted peers the network is rolled with.
+    fn configure_peers(&self, peers_config: &mut PeersConfig) {
+        // this is rolled with limited trusted peers and we want ignore any reputation slashing
+        peers_config.reputation_weights = ReputationChangeWeights::zero();
+        peers_config.backoff_durations.low = Duration::from_secs(5);
+        peers_config.backoff_durations.medium = Duration::from_secs(5);
+        peers_config.backoff_durations.high = Duration::from_secs(5);
+        peers_config.max_backoff_count = u8::MAX;
+        peers_config.trusted_nodes_only |= self.trusted_nodes_only;
     }
 }
 
@@ -232,12 +787,7 @@ where
         pool: Pool,
     ) -> eyre::Result<NetworkHandle<OpNetworkPrimitives>> {
         let mut network_config = self.inner.network_config(ctx)?;
-        // this is rolled with limited trusted peers and we want ignore any reputation slashing
-        network_config.peers_config.reputation_weights = ReputationChangeWeights::zero();
-        network_config.peers_config.backoff_durations.low = Duration::from_secs(5);
-        network_config.peers_config.backoff_durations.medium = Duration::from_secs(5);
-        network_config.peers_config.backoff_durations.high = Duration::from_secs(5);
-        network_config.peers_config.max_backoff_count = u8::MAX;
+        self.configure_peers(&mut network_config.peers_config);
         network_config.sessions_config.session_command_buffer = 750;
         network_config.sessions_config.session_event_buffer = 750;
 
@@ -251,3 +801,140 @@ where
         Ok(handle)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{TraverseNetworkBuilder, TraverseNode, TraversePayloadBuilder};
+    use reth_db_api::transaction::DbTx;
+    use reth_network_types::{PeersConfig, ReputationChangeWeights};
+    use reth_node_api::NodeTypesWithEngine;
+    use reth_optimism_chainspec::OpChainSpec;
+    use reth_optimism_node::OpEngineTypes;
+    use reth_trie_db::{MerklePatriciaTrie, StateCommitment};
+    use std::time::Duration;
+    use traverse_wallet::TraverseWalletConfig;
+
+    /// A state commitment distinct from [`MerklePatriciaTrie`], reusing its implementation.
+    #[derive(Debug)]
+    struct TestCommitment;
+
+    impl StateCommitment for TestCommitment {
+        type StateRoot<'a, TX: DbTx + 'a> =
+            <MerklePatriciaTrie as StateCommitment>::StateRoot<'a, TX>;
+        type StorageRoot<'a, TX: DbTx + 'a> =
+            <MerklePatriciaTrie as StateCommitment>::StorageRoot<'a, TX>;
+        type StateProof<'a, TX: DbTx + 'a> =
+            <MerklePatriciaTrie as StateCommitment>::StateProof<'a, TX>;
+        type StateWitness<'a, TX: DbTx + 'a> =
+            <MerklePatriciaTrie as StateCommitment>::StateWitness<'a, TX>;
+        type KeyHasher = <MerklePatriciaTrie as StateCommitment>::KeyHasher;
+    }
+
+    fn assert_node_types<N>()
+    where
+        N: NodeTypesWithEngine<Engine = OpEngineTypes, ChainSpec = OpChainSpec>,
+    {
+    }
+
+    fn assert_node_types_with_commitment<SC: StateCommitment>() {
+        assert_node_types::<TraverseNode<SC>>();
+    }
+
+    fn assert_state_commitment<N, SC>()
+    where
+        N: NodeTypesWithEngine<StateCommitment = SC>,
+        SC: StateCommitment,
+    {
+    }
+
+    #[test]
+    fn node_types_with_state_commitment() {
+        assert_node_types::<TraverseNode>();
+        assert_node_types_with_commitment::<MerklePatriciaTrie>();
+        assert_node_types_with_commitment::<TestCommitment>();
+        assert_state_commitment::<TraverseNode, MerklePatriciaTrie>();
+        assert_state_commitment::<TraverseNode<TestCommitment>, TestCommitment>();
+
+        let node = TraverseNode::new(Default::default())
+            .with_payload_delay(Duration::from_secs(1))
+            .with_wallet_config(TraverseWalletConfig::default().with_max_in_flight_gas(1_000_000));
+        let node: TraverseNode<TestCommitment> = node.with_state_commitment();
+        assert_eq!(node.payload_delay, Some(Duration::from_secs(1)));
+        assert_eq!(node.wallet_config.max_in_flight_gas, Some(1_000_000));
+        let node: TraverseNode = node.with_state_commitment();
+        assert_eq!(node.payload_delay, Some(Duration::from_secs(1)));
+        assert_eq!(node.wallet_config.max_in_flight_gas, Some(1_000_000));
+    }
+
+    #[test]
+    fn trusted_nodes_only() {
+        let mut peers_config = PeersConfig::default();
+        TraverseNetworkBuilder::default().configure_peers(&mut peers_config);
+        assert!(!peers_config.trusted_nodes_only);
+        assert_eq!(peers_config.reputation_weights, ReputationChangeWeights::zero());
+
+        let mut peers_config = PeersConfig::default();
+        TraverseNetworkBuilder::default()
+            .with_trusted_nodes_only(true)
+            .configure_peers(&mut peers_config);
+        assert!(peers_config.trusted_nodes_only);
+
+        // the `--trusted-only` flag of the node is preserved
+        let mut peers_config = PeersConfig::default().with_trusted_nodes_only(true);
+        TraverseNetworkBuilder::default().configure_peers(&mut peers_config);
+        assert!(peers_config.trusted_nodes_only);
+
+        // the option of the node type is passed to its network builder
+        let node = TraverseNode::new(Default::default());
+        let mut peers_config = PeersConfig::default();
+        node.network_builder().configure_peers(&mut peers_config);
+        assert!(!peers_config.trusted_nodes_only);
+
+        let node = node.with_trusted_nodes_only(true);
+        let node: TraverseNode<MerklePatriciaTrie> = node.with_state_commitment();
+        let mut peers_config = PeersConfig::default();
+        node.network_builder().configure_peers(&mut peers_config);
+        assert!(peers_config.trusted_nodes_only);
+    }
+
+    #[test]
+    fn payload_deadline() {
+        let node_deadline = Duration::from_secs(12);
+
+        // the deadline of the node applies by default
+        let builder = TraversePayloadBuilder::new(false);
+        assert_eq!(builder.deadline_or(node_deadline), node_deadline);
+
+        // the deadline is kept when the service transactions are set
+        let builder = TraversePayloadBuilder::new(false)
+            .with_deadline(Duration::from_millis(500))
+            .with_service_transactions(Default::default());
+        assert_eq!(builder.deadline_or(node_deadline), Duration::from_millis(500));
+
+        // the deadline of the node type is passed to its payload builder
+        let node = TraverseNode::new(Default::default());
+        assert_eq!(node.payload_builder().deadline_or(node_deadline), node_deadline);
+        let node = node.with_payload_deadline(Duration::from_millis(500));
+        let node: TraverseNode<MerklePatriciaTrie> = node.with_state_commitment();
+        assert_eq!(node.payload_builder().deadline_or(node_deadline), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn block_production() {
+        // payloads are built by default
+        assert!(TraverseNode::new(Default::default()).block_production);
+        assert!(!TraversePayloadBuilder::default().is_noop());
+        assert!(!TraversePayloadBuilder::new(false).is_noop());
+
+        // the no-op payload service is selected when block production is disabled
+        let builder = TraversePayloadBuilder::new(false)
+            .with_block_production(false)
+            .with_service_transactions(Default::default())
+            .with_deadline(Duration::from_millis(500));
+        assert!(builder.is_noop());
+
+        let node = TraverseNode::new(Default::default()).with_block_production(false);
+        let node: TraverseNode<MerklePatriciaTrie> = node.with_state_commitment();
+        assert!(!node.block_production);
+    }
+}
@@ -0,0 +1,251 @@
+//! Service transactions included at the top of built payloads.
+//!
+//! Transactions sponsored by the service are queued in [`ServiceTransactions`], and the
+//! [`TraversePayloadBuilder`](crate::node::TraversePayloadBuilder) includes them ahead of the
+//! transactions of the pool, so they land in the next block.
+
+use alloy_consensus::Transaction;
+use alloy_primitives::{Address, TxHash};
+use parking_lot::Mutex;
+use reth_optimism_payload_builder::builder::OpPayloadTransactions;
+use reth_optimism_primitives::OpTransactionSigned;
+use reth_payload_util::{PayloadTransactions, PayloadTransactionsChain, PayloadTransactionsFixed};
+use reth_primitives::RecoveredTx;
+use reth_primitives_traits::SignedTransaction;
+use reth_transaction_pool::{
+    pool::BestPayloadTransactions, BestTransactionsAttributes, PoolTransaction, TransactionPool,
+};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+};
+
+/// The default number of service transactions that can be queued.
+pub const DEFAULT_SERVICE_TRANSACTIONS_CAPACITY: usize = 128;
+
+/// A bounded queue of service transactions, which are included ahead of the pool in every built
+/// payload.
+///
+/// Queued transactions stay in the queue until they are removed with
+/// [`ServiceTransactions::remove`], e.g. once they are mined, or until their nonce is below the
+/// nonce of their sender, see [`ServiceTransactions::remove_stale`]. Transactions that became
+/// invalid are skipped by the payload builder.
+#[derive(Debug, Clone)]
+pub struct ServiceTransactions<T = OpTransactionSigned> {
+    inner: Arc<Mutex<VecDeque<RecoveredTx<T>>>>,
+    capacity: usize,
+}
+
+impl<T> Default for ServiceTransactions<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_SERVICE_TRANSACTIONS_CAPACITY)
+    }
+}
+
+impl<T> ServiceTransactions<T> {
+    /// Creates a new, empty queue that holds at most `capacity` transactions.
+    pub fn new(capacity: usize) -> Self {
+        Self { inner: Default::default(), capacity }
+    }
+
+    /// Returns the maximum number of queued transactions.
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of queued transactions.
+    pub fn len(&self) -> usize {
+        self.inner.lock().len()
+    }
+
+    /// Returns `true` if no transactions are queued.
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().is_empty()
+    }
+}
+
+impl<T: SignedTransaction> ServiceTransactions<T> {
+    /// Queues the given transaction.
+    ///
+    /// Returns `false` if the transaction is already queued, or if the queue is full.
+    pub fn push(&self, tx: RecoveredTx<T>) -> bool {
+        let mut inner = self.inner.lock();
+        if inner.len() >= self.capacity
+            || inner.iter().any(|queued| queued.tx_hash() == tx.tx_hash())
+        {
+            return false;
+        }
+        inner.push_back(tx);
+        true
+    }
+
+    /// Removes the given transactions from the queue.
+    pub fn remove<'a>(&self, tx_hashes: impl IntoIterator<Item = &'a TxHash>) {
+        let mut inner = self.inner.lock();
+        for tx_hash in tx_hashes {
+            inner.retain(|queued| queued.tx_hash() != tx_hash);
+        }
+    }
+
+    /// Removes the queued transactions whose nonce is below the given nonce of their sender, e.g.
+    /// its nonce in the latest state.
+    ///
+    /// This removes the transactions that were included, even if they were queued after they left
+    /// the pool, and are thus never removed with [`ServiceTransactions::remove`].
+    pub fn remove_stale(&self, mut nonce: impl FnMut(Address) -> u64) {
+        self.inner.lock().retain(|queued| queued.nonce() >= nonce(queued.signer()));
+    }
+
+    /// Returns the queued transactions, in the order they were queued.
+    pub fn transactions(&self) -> Vec<RecoveredTx<T>> {
+        self.inner.lock().iter().cloned().collect()
+    }
+
+    /// Chains the queued transactions ahead of the given transactions.
+    ///
+    /// Queued transactions are usually in the pool as well, so they are skipped in the given
+    /// transactions. Otherwise, the second copy would fail with a nonce too low, and the rest of
+    /// the transactions of the sponsor would be marked invalid.
+    pub fn chain<P>(&self, after: P) -> impl PayloadTransactions<Transaction = T>
+    where
+        P: PayloadTransactions<Transaction = T>,
+    {
+        let queued = self.transactions();
+        let skipped = queued.iter().map(|tx| *tx.tx_hash()).collect();
+        PayloadTransactionsChain::new(
+            PayloadTransactionsFixed::new(queued),
+            None,
+            SkipTransactions { inner: after, skipped },
+            None,
+        )
+    }
+}
+
+/// [`PayloadTransactions`] that skip the transactions with the given hashes.
+#[derive(Debug)]
+struct SkipTransactions<P> {
+    inner: P,
+    skipped: HashSet<TxHash>,
+}
+
+impl<P> PayloadTransactions for SkipTransactions<P>
+where
+    P: PayloadTransactions<Transaction: SignedTransaction>,
+{
+    type Transaction = P::Transaction;
+
+    fn next(&mut self, ctx: ()) -> Option<RecoveredTx<Self::Transaction>> {
+        loop {
+            let tx = self.inner.next(ctx)?;
+            if !self.skipped.contains(tx.tx_hash()) {
+                return Some(tx);
+            }
+        }
+    }
+
+    fn mark_invalid(&mut self, sender: Address, nonce: u64) {
+        self.inner.mark_invalid(sender, nonce)
+    }
+}
+
+impl OpPayloadTransactions for ServiceTransactions {
+    fn best_transactions<
+        Pool: TransactionPool<Transaction: PoolTransaction<Consensus = OpTransactionSigned>>,
+    >(
+        &self,
+        pool: Pool,
+        attr: BestTransactionsAttributes,
+    ) -> impl PayloadTransactions<Transaction = OpTransactionSigned> {
+        self.chain(BestPayloadTransactions::new(pool.best_transactions_with_attributes(attr)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_consensus::TxLegacy;
+    use alloy_primitives::{PrimitiveSignature, TxKind};
+    use op_alloy_consensus::OpTypedTransaction;
+
+    fn tx(sender: Address, nonce: u64) -> RecoveredTx<OpTransactionSigned> {
+        // the signature is fixed, so the sender is also the recipient to keep the hashes unique
+        let tx = OpTransactionSigned::new_unhashed(
+            OpTypedTransaction::Legacy(TxLegacy {
+                nonce,
+                to: TxKind::Call(sender),
+                ..Default::default()
+            }),
+            PrimitiveSignature::test_signature(),
+        );
+        RecoveredTx::from_signed_transaction(tx, sender)
+    }
+
+    #[test]
+    fn queued_transactions_are_included_first() {
+        let queue = ServiceTransactions::default();
+        let service = tx(Address::with_last_byte(1), 0);
+        assert!(queue.push(service.clone()));
+        // already queued
+        assert!(!queue.push(service.clone()));
+
+        let pooled = tx(Address::with_last_byte(2), 0);
+        let mut best = queue.chain(PayloadTransactionsFixed::new(vec![pooled.clone()]));
+
+        assert_eq!(best.next(()).map(|tx| *tx.tx_hash()), Some(*service.tx_hash()));
+        assert_eq!(best.next(()).map(|tx| *tx.tx_hash()), Some(*pooled.tx_hash()));
+        assert!(best.next(()).is_none());
+
+        // queued transactions are included in every payload until they are removed
+        let mut best = queue.chain(PayloadTransactionsFixed::new(vec![]));
+        assert_eq!(best.next(()).map(|tx| *tx.tx_hash()), Some(*service.tx_hash()));
+
+        queue.remove([service.tx_hash()]);
+        assert!(queue.is_empty());
+        let mut best = queue.chain(PayloadTransactionsFixed::new(vec![pooled.clone()]));
+        assert_eq!(best.next(()).map(|tx| *tx.tx_hash()), Some(*pooled.tx_hash()));
+    }
+
+    #[test]
+    fn queued_transactions_in_the_pool_are_included_once() {
+        let queue = ServiceTransactions::default();
+        let service = tx(Address::with_last_byte(1), 0);
+        let next = tx(Address::with_last_byte(1), 1);
+        let pooled = tx(Address::with_last_byte(2), 0);
+        assert!(queue.push(service.clone()));
+
+        // the service transaction was also added to the pool
+        let mut best = queue.chain(PayloadTransactionsFixed::new(vec![
+            service.clone(),
+            next.clone(),
+            pooled.clone(),
+        ]));
+        let included: Vec<_> =
+            std::iter::from_fn(|| best.next(())).map(|tx| *tx.tx_hash()).collect();
+        assert_eq!(included, [*service.tx_hash(), *next.tx_hash(), *pooled.tx_hash()]);
+    }
+
+    #[test]
+    fn stale_transactions() {
+        let queue = ServiceTransactions::default();
+        let sponsor = Address::with_last_byte(1);
+        let other = Address::with_last_byte(2);
+        for tx in [tx(sponsor, 0), tx(sponsor, 1), tx(sponsor, 2), tx(other, 0)] {
+            assert!(queue.push(tx));
+        }
+
+        // the first two transactions of the sponsor were included
+        queue.remove_stale(|sender| if sender == sponsor { 2 } else { 0 });
+        let queued: Vec<_> =
+            queue.transactions().iter().map(|tx| (tx.signer(), tx.nonce())).collect();
+        assert_eq!(queued, [(sponsor, 2), (other, 0)]);
+    }
+
+    #[test]
+    fn bounded() {
+        let queue = ServiceTransactions::new(2);
+        assert!(queue.push(tx(Address::with_last_byte(1), 0)));
+        assert!(queue.push(tx(Address::with_last_byte(1), 1)));
+        assert!(!queue.push(tx(Address::with_last_byte(1), 2)));
+        assert_eq!(queue.len(), queue.capacity());
+    }
+}
@@ -3,25 +3,50 @@
 //! `eth_` namespace overrides:
 //!
 //! - `eth_getProof` will _ONLY_ return the storage proofs _WITHOUT_ an account proof _IF_ targeting
-//!   the withdrawal contract. Otherwise, it fallbacks to default behaviour.
+//!   one of the storage proof addresses, by default the withdrawal contract (the
+//!   `L2ToL1MessagePasser`). Otherwise, it fallbacks to default behaviour. If the targeted contract
+//!   does not exist, the default proof of its absence is returned. The storage proofs can
+//!   optionally be verified against the storage root before they are returned, see
+//!   [`EthApiExt::with_proof_verification`]. The storage proofs of large key sets can be computed
+//!   in batches, see [`EthApiExt::with_proof_batch_size`]. The override can be disabled, see
+//!   [`EthApiExt::with_storage_only_proofs`]. The number of storage keys of a proof is capped, see
+//!   [`EthApiExt::with_max_proof_keys`]. Proofs can be requested at a block number, tag or hash, if
+//!   the state of a block hash is unavailable the error names the block hash.
+//!
+//! `traverse_` namespace:
+//!
+//! - `traverse_getProofs` returns the proofs of multiple `eth_getProof` requests in one call. The
+//!   proofs in flight across all calls are bounded, see [`EthApiExt::with_max_concurrent_proofs`].
+//! - `traverse_getFullProof` returns the complete `eth_getProof` response, with the account proof,
+//!   for any address including the storage proof addresses.
+//! - `traverse_getEvmConfig` returns the effective EVM configuration at the latest block.
+//! - `traverse_getPoolConfig` returns the effective transaction pool limits.
 
+use crate::evm::{EvmConfigInfo, TraverseEvmConfig};
+use alloy_consensus::Header;
 use alloy_eips::BlockId;
 use alloy_primitives::{Address, B256};
 use alloy_rpc_types::serde_helpers::JsonStorageKey;
 use alloy_rpc_types_eth::EIP1186AccountProofResponse;
+use futures::{StreamExt, TryStreamExt};
 use jsonrpsee::{
     core::{async_trait, RpcResult},
     proc_macros::rpc,
 };
-use traverse_common::WITHDRAWAL_CONTRACT;
 use reth_errors::RethError;
 use reth_rpc_eth_api::{
     helpers::{EthState, FullEthApi},
-    FromEthApiError,
+    AsEthApiError, FromEthApiError,
 };
 use reth_rpc_eth_types::EthApiError;
-use reth_trie_common::AccountProof;
+use reth_storage_api::{BlockNumReader, HeaderProvider, StateProvider};
+use reth_transaction_pool::{PoolConfig, SubPoolLimit};
+use reth_trie_common::{AccountProof, StorageMultiProof};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, future::Future, sync::Arc};
+use tokio::sync::Semaphore;
 use tracing::trace;
+use traverse_common::WITHDRAWAL_CONTRACT;
 
 /// Traverse `eth_` RPC namespace overrides.
 #[cfg_attr(not(test), rpc(server, namespace = "eth"))]
@@ -38,35 +63,304 @@ pub trait EthApiOverride {
     ) -> RpcResult<EIP1186AccountProofResponse>;
 }
 
+/// A single proof request of `traverse_getProofs`, in the same shape as the parameters of
+/// `eth_getProof`.
+pub type ProofRequest = (Address, Vec<JsonStorageKey>, Option<BlockId>);
+
+/// Traverse `traverse_` RPC namespace for proofs.
+#[cfg_attr(not(test), rpc(server, namespace = "traverse"))]
+#[cfg_attr(test, rpc(server, client, namespace = "traverse"))]
+pub trait TraverseProofApi {
+    /// Returns the proofs for each of the given requests, in the same order.
+    ///
+    /// Each request is served like `eth_getProof`, including the storage-only proofs for the
+    /// storage proof addresses.
+    #[method(name = "getProofs")]
+    async fn get_proofs(
+        &self,
+        requests: Vec<ProofRequest>,
+    ) -> RpcResult<Vec<EIP1186AccountProofResponse>>;
+
+    /// Returns the account and storage values of the specified account including the
+    /// Merkle-proof, like the standard `eth_getProof`.
+    ///
+    /// Unlike the `eth_getProof` override, the account proof is included for the storage proof
+    /// addresses as well.
+    #[method(name = "getFullProof")]
+    async fn get_full_proof(
+        &self,
+        address: Address,
+        keys: Vec<JsonStorageKey>,
+        block_number: Option<BlockId>,
+    ) -> RpcResult<EIP1186AccountProofResponse>;
+}
+
+/// Traverse `traverse_` RPC namespace for introspecting the EVM configuration.
+#[cfg_attr(not(test), rpc(server, namespace = "traverse"))]
+#[cfg_attr(test, rpc(server, client, namespace = "traverse"))]
+pub trait TraverseEvmConfigApi {
+    /// Returns the effective EVM configuration at the latest block.
+    #[method(name = "getEvmConfig")]
+    async fn get_evm_config(&self) -> RpcResult<EvmConfigInfo>;
+}
+
+/// Implementation of `traverse_getEvmConfig`.
+#[derive(Debug)]
+pub struct TraverseEvmConfigExt<Provider> {
+    provider: Provider,
+    evm_config: TraverseEvmConfig,
+}
+
+impl<Provider> TraverseEvmConfigExt<Provider> {
+    /// Create a new `TraverseEvmConfigExt` module.
+    pub const fn new(provider: Provider, evm_config: TraverseEvmConfig) -> Self {
+        Self { provider, evm_config }
+    }
+}
+
+#[async_trait]
+impl<Provider> TraverseEvmConfigApiServer for TraverseEvmConfigExt<Provider>
+where
+    Provider: HeaderProvider<Header = Header> + BlockNumReader + 'static,
+{
+    async fn get_evm_config(&self) -> RpcResult<EvmConfigInfo> {
+        trace!(target: "rpc::traverse", "Serving traverse_getEvmConfig");
+
+        let header = self
+            .provider
+            .best_block_number()
+            .and_then(|number| self.provider.header_by_number(number))
+            .map_err(EthApiError::from)?
+            .ok_or(EthApiError::HeaderNotFound(BlockId::latest()))?;
+
+        Ok(self.evm_config.info(&header))
+    }
+}
+
+/// The limits of a subpool of the transaction pool.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubPoolLimitInfo {
+    /// The maximum number of transactions in the subpool.
+    pub max_txs: usize,
+    /// The maximum size of the transactions in the subpool, in bytes.
+    pub max_size: usize,
+}
+
+impl From<SubPoolLimit> for SubPoolLimitInfo {
+    fn from(limit: SubPoolLimit) -> Self {
+        Self { max_txs: limit.max_txs, max_size: limit.max_size }
+    }
+}
+
+/// The effective limits of the transaction pool, as returned by `traverse_getPoolConfig`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PoolConfigInfo {
+    /// The limits of the pending subpool.
+    pub pending_limit: SubPoolLimitInfo,
+    /// The limits of the basefee subpool.
+    pub basefee_limit: SubPoolLimitInfo,
+    /// The limits of the queued subpool.
+    pub queued_limit: SubPoolLimitInfo,
+    /// The limits of the blob subpool.
+    pub blob_limit: SubPoolLimitInfo,
+    /// The maximum number of transactions of a single sender in the pool.
+    pub max_account_slots: usize,
+}
+
+impl From<&PoolConfig> for PoolConfigInfo {
+    fn from(config: &PoolConfig) -> Self {
+        Self {
+            pending_limit: config.pending_limit.into(),
+            basefee_limit: config.basefee_limit.into(),
+            queued_limit: config.queued_limit.into(),
+            blob_limit: config.blob_limit.into(),
+            max_account_slots: config.max_account_slots,
+        }
+    }
+}
+
+/// Traverse `traverse_` RPC namespace for introspecting the transaction pool configuration.
+#[cfg_attr(not(test), rpc(server, namespace = "traverse"))]
+#[cfg_attr(test, rpc(server, client, namespace = "traverse"))]
+pub trait TraversePoolConfigApi {
+    /// Returns the effective limits of the transaction pool.
+    #[method(name = "getPoolConfig")]
+    async fn get_pool_config(&self) -> RpcResult<PoolConfigInfo>;
+}
+
+/// Implementation of `traverse_getPoolConfig`.
+#[derive(Debug)]
+pub struct TraversePoolConfigExt {
+    config: PoolConfigInfo,
+}
+
+impl TraversePoolConfigExt {
+    /// Create a new `TraversePoolConfigExt` module serving the given pool configuration.
+    pub fn new(config: &PoolConfig) -> Self {
+        Self { config: config.into() }
+    }
+}
+
+#[async_trait]
+impl TraversePoolConfigApiServer for TraversePoolConfigExt {
+    async fn get_pool_config(&self) -> RpcResult<PoolConfigInfo> {
+        trace!(target: "rpc::traverse", "Serving traverse_getPoolConfig");
+        Ok(self.config)
+    }
+}
+
+/// The default number of proofs computed concurrently by `traverse_getProofs`.
+pub const DEFAULT_PROOF_PARALLELISM: usize = 4;
+
+/// The default number of proofs computed concurrently across all `traverse_getProofs` calls.
+pub const DEFAULT_MAX_CONCURRENT_PROOFS: usize = 4;
+
+/// The default maximum number of storage keys of a single proof.
+pub const DEFAULT_MAX_PROOF_KEYS: usize = 1024;
+
 /// Implementation of the `eth_` namespace override
 #[derive(Debug)]
 pub struct EthApiExt<Eth> {
     eth_api: Eth,
+    /// The number of proofs computed concurrently by `traverse_getProofs`.
+    proof_parallelism: usize,
+    /// The permits of the proofs computed concurrently across all `traverse_getProofs` calls.
+    proof_permits: Arc<Semaphore>,
+    /// Whether `eth_getProof` returns storage-only proofs for the storage proof addresses.
+    storage_only_proofs: bool,
+    /// The addresses for which `eth_getProof` only returns the storage proofs.
+    storage_proof_addresses: HashSet<Address>,
+    /// Whether storage-only proofs are verified against their storage root before they are
+    /// returned.
+    verify_proofs: bool,
+    /// The number of keys of each storage multiproof of a storage-only proof, all keys are proven
+    /// at once if this is not set.
+    proof_batch_size: Option<usize>,
+    /// The maximum number of storage keys of a single proof.
+    max_proof_keys: usize,
 }
 
 impl<E> EthApiExt<E> {
     /// Create a new `EthApiExt` module.
-    pub const fn new(eth_api: E) -> Self {
-        Self { eth_api }
+    ///
+    /// Only the withdrawal contract is served with storage-only proofs by default.
+    pub fn new(eth_api: E) -> Self {
+        Self {
+            eth_api,
+            proof_parallelism: DEFAULT_PROOF_PARALLELISM,
+            proof_permits: Arc::new(Semaphore::new(DEFAULT_MAX_CONCURRENT_PROOFS)),
+            storage_only_proofs: true,
+            storage_proof_addresses: HashSet::from([WITHDRAWAL_CONTRACT]),
+            verify_proofs: false,
+            proof_batch_size: None,
+            max_proof_keys: DEFAULT_MAX_PROOF_KEYS,
+        }
+    }
+
+    /// Sets the number of proofs computed concurrently by `traverse_getProofs`.
+    pub const fn with_proof_parallelism(mut self, proof_parallelism: usize) -> Self {
+        self.proof_parallelism = proof_parallelism;
+        self
+    }
+
+    /// Sets the number of proofs computed concurrently across all `traverse_getProofs` calls.
+    ///
+    /// Each proof is computed on the blocking pool, so a higher bound serves large batches
+    /// faster, at the cost of starving other blocking tasks, e.g. `eth_call`s, while concurrent
+    /// batches are served. A bound of 0 is treated as 1.
+    pub fn with_max_concurrent_proofs(mut self, max_concurrent_proofs: usize) -> Self {
+        self.proof_permits = Arc::new(Semaphore::new(max_concurrent_proofs.max(1)));
+        self
+    }
+
+    /// Sets whether `eth_getProof` returns storage-only proofs for the storage proof addresses.
+    ///
+    /// This is on by default. If disabled, every proof is served by [`EthState::get_proof`], with
+    /// the account proof and fields.
+    pub const fn with_storage_only_proofs(mut self, storage_only_proofs: bool) -> Self {
+        self.storage_only_proofs = storage_only_proofs;
+        self
+    }
+
+    /// Returns whether a storage-only proof is served for the given address.
+    fn is_storage_only(&self, address: &Address) -> bool {
+        self.storage_only_proofs && self.storage_proof_addresses.contains(address)
+    }
+
+    /// Sets the addresses for which `eth_getProof` only returns the storage proofs.
+    pub fn with_storage_proof_addresses(
+        mut self,
+        addresses: impl IntoIterator<Item = Address>,
+    ) -> Self {
+        self.storage_proof_addresses = addresses.into_iter().collect();
+        self
+    }
+
+    /// Sets whether storage-only proofs are verified against their storage root before they are
+    /// returned.
+    ///
+    /// This is off by default. If a proof does not verify, an internal error is returned instead
+    /// of the proof.
+    pub const fn with_proof_verification(mut self, verify_proofs: bool) -> Self {
+        self.verify_proofs = verify_proofs;
+        self
+    }
+
+    /// Sets the number of keys of each storage multiproof computed for a storage-only proof.
+    ///
+    /// By default, the storage proofs of all keys are computed in a single multiproof. With a
+    /// batch size, the keys are chunked, a multiproof is computed for each chunk, and the storage
+    /// proofs are merged in the order of the keys. Whether batching pays off depends on the size
+    /// of the storage trie and of the key set, the `proof` benchmark of this crate measures the
+    /// latency of both across key set sizes, see `benches/proof.rs`.
+    pub const fn with_proof_batch_size(mut self, batch_size: usize) -> Self {
+        self.proof_batch_size = Some(batch_size);
+        self
+    }
+
+    /// Sets the maximum number of storage keys of a single proof.
+    ///
+    /// Proofs requesting more keys are rejected with an invalid params error, before any proof
+    /// is computed. Defaults to [`DEFAULT_MAX_PROOF_KEYS`].
+    pub const fn with_max_proof_keys(mut self, max_proof_keys: usize) -> Self {
+        self.max_proof_keys = max_proof_keys;
+        self
+    }
+
+    /// Returns an error if the given storage keys exceed the maximum number of keys of a proof.
+    fn ensure_proof_keys(&self, keys: &[JsonStorageKey]) -> Result<(), EthApiError> {
+        if keys.len() > self.max_proof_keys {
+            return Err(EthApiError::InvalidParams(format!(
+                "too many storage keys: {} exceeds the maximum of {}",
+                keys.len(),
+                self.max_proof_keys
+            )));
+        }
+        Ok(())
     }
 }
 
-#[async_trait]
-impl<Eth> EthApiOverrideServer for EthApiExt<Eth>
+impl<Eth> EthApiExt<Eth>
 where
     Eth: FullEthApi + Send + Sync + 'static,
 {
-    async fn get_proof(
+    /// Returns the proof for the given account and storage keys.
+    ///
+    /// If we are targeting one of the storage proof addresses, only the storage proofs are
+    /// returned.
+    async fn proof(
         &self,
         address: Address,
         keys: Vec<JsonStorageKey>,
         block_number: Option<BlockId>,
     ) -> RpcResult<EIP1186AccountProofResponse> {
-        trace!(target: "rpc::eth", ?address, ?keys, ?block_number, "Serving eth_getProof");
+        self.ensure_proof_keys(&keys)?;
 
-        // If we are targeting the withdrawal contract, then we only need to provide the storage
-        // proofs for withdrawal.
-        if address == WITHDRAWAL_CONTRACT {
+        // If we are targeting a storage proof address, e.g. the withdrawal contract, then we only
+        // need to provide the storage proofs for withdrawal.
+        if self.is_storage_only(&address) {
             let _permit = self
                 .eth_api
                 .acquire_owned()
@@ -74,36 +368,518 @@ where
                 .map_err(RethError::other)
                 .map_err(EthApiError::Internal)?;
 
-            return self
+            let b256_keys: Vec<B256> = keys.iter().map(|k| k.as_b256()).collect();
+            let verify_proofs = self.verify_proofs;
+            let batch_size = self.proof_batch_size;
+            let block_id = block_number.unwrap_or_default();
+            let account_proof = self
                 .eth_api
                 .spawn_blocking_io(move |this| {
-                    let b256_keys: Vec<B256> = keys.iter().map(|k| k.as_b256()).collect();
-                    let state = this.state_at_block_id(block_number.unwrap_or_default())?;
-
-                    let proofs = state
-                        .storage_multiproof(WITHDRAWAL_CONTRACT, &b256_keys, Default::default())
-                        .map_err(EthApiError::from_eth_err)?;
-
-                    let account_proof = AccountProof {
-                        address,
-                        storage_root: proofs.root,
-                        storage_proofs: b256_keys
-                            .into_iter()
-                            .map(|k| proofs.storage_proof(k))
-                            .collect::<Result<_, _>>()
-                            .map_err(RethError::other)
-                            .map_err(EthApiError::Internal)?,
-                        ..Default::default()
-                    };
-                    Ok(account_proof.into_eip1186_response(keys))
+                    let state = state_at_block_id(block_id, |id| this.state_at_block_id(id))?;
+                    let account_proof =
+                        storage_only_proof(&state, address, &b256_keys, batch_size)?;
+                    if let Some(account_proof) = account_proof.as_ref().filter(|_| verify_proofs) {
+                        verify_storage_proofs(account_proof)?;
+                    }
+                    Ok(account_proof)
                 })
                 .await
-                .map_err(Into::into);
+                .map_err(Into::into)?;
+
+            if let Some(account_proof) = account_proof {
+                return Ok(account_proof.into_eip1186_response(keys));
+            }
+
+            // the contract does not exist, a storage-only proof would carry a default storage
+            // root, so we prove the absence of the account instead
         }
 
+        self.full_proof(address, keys, block_number).await
+    }
+
+    /// Returns the complete proof for the given account and storage keys, with the account proof
+    /// and fields, regardless of the storage proof addresses.
+    async fn full_proof(
+        &self,
+        address: Address,
+        keys: Vec<JsonStorageKey>,
+        block_number: Option<BlockId>,
+    ) -> RpcResult<EIP1186AccountProofResponse> {
         EthState::get_proof(&self.eth_api, address, keys, block_number)
             .map_err(Into::into)?
             .await
             .map_err(Into::into)
     }
 }
+
+/// Returns the state at the given block id with the given state lookup.
+///
+/// Block hashes are looked up like block numbers and tags, so they are served by the storage-only
+/// fast path too. An unknown block is reported as not found, and if the state of a known block
+/// hash is unavailable, e.g. pruned, the error names the block hash instead of an internal error.
+/// The standard proof resolves block hashes the same way, see [`EthState::get_proof`].
+fn state_at_block_id<S, E>(
+    block_id: BlockId,
+    state_at: impl FnOnce(BlockId) -> Result<S, E>,
+) -> Result<S, E>
+where
+    E: FromEthApiError + AsEthApiError,
+{
+    let BlockId::Hash(hash) = block_id else { return state_at(block_id) };
+    state_at(block_id).map_err(|err| match err.as_err() {
+        Some(EthApiError::HeaderNotFound(_)) => err,
+        _ => E::from_eth_err(EthApiError::InvalidParams(format!(
+            "state of block {} is unavailable",
+            hash.block_hash
+        ))),
+    })
+}
+
+/// Returns the storage-only proof of the given contract for the given keys, computing a
+/// multiproof for each batch of keys if a batch size is given.
+///
+/// Returns `None` if the contract does not exist in the given state.
+fn storage_only_proof(
+    state: &impl StateProvider,
+    address: Address,
+    keys: &[B256],
+    batch_size: Option<usize>,
+) -> Result<Option<AccountProof>, EthApiError> {
+    if state.basic_account(address).map_err(EthApiError::from_eth_err)?.is_none() {
+        return Ok(None);
+    }
+
+    batched_storage_only_proof(address, keys, batch_size, |keys| {
+        state
+            .storage_multiproof(address, keys, Default::default())
+            .map_err(EthApiError::from_eth_err)
+    })
+    .map(Some)
+}
+
+/// Builds the storage-only proof of the given contract from the multiproofs of the given keys,
+/// in chunks of the given batch size.
+///
+/// The storage proofs are in the order of the keys, like a single multiproof of all keys. All keys
+/// are proven at once if no batch size is given.
+pub fn batched_storage_only_proof(
+    address: Address,
+    keys: &[B256],
+    batch_size: Option<usize>,
+    mut multiproof: impl FnMut(&[B256]) -> Result<StorageMultiProof, EthApiError>,
+) -> Result<AccountProof, EthApiError> {
+    let mut chunks = keys.chunks(batch_size.unwrap_or(keys.len()).max(1));
+    // the storage root is proven even without keys
+    let first = chunks.next().unwrap_or_default();
+    let mut account_proof = storage_only_account_proof(address, &multiproof(first)?, first)?;
+    for chunk in chunks {
+        let proof = storage_only_account_proof(address, &multiproof(chunk)?, chunk)?;
+        account_proof.storage_proofs.extend(proof.storage_proofs);
+    }
+    Ok(account_proof)
+}
+
+/// Builds an account proof carrying only the storage proofs of the given keys, without the
+/// account proof nor the account fields.
+fn storage_only_account_proof(
+    address: Address,
+    proofs: &StorageMultiProof,
+    keys: &[B256],
+) -> Result<AccountProof, EthApiError> {
+    Ok(AccountProof {
+        address,
+        storage_root: proofs.root,
+        storage_proofs: keys
+            .iter()
+            .map(|k| proofs.storage_proof(*k))
+            .collect::<Result<_, _>>()
+            .map_err(RethError::other)
+            .map_err(EthApiError::Internal)?,
+        ..Default::default()
+    })
+}
+
+/// Verifies each storage proof of the given account proof against its storage root.
+fn verify_storage_proofs(proof: &AccountProof) -> Result<(), EthApiError> {
+    for storage_proof in &proof.storage_proofs {
+        storage_proof.verify(proof.storage_root).map_err(|err| {
+            EthApiError::Internal(RethError::msg(format!(
+                "invalid storage proof of {} for key {}: {err}",
+                proof.address, storage_proof.key
+            )))
+        })?;
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl<Eth> EthApiOverrideServer for EthApiExt<Eth>
+where
+    Eth: FullEthApi + Send + Sync + 'static,
+{
+    async fn get_proof(
+        &self,
+        address: Address,
+        keys: Vec<JsonStorageKey>,
+        block_number: Option<BlockId>,
+    ) -> RpcResult<EIP1186AccountProofResponse> {
+        trace!(target: "rpc::eth", ?address, ?keys, ?block_number, "Serving eth_getProof");
+        self.proof(address, keys, block_number).await
+    }
+}
+
+#[async_trait]
+impl<Eth> TraverseProofApiServer for EthApiExt<Eth>
+where
+    Eth: FullEthApi + Send + Sync + 'static,
+{
+    async fn get_proofs(
+        &self,
+        requests: Vec<ProofRequest>,
+    ) -> RpcResult<Vec<EIP1186AccountProofResponse>> {
+        trace!(target: "rpc::traverse", ?requests, "Serving traverse_getProofs");
+
+        bounded_proofs(
+            requests,
+            self.proof_parallelism,
+            &self.proof_permits,
+            |(address, keys, block_number)| self.proof(address, keys, block_number),
+        )
+        .await
+    }
+
+    async fn get_full_proof(
+        &self,
+        address: Address,
+        keys: Vec<JsonStorageKey>,
+        block_number: Option<BlockId>,
+    ) -> RpcResult<EIP1186AccountProofResponse> {
+        trace!(
+            target: "rpc::traverse",
+            ?address,
+            ?keys,
+            ?block_number,
+            "Serving traverse_getFullProof"
+        );
+        self.ensure_proof_keys(&keys)?;
+        self.full_proof(address, keys, block_number).await
+    }
+}
+
+/// Computes the proofs of the given requests in the same order, polling at most `parallelism`
+/// proofs at once.
+///
+/// Each proof holds a permit of the given semaphore while it is computed, which bounds the proofs
+/// in flight across concurrent batches.
+async fn bounded_proofs<R, T, E, Fut>(
+    requests: Vec<R>,
+    parallelism: usize,
+    permits: &Semaphore,
+    proof: impl Fn(R) -> Fut,
+) -> Result<Vec<T>, E>
+where
+    Fut: Future<Output = Result<T, E>>,
+{
+    let proof = &proof;
+    futures::stream::iter(requests)
+        .map(|request| async move {
+            let _permit = permits.acquire().await.expect("proof permits are never closed");
+            proof(request).await
+        })
+        .buffered(parallelism.max(1))
+        .try_collect()
+        .await
+}
+
+/// Helpers to compute storage multiproofs without a database, for tests and benchmarks.
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils {
+    use alloy_primitives::{keccak256, B256};
+    use reth_trie_common::{proof::ProofRetainer, HashBuilder, Nibbles, StorageMultiProof};
+    use std::collections::BTreeMap;
+
+    /// Returns the leaves of the storage trie of the given storage, sorted by their path.
+    ///
+    /// The values must be below `0x80`, so that they are RLP encoded as a single byte.
+    pub fn storage_leaves(storage: &BTreeMap<B256, u8>) -> Vec<(Nibbles, [u8; 1])> {
+        let mut leaves: Vec<_> = storage
+            .iter()
+            .map(|(slot, value)| (Nibbles::unpack(keccak256(slot)), [*value]))
+            .collect();
+        leaves.sort_unstable();
+        leaves
+    }
+
+    /// Computes the storage multiproof of the given keys in the trie of the given leaves, see
+    /// [`storage_leaves`].
+    ///
+    /// The whole trie is walked, like a multiproof of the state does.
+    pub fn storage_multiproof(leaves: &[(Nibbles, [u8; 1])], keys: &[B256]) -> StorageMultiProof {
+        let targets = keys.iter().map(|key| Nibbles::unpack(keccak256(key)));
+        let mut hash_builder =
+            HashBuilder::default().with_proof_retainer(ProofRetainer::from_iter(targets));
+        for (path, value) in leaves {
+            hash_builder.add_leaf(path.clone(), value);
+        }
+
+        let mut proof = StorageMultiProof::empty();
+        proof.root = hash_builder.root();
+        proof.subtree = hash_builder.take_proof_nodes();
+        proof
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        batched_storage_only_proof, bounded_proofs, state_at_block_id, storage_only_account_proof,
+        storage_only_proof,
+        test_utils::{storage_leaves, storage_multiproof},
+        verify_storage_proofs, EthApiExt, TraversePoolConfigApiServer, TraversePoolConfigExt,
+        DEFAULT_MAX_PROOF_KEYS,
+    };
+    use crate::node::TraverseNode;
+    use alloy_eips::BlockId;
+    use alloy_primitives::{address, Address, B256, U256};
+    use alloy_rpc_types::serde_helpers::JsonStorageKey;
+    use jsonrpsee::types::{error::INVALID_PARAMS_CODE, ErrorObject};
+    use reth_errors::RethError;
+    use reth_primitives_traits::Account;
+    use reth_revm::test_utils::StateProviderTest;
+    use reth_rpc_eth_types::EthApiError;
+    use reth_storage_api::AccountReader;
+    use reth_transaction_pool::{PoolConfig, SubPoolLimit, TXPOOL_MAX_ACCOUNT_SLOTS_PER_SENDER};
+    use reth_trie_common::StorageMultiProof;
+    use std::{
+        collections::{BTreeMap, HashMap},
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+    use traverse_common::WITHDRAWAL_CONTRACT;
+
+    /// The `L2ToL1MessagePasser` predeploy.
+    const MESSAGE_PASSER: Address = address!("4200000000000000000000000000000000000016");
+
+    #[test]
+    fn withdrawal_contract_absent() {
+        let state = StateProviderTest::default();
+        let proof = storage_only_proof(&state, WITHDRAWAL_CONTRACT, &[B256::ZERO], None).unwrap();
+        assert!(proof.is_none());
+    }
+
+    #[test]
+    fn state_at_block_hash() {
+        let block_hash = B256::with_last_byte(1);
+        let pruned_hash = B256::with_last_byte(2);
+        let mut state = StateProviderTest::default();
+        let account = Account { nonce: 1, ..Default::default() };
+        state.insert_account(MESSAGE_PASSER, account, None, Default::default());
+        let states = HashMap::from([(block_hash, state)]);
+        let state_at = |block_id: BlockId| match block_id {
+            BlockId::Hash(hash) if hash.block_hash == pruned_hash => {
+                Err(EthApiError::Internal(RethError::msg("state pruned")))
+            }
+            BlockId::Hash(hash) => {
+                states.get(&hash.block_hash).ok_or(EthApiError::HeaderNotFound(block_id))
+            }
+            BlockId::Number(_) => Ok(&states[&block_hash]),
+        };
+
+        // the state of a block hash serves the storage-only proof
+        let state = state_at_block_id(BlockId::hash(block_hash), state_at).unwrap();
+        assert_eq!(state.basic_account(MESSAGE_PASSER).unwrap(), Some(account));
+        let proof = storage_only_proof(state, WITHDRAWAL_CONTRACT, &[B256::ZERO], None).unwrap();
+        assert!(proof.is_none());
+
+        // an unknown block is not found
+        let unknown = BlockId::hash(B256::with_last_byte(3));
+        let err = state_at_block_id(unknown, state_at).unwrap_err();
+        assert!(matches!(err, EthApiError::HeaderNotFound(id) if id == unknown));
+
+        // the unavailable state of a known block names the block hash
+        let err = state_at_block_id(BlockId::hash(pruned_hash), state_at).unwrap_err();
+        assert!(
+            matches!(err, EthApiError::InvalidParams(msg) if msg.contains(&pruned_hash.to_string()))
+        );
+
+        // block numbers and tags are looked up as is
+        assert!(state_at_block_id(BlockId::latest(), state_at).is_ok());
+    }
+
+    #[test]
+    fn message_passer_storage_only_proof() {
+        let ext = EthApiExt::new(());
+        assert!(ext.storage_proof_addresses.contains(&MESSAGE_PASSER));
+
+        let keys = [B256::ZERO, B256::with_last_byte(1)];
+        let proof =
+            storage_only_account_proof(MESSAGE_PASSER, &StorageMultiProof::empty(), &keys).unwrap();
+
+        assert_eq!(proof.address, MESSAGE_PASSER);
+        assert!(proof.proof.is_empty());
+        assert!(proof.info.is_none());
+        assert_eq!(proof.storage_proofs.len(), keys.len());
+        for (storage_proof, key) in proof.storage_proofs.iter().zip(keys) {
+            assert_eq!(storage_proof.key, key);
+        }
+    }
+
+    #[test]
+    fn storage_proof_addresses_configurable() {
+        let other = address!("4200000000000000000000000000000000000007");
+        let ext = EthApiExt::new(()).with_storage_proof_addresses([WITHDRAWAL_CONTRACT, other]);
+        assert!(ext.storage_proof_addresses.contains(&WITHDRAWAL_CONTRACT));
+        assert!(ext.storage_proof_addresses.contains(&other));
+    }
+
+    #[test]
+    fn storage_only_proofs_toggle() {
+        let keys = [B256::ZERO];
+        let json_keys: Vec<_> = keys.iter().copied().map(JsonStorageKey::from).collect();
+
+        // storage-only proofs carry no account proof
+        let ext = EthApiExt::new(());
+        assert!(ext.is_storage_only(&WITHDRAWAL_CONTRACT));
+        let response =
+            storage_only_account_proof(WITHDRAWAL_CONTRACT, &StorageMultiProof::empty(), &keys)
+                .unwrap()
+                .into_eip1186_response(json_keys);
+        assert!(response.account_proof.is_empty());
+        assert_eq!(response.storage_proof.len(), keys.len());
+
+        // disabled, the withdrawal contract is served by the standard proof
+        let ext = EthApiExt::new(()).with_storage_only_proofs(false);
+        assert!(!ext.is_storage_only(&WITHDRAWAL_CONTRACT));
+        assert!(!ext.is_storage_only(&MESSAGE_PASSER));
+    }
+
+    #[test]
+    fn max_proof_keys() {
+        let keys = |len: usize| vec![JsonStorageKey::from(B256::ZERO); len];
+
+        let ext = EthApiExt::new(());
+        assert!(ext.ensure_proof_keys(&keys(DEFAULT_MAX_PROOF_KEYS)).is_ok());
+
+        // a custom low cap rejects larger key sets with invalid params
+        let ext = EthApiExt::new(()).with_max_proof_keys(2);
+        assert!(ext.ensure_proof_keys(&[]).is_ok());
+        assert!(ext.ensure_proof_keys(&keys(2)).is_ok());
+        let err = ErrorObject::from(ext.ensure_proof_keys(&keys(3)).unwrap_err());
+        assert_eq!(err.code(), INVALID_PARAMS_CODE);
+    }
+
+    #[tokio::test]
+    async fn pool_config() {
+        let config = TraverseNode::pool_config_overrides().apply(PoolConfig::default());
+        let info = TraversePoolConfigExt::new(&config).get_pool_config().await.unwrap();
+
+        // the subpool limits and account slots of the node are doubled
+        let limit = SubPoolLimit::default() * 2;
+        for subpool in [info.pending_limit, info.basefee_limit, info.queued_limit] {
+            assert_eq!(subpool.max_txs, limit.max_txs);
+            assert_eq!(subpool.max_size, limit.max_size);
+        }
+        assert_eq!(info.blob_limit.max_txs, PoolConfig::default().blob_limit.max_txs);
+        assert_eq!(info.max_account_slots, TXPOOL_MAX_ACCOUNT_SLOTS_PER_SENDER * 2);
+
+        let json = serde_json::to_value(info).unwrap();
+        assert_eq!(json["pendingLimit"]["maxTxs"], limit.max_txs);
+        assert_eq!(json["maxAccountSlots"], TXPOOL_MAX_ACCOUNT_SLOTS_PER_SENDER * 2);
+    }
+
+    #[test]
+    fn corrupted_storage_proof() {
+        let ext = EthApiExt::new(()).with_proof_verification(true);
+        assert!(ext.verify_proofs);
+
+        let keys = [B256::ZERO, B256::with_last_byte(1)];
+        let mut proof =
+            storage_only_account_proof(MESSAGE_PASSER, &StorageMultiProof::empty(), &keys).unwrap();
+        verify_storage_proofs(&proof).unwrap();
+
+        // the value is not in the trie
+        proof.storage_proofs[1].value = U256::from(1);
+        assert!(verify_storage_proofs(&proof).is_err());
+    }
+
+    /// Computes the storage multiproof of the given keys in a trie of the given storage.
+    fn multiproof(storage: &BTreeMap<B256, u8>, keys: &[B256]) -> StorageMultiProof {
+        storage_multiproof(&storage_leaves(storage), keys)
+    }
+
+    #[test]
+    fn batched_storage_proofs() {
+        let storage: BTreeMap<_, _> = (1..=64u8).map(|i| (B256::with_last_byte(i), i)).collect();
+        // existing slots out of order, and slots that are not set
+        let keys: Vec<_> = (0..=70u8).rev().step_by(3).map(B256::with_last_byte).collect();
+
+        let single = batched_storage_only_proof(MESSAGE_PASSER, &keys, None, |keys| {
+            Ok(multiproof(&storage, keys))
+        })
+        .unwrap();
+        verify_storage_proofs(&single).unwrap();
+
+        for batch_size in [1, 4, 7, keys.len(), keys.len() + 1] {
+            let mut batches = 0;
+            let batched =
+                batched_storage_only_proof(MESSAGE_PASSER, &keys, Some(batch_size), |keys| {
+                    batches += 1;
+                    Ok(multiproof(&storage, keys))
+                })
+                .unwrap();
+            assert_eq!(batches, keys.len().div_ceil(batch_size));
+            assert_eq!(batched, single);
+            verify_storage_proofs(&batched).unwrap();
+
+            let json_keys: Vec<_> = keys.iter().copied().map(JsonStorageKey::from).collect();
+            assert_eq!(
+                batched.into_eip1186_response(json_keys.clone()),
+                single.clone().into_eip1186_response(json_keys)
+            );
+        }
+
+        for (proof, key) in single.storage_proofs.iter().zip(&keys) {
+            assert_eq!(proof.key, *key);
+            assert_eq!(proof.value, U256::from(storage.get(key).copied().unwrap_or_default()));
+        }
+
+        // the storage root is proven without keys
+        let empty = batched_storage_only_proof(MESSAGE_PASSER, &[], Some(4), |keys| {
+            Ok(multiproof(&storage, keys))
+        })
+        .unwrap();
+        assert_eq!(empty.storage_root, single.storage_root);
+        assert!(empty.storage_proofs.is_empty());
+    }
+
+    #[tokio::test]
+    async fn max_concurrent_proofs() {
+        let ext = EthApiExt::new(()).with_max_concurrent_proofs(3);
+        assert_eq!(ext.proof_permits.available_permits(), 3);
+
+        let in_flight = AtomicUsize::new(0);
+        let max_in_flight = AtomicUsize::new(0);
+        let requests: Vec<u64> = (0..20).collect();
+
+        // two concurrent batches with more entries than the bound
+        let batch = || {
+            bounded_proofs(requests.clone(), 8, &ext.proof_permits, |request| {
+                let (in_flight, max_in_flight) = (&in_flight, &max_in_flight);
+                async move {
+                    let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                    max_in_flight.fetch_max(current, Ordering::SeqCst);
+                    tokio::time::sleep(Duration::from_millis(1)).await;
+                    in_flight.fetch_sub(1, Ordering::SeqCst);
+                    Ok::<_, ()>(request * 2)
+                }
+            })
+        };
+        let (first, second) = tokio::join!(batch(), batch());
+
+        let expected: Vec<u64> = requests.iter().map(|request| request * 2).collect();
+        assert_eq!(first.unwrap(), expected);
+        assert_eq!(second.unwrap(), expected);
+        assert!(max_in_flight.load(Ordering::SeqCst) <= 3);
+        assert_eq!(ext.proof_permits.available_permits(), 3);
+    }
+}
@@ -0,0 +1,304 @@
+//! Tracks the status of sponsored transactions.
+//!
+//! `traverse_` namespace:
+//!
+//! - `traverse_getSponsoredTransactionStatus` returns whether a sponsored transaction is pending or
+//!   mined. Transactions of blocks that are reorged out are pending again.
+
+use alloy_consensus::BlockHeader;
+use alloy_primitives::TxHash;
+use futures::{Stream, StreamExt};
+use jsonrpsee::{
+    core::{async_trait, RpcResult},
+    proc_macros::rpc,
+};
+use parking_lot::RwLock;
+use reth_chain_state::CanonStateNotification;
+use reth_node_api::NodePrimitives;
+use reth_primitives_traits::{BlockBody, SignedTransaction};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{hash_map::Entry, HashMap, VecDeque},
+    sync::Arc,
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{trace, warn};
+use traverse_wallet::SponsoredTxEvent;
+
+/// The maximum number of sponsored transactions tracked by the [`SponsoredTxStatusTracker`].
+///
+/// The statuses of the oldest transactions are dropped first.
+pub const SPONSORED_TX_STATUS_CAPACITY: usize = 10_000;
+
+/// The status of a sponsored transaction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum SponsoredTxStatus {
+    /// The transaction is not mined, or was mined in a block that was reorged out.
+    Pending,
+    /// The transaction is mined in a canonical block.
+    #[serde(rename_all = "camelCase")]
+    Mined {
+        /// The number of the block the transaction is mined in.
+        block_number: u64,
+    },
+}
+
+/// Traverse `traverse_` RPC namespace for the status of sponsored transactions.
+#[cfg_attr(not(test), rpc(server, namespace = "traverse"))]
+#[cfg_attr(test, rpc(server, client, namespace = "traverse"))]
+pub trait SponsoredTxStatusApi {
+    /// Returns the status of the given sponsored transaction, if it is tracked.
+    #[method(name = "getSponsoredTransactionStatus")]
+    async fn get_sponsored_transaction_status(
+        &self,
+        tx_hash: TxHash,
+    ) -> RpcResult<Option<SponsoredTxStatus>>;
+}
+
+/// Tracks the status of sponsored transactions across new canonical blocks and reorgs.
+#[derive(Debug, Clone, Default)]
+pub struct SponsoredTxStatusTracker {
+    inner: Arc<RwLock<SponsoredTxStatuses>>,
+}
+
+impl SponsoredTxStatusTracker {
+    /// Creates a new, empty tracker.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Tracks the sponsored transactions of the given receiver, and updates their status with the
+    /// given canonical state stream.
+    ///
+    /// The task ends once either the receiver is closed, or the stream ends.
+    pub fn spawn<St, N>(self, mut events: broadcast::Receiver<SponsoredTxEvent>, mut st: St)
+    where
+        St: Stream<Item = CanonStateNotification<N>> + Send + Unpin + 'static,
+        N: NodePrimitives,
+    {
+        tokio::task::spawn(async move {
+            loop {
+                tokio::select! {
+                    event = events.recv() => match event {
+                        Ok(event) => self.on_sponsored(event.tx_hash),
+                        Err(RecvError::Lagged(skipped)) => {
+                            warn!(target: "rpc::traverse", skipped, "Missed sponsored txs");
+                        }
+                        Err(RecvError::Closed) => break,
+                    },
+                    notification = st.next() => {
+                        let Some(notification) = notification else { break };
+                        self.on_canon_state_notification(&notification);
+                    }
+                }
+            }
+        });
+    }
+
+    /// Returns the status of the given transaction, if it is tracked.
+    pub fn status(&self, tx_hash: &TxHash) -> Option<SponsoredTxStatus> {
+        self.inner.read().statuses.get(tx_hash).copied()
+    }
+
+    /// Starts tracking the given sponsored transaction as pending.
+    ///
+    /// The status of a transaction that is already tracked is kept.
+    pub fn on_sponsored(&self, tx_hash: TxHash) {
+        self.inner.write().insert(tx_hash);
+    }
+
+    /// Updates the status of the tracked transactions with the given canonical state
+    /// notification.
+    ///
+    /// On a reorg, the transactions of the reverted blocks are pending again, unless they are
+    /// also included in the new canonical blocks.
+    pub fn on_canon_state_notification<N: NodePrimitives>(
+        &self,
+        notification: &CanonStateNotification<N>,
+    ) {
+        let reverted = notification.reverted();
+        let committed = notification.committed();
+        self.apply(
+            reverted.iter().flat_map(|chain| {
+                chain
+                    .blocks_iter()
+                    .flat_map(|block| block.body.transactions().iter().map(|tx| *tx.tx_hash()))
+            }),
+            committed.blocks_iter().flat_map(|block| {
+                let block_number = block.header().number();
+                block.body.transactions().iter().map(move |tx| (block_number, *tx.tx_hash()))
+            }),
+        );
+    }
+
+    /// Marks the transactions of reverted blocks as pending, then the transactions of committed
+    /// blocks as mined.
+    fn apply(
+        &self,
+        reverted: impl IntoIterator<Item = TxHash>,
+        committed: impl IntoIterator<Item = (u64, TxHash)>,
+    ) {
+        let mut inner = self.inner.write();
+        for tx_hash in reverted {
+            if let Some(status) = inner.statuses.get_mut(&tx_hash) {
+                trace!(target: "rpc::traverse", ?tx_hash, "Sponsored transaction reorged out");
+                *status = SponsoredTxStatus::Pending;
+            }
+        }
+        for (block_number, tx_hash) in committed {
+            if let Some(status) = inner.statuses.get_mut(&tx_hash) {
+                *status = SponsoredTxStatus::Mined { block_number };
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl SponsoredTxStatusApiServer for SponsoredTxStatusTracker {
+    async fn get_sponsored_transaction_status(
+        &self,
+        tx_hash: TxHash,
+    ) -> RpcResult<Option<SponsoredTxStatus>> {
+        trace!(target: "rpc::traverse", ?tx_hash, "Serving traverse_getSponsoredTransactionStatus");
+        Ok(self.status(&tx_hash))
+    }
+}
+
+/// The statuses of tracked transactions, in the order they were sponsored.
+#[derive(Debug, Default)]
+struct SponsoredTxStatuses {
+    statuses: HashMap<TxHash, SponsoredTxStatus>,
+    order: VecDeque<TxHash>,
+}
+
+impl SponsoredTxStatuses {
+    fn insert(&mut self, tx_hash: TxHash) {
+        match self.statuses.entry(tx_hash) {
+            Entry::Occupied(_) => return,
+            Entry::Vacant(entry) => {
+                entry.insert(SponsoredTxStatus::Pending);
+            }
+        }
+        self.order.push_back(tx_hash);
+        if self.order.len() > SPONSORED_TX_STATUS_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.statuses.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy_consensus::{Header, TxLegacy};
+    use alloy_primitives::{Address, PrimitiveSignature, B256};
+    use op_alloy_consensus::OpTypedTransaction;
+    use reth_optimism_primitives::{OpPrimitives, OpTransactionSigned};
+    use reth_primitives::{BlockBody, SealedBlock, SealedBlockWithSenders, SealedHeader};
+    use reth_provider::{Chain, ExecutionOutcome};
+
+    fn tx(nonce: u64) -> OpTransactionSigned {
+        OpTransactionSigned::new_unhashed(
+            OpTypedTransaction::Legacy(TxLegacy { nonce, ..Default::default() }),
+            PrimitiveSignature::test_signature(),
+        )
+    }
+
+    /// A chain of the given blocks, whose hashes are derived from the given fork to tell forks
+    /// apart.
+    fn chain(
+        fork: u8,
+        blocks: impl IntoIterator<Item = (u64, Vec<OpTransactionSigned>)>,
+    ) -> Arc<Chain<OpPrimitives>> {
+        let blocks = blocks.into_iter().map(|(number, transactions)| {
+            let header = Header { number, ..Default::default() };
+            let hash = B256::left_padding_from(&[fork, number as u8]);
+            let senders = vec![Address::ZERO; transactions.len()];
+            let body = BlockBody { transactions, ..Default::default() };
+            let block = SealedBlock::new(SealedHeader::new(header, hash), body);
+            SealedBlockWithSenders::new(block, senders).unwrap()
+        });
+        Arc::new(Chain::new(blocks, ExecutionOutcome::default(), None))
+    }
+
+    #[test]
+    fn reorg_reverts_mined_status() {
+        let tracker = SponsoredTxStatusTracker::new();
+        let tx_hash = TxHash::with_last_byte(1);
+        let untracked = TxHash::with_last_byte(2);
+
+        tracker.on_sponsored(tx_hash);
+        assert_eq!(tracker.status(&tx_hash), Some(SponsoredTxStatus::Pending));
+
+        // the transaction is mined
+        tracker.apply([], [(10, tx_hash), (10, untracked)]);
+        assert_eq!(tracker.status(&tx_hash), Some(SponsoredTxStatus::Mined { block_number: 10 }));
+        assert_eq!(tracker.status(&untracked), None);
+
+        // its block is reorged out, and the new block does not include it
+        tracker.apply([tx_hash], []);
+        assert_eq!(tracker.status(&tx_hash), Some(SponsoredTxStatus::Pending));
+
+        // the transaction is mined again in another block of the new chain
+        tracker.apply([], [(11, tx_hash)]);
+        assert_eq!(tracker.status(&tx_hash), Some(SponsoredTxStatus::Mined { block_number: 11 }));
+    }
+
+    #[test]
+    fn canon_state_notifications() {
+        let tracker = SponsoredTxStatusTracker::new();
+        let sponsored = tx(0);
+        let tx_hash = *sponsored.tx_hash();
+        tracker.on_sponsored(tx_hash);
+
+        // the transaction is mined
+        let mined = chain(0, [(10, vec![tx(1), sponsored.clone()])]);
+        tracker.on_canon_state_notification(&CanonStateNotification::Commit { new: mined.clone() });
+        assert_eq!(tracker.status(&tx_hash), Some(SponsoredTxStatus::Mined { block_number: 10 }));
+
+        // a late event of the sponsored transaction does not reset its status
+        tracker.on_sponsored(tx_hash);
+        assert_eq!(tracker.status(&tx_hash), Some(SponsoredTxStatus::Mined { block_number: 10 }));
+
+        // its block is reorged out, and the new block does not include it
+        tracker.on_canon_state_notification(&CanonStateNotification::Reorg {
+            old: mined,
+            new: chain(1, [(10, vec![tx(1)])]),
+        });
+        assert_eq!(tracker.status(&tx_hash), Some(SponsoredTxStatus::Pending));
+
+        // the transaction is mined again in the next block of the new chain
+        tracker.on_canon_state_notification(&CanonStateNotification::Commit {
+            new: chain(1, [(11, vec![sponsored])]),
+        });
+        assert_eq!(tracker.status(&tx_hash), Some(SponsoredTxStatus::Mined { block_number: 11 }));
+    }
+
+    #[test]
+    fn capacity() {
+        let tracker = SponsoredTxStatusTracker::new();
+        for i in 0..=SPONSORED_TX_STATUS_CAPACITY as u64 {
+            tracker.on_sponsored(TxHash::left_padding_from(&i.to_be_bytes()));
+        }
+
+        assert_eq!(tracker.status(&TxHash::ZERO), None);
+        assert_eq!(
+            tracker.status(&TxHash::left_padding_from(&1u64.to_be_bytes())),
+            Some(SponsoredTxStatus::Pending)
+        );
+    }
+
+    #[test]
+    fn status_serde() {
+        assert_eq!(
+            serde_json::to_value(SponsoredTxStatus::Mined { block_number: 1 }).unwrap(),
+            serde_json::json!({ "status": "mined", "blockNumber": 1 })
+        );
+        assert_eq!(
+            serde_json::to_value(SponsoredTxStatus::Pending).unwrap(),
+            serde_json::json!({ "status": "pending" })
+        );
+    }
+}
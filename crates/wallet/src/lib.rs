@@ -4,6 +4,13 @@
 //!
 //! - `traverse_sendTransaction` that can perform service-sponsored [EIP-7702][eip-7702] delegations
 //!   and send other service-sponsored transactions on behalf of EOAs with delegated code.
+//! - `wallet_traceSponsoredTransaction` that simulates a sponsored transaction with a call tracer
+//!   without sending it, to diagnose reverts.
+//! - `wallet_subscribeSponsoredTransactions` that streams the transactions sponsored by the service
+//!   to websocket subscribers.
+//!
+//! The `walletAdmin_` namespace lets operators pause and resume sponsoring, and rotate the sponsor
+//! signer without downtime.
 //!
 //! # Restrictions
 //!
@@ -16,29 +23,70 @@
 
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 
+use alloy_consensus::{SignableTransaction, TxEnvelope, TypedTransaction};
+use alloy_eips::eip7702::{constants::PER_EMPTY_ACCOUNT_COST, SignedAuthorization};
 use alloy_network::{
-    eip2718::Encodable2718, Ethereum, EthereumWallet, NetworkWallet, TransactionBuilder,
+    eip2718::{Decodable2718, Encodable2718},
+    Ethereum, EthereumWallet, NetworkWallet, TransactionBuilder,
+};
+use alloy_primitives::{
+    address, keccak256, Address, BlockNumber, Bytes, ChainId, TxHash, TxKind, B256, U256,
+};
+use alloy_provider::{
+    fillers::{FillProvider, JoinFill, RecommendedFillers, WalletFiller},
+    utils::Eip1559Estimation,
+    Identity, Provider, ProviderBuilder, RootProvider, WalletProvider,
+};
+use alloy_rpc_client::RpcClient;
+use alloy_rpc_types::{
+    trace::geth::{
+        CallConfig, CallFrame, GethDebugBuiltInTracerType, GethDebugTracingCallOptions,
+        GethDebugTracingOptions, TraceResult,
+    },
+    AccessListResult, BlockId, BlockNumberOrTag, FeeHistory, TransactionRequest,
 };
-use alloy_primitives::{Address, Bytes, ChainId, TxHash, TxKind, U256};
-use alloy_provider::{utils::Eip1559Estimation, Provider, WalletProvider};
-use alloy_rpc_types::{BlockId, TransactionRequest};
-use alloy_transport::Transport;
+use alloy_signer_local::PrivateKeySigner;
+use alloy_transport::{BoxTransport, RpcError, Transport, TransportError};
+use alloy_transport_http::Http;
+use futures::{Stream, StreamExt};
 use jsonrpsee::{
-    core::{async_trait, RpcResult},
+    core::{async_trait, RpcResult, SubscriptionResult},
     proc_macros::rpc,
+    PendingSubscriptionSink, SubscriptionMessage,
 };
 use metrics::Counter;
 use metrics_derive::Metrics;
 
-use reth_rpc_eth_api::helpers::{EthCall, EthTransactions, FullEthApi, LoadFee, LoadState};
-use reth_storage_api::StateProviderFactory;
+use reth_primitives::RecoveredTx;
+use reth_rpc_eth_api::helpers::{
+    Call, EthApiSpec, EthCall, EthFees, EthTransactions, FullEthApi, LoadFee, LoadState,
+};
+use reth_storage_api::{BlockNumReader, StateProviderFactory};
+use reth_transaction_pool::{PoolTransaction, TransactionOrigin, TransactionPool};
+use revm_inspectors::tracing::{TracingInspector, TracingInspectorConfig};
 use serde::{Deserialize, Serialize};
-use std::{marker::PhantomData, sync::Arc};
-use tracing::{trace, warn};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    future::Future,
+    marker::PhantomData,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tracing::{debug, trace, warn};
 
 use reth_optimism_primitives as _;
 use reth_optimism_rpc as _;
-use tokio::sync::Mutex;
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        Mutex,
+    },
+    time::Instant,
+};
+use url::Url;
 
 /// An upstream is capable of estimating, signing, and propagating signed transactions for a
 /// specific chain.
@@ -47,30 +95,205 @@ pub trait Upstream {
     /// Get the address of the account that sponsors transactions.
     fn default_signer_address(&self) -> Address;
 
+    /// Get the chain id of the upstream.
+    async fn chain_id(&self) -> Result<ChainId, TraverseWalletError>;
+
+    /// Get the pending nonce of the account that sponsors transactions.
+    async fn pending_nonce(&self) -> Result<u64, TraverseWalletError>;
+
     /// Get the code at a specific address.
     async fn get_code(&self, address: Address) -> Result<Bytes, TraverseWalletError>;
 
+    /// Get the block at which the code at a specific address last changed, if it changed within
+    /// the given number of recent blocks.
+    async fn code_changed_within(
+        &self,
+        address: Address,
+        blocks: u64,
+    ) -> Result<Option<BlockNumber>, TraverseWalletError>;
+
     /// Estimate the transaction request's gas usage and fees.
     async fn estimate(
         &self,
         tx: &TransactionRequest,
     ) -> Result<(u64, Eip1559Estimation), TraverseWalletError>;
 
+    /// Get the fee history of the given number of recent blocks, including the priority fees at
+    /// the given percentile.
+    async fn fee_history(
+        &self,
+        block_count: u64,
+        percentile: f64,
+    ) -> Result<FeeHistory, TraverseWalletError>;
+
+    /// Create an access list for the transaction request.
+    async fn create_access_list(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<AccessListResult, TraverseWalletError>;
+
     /// Sign the transaction request and send it to the upstream.
     async fn sign_and_send(&self, tx: TransactionRequest) -> Result<TxHash, TraverseWalletError>;
+
+    /// Estimate the L1 data fee of the transaction request, in wei.
+    ///
+    /// OP chains charge the L1 data fee on top of the L2 gas of a transaction. By default, there
+    /// is no L1 data fee.
+    async fn l1_data_fee(&self, _tx: &TransactionRequest) -> Result<U256, TraverseWalletError> {
+        Ok(U256::ZERO)
+    }
+
+    /// Simulate the transaction request at the latest block with a call tracer, without signing
+    /// or sending it.
+    ///
+    /// By default, the upstream does not support tracing.
+    async fn trace_call(
+        &self,
+        _tx: &TransactionRequest,
+    ) -> Result<TraceResult, TraverseWalletError> {
+        Err(TraverseWalletError::InternalError(eyre::eyre!("tracing is not supported")))
+    }
+
+    /// Replace the signer of sponsored transactions with the given wallet.
+    ///
+    /// This must not be called while a transaction is signed and sent, see
+    /// [`TraverseWallet::rotate_signer`]. By default, the signer cannot be rotated.
+    fn rotate_signer(&self, _signer: EthereumWallet) -> Result<(), TraverseWalletError> {
+        Err(TraverseWalletError::SignerRotationUnsupported)
+    }
+
+    /// Sign the transaction request and send it to the upstream, returning the encoded signed
+    /// transaction along with its hash, if the upstream has it.
+    ///
+    /// By default, this does not return the encoded transaction.
+    async fn sign_and_send_raw(
+        &self,
+        tx: TransactionRequest,
+    ) -> Result<(TxHash, Option<Bytes>), TraverseWalletError> {
+        self.sign_and_send(tx).await.map(|tx_hash| (tx_hash, None))
+    }
 }
 
 /// A wrapper around an Alloy provider for signing and sending sponsored transactions.
+///
+/// Read-only calls, e.g. code lookups, estimates and the fee history, can be retried on
+/// transport errors, see [`AlloyUpstream::with_retries`]. Signing and sending is never retried,
+/// to avoid submitting a transaction twice.
 #[derive(Debug)]
 pub struct AlloyUpstream<P, T> {
     provider: P,
+    /// The maximum number of retries of read-only calls.
+    max_retries: u32,
+    /// The delay before the first retry, which doubles with each retry.
+    base_delay: Duration,
     _transport: PhantomData<T>,
 }
 
 impl<P, T> AlloyUpstream<P, T> {
     /// Create a new [`AlloyUpstream`]
     pub const fn new(provider: P) -> Self {
-        Self { provider, _transport: PhantomData }
+        Self::with_retries(provider, 0, Duration::ZERO)
+    }
+
+    /// Create a new [`AlloyUpstream`] that retries read-only calls failing with a transport error
+    /// up to `max_retries` times, with an exponential backoff starting at `base_delay`.
+    pub const fn with_retries(provider: P, max_retries: u32, base_delay: Duration) -> Self {
+        Self { provider, max_retries, base_delay, _transport: PhantomData }
+    }
+
+    /// Returns the underlying provider.
+    pub const fn provider(&self) -> &P {
+        &self.provider
+    }
+}
+
+/// The HTTP provider used by [`AlloyUpstream::with_client_config`].
+pub type HttpWalletProvider = FillProvider<
+    JoinFill<JoinFill<Identity, RecommendedFillers>, WalletFiller<EthereumWallet>>,
+    RootProvider<BoxTransport>,
+    BoxTransport,
+    Ethereum,
+>;
+
+impl AlloyUpstream<HttpWalletProvider, BoxTransport> {
+    /// Create a new [`AlloyUpstream`] that connects to the given HTTP endpoint using a client
+    /// configured with the given [`HttpClientConfig`].
+    ///
+    /// Each sponsored transaction issues several requests to the upstream (code lookup, gas and
+    /// fee estimation, submission). Keeping idle connections alive lets these requests reuse an
+    /// established connection instead of paying for a new TCP (and TLS) handshake each time,
+    /// which is the dominant latency cost for remote upstreams.
+    pub fn with_client_config(
+        url: Url,
+        wallet: EthereumWallet,
+        config: HttpClientConfig,
+    ) -> Result<Self, TraverseWalletError> {
+        let client =
+            config.build().map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+        let rpc_client = RpcClient::new(Http::with_client(client, url), false).boxed();
+        let provider =
+            ProviderBuilder::new().with_recommended_fillers().wallet(wallet).on_client(rpc_client);
+
+        Ok(Self::new(provider))
+    }
+}
+
+/// Connection pool configuration for the HTTP client of an [`AlloyUpstream`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HttpClientConfig {
+    /// The maximum number of idle connections kept alive per host.
+    pub pool_max_idle_per_host: usize,
+    /// How long an idle connection is kept alive in the pool.
+    pub pool_idle_timeout: Duration,
+    /// The interval of TCP keep-alive probes, if enabled.
+    pub tcp_keepalive: Option<Duration>,
+}
+
+impl Default for HttpClientConfig {
+    fn default() -> Self {
+        Self {
+            pool_max_idle_per_host: 32,
+            pool_idle_timeout: Duration::from_secs(90),
+            tcp_keepalive: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl HttpClientConfig {
+    /// Builds a [`reqwest::Client`] with this configuration.
+    pub fn build(&self) -> reqwest::Result<reqwest::Client> {
+        reqwest::Client::builder()
+            .pool_max_idle_per_host(self.pool_max_idle_per_host)
+            .pool_idle_timeout(self.pool_idle_timeout)
+            .tcp_keepalive(self.tcp_keepalive)
+            .build()
+    }
+}
+
+impl<P, T> AlloyUpstream<P, T> {
+    /// Performs the read-only call, retrying it on transport errors.
+    ///
+    /// Error responses of the provider are returned immediately, since retrying them would yield
+    /// the same response.
+    async fn retry<R, F, Fut>(&self, mut call: F) -> Result<R, TransportError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<R, TransportError>>,
+    {
+        let mut retries = 0;
+        loop {
+            match call().await {
+                Err(err)
+                    if retries < self.max_retries && !matches!(err, RpcError::ErrorResp(_)) =>
+                {
+                    let delay = self.base_delay.saturating_mul(2u32.saturating_pow(retries));
+                    debug!(target: "rpc::wallet", ?err, ?delay, "Retrying upstream call");
+                    tokio::time::sleep(delay).await;
+                    retries += 1;
+                }
+                result => return result,
+            }
+        }
     }
 }
 
@@ -84,19 +307,55 @@ where
         self.provider.default_signer_address()
     }
 
+    async fn chain_id(&self) -> Result<ChainId, TraverseWalletError> {
+        self.retry(|| async { self.provider.get_chain_id().await })
+            .await
+            .map_err(|err| TraverseWalletError::InternalError(err.into()))
+    }
+
+    async fn pending_nonce(&self) -> Result<u64, TraverseWalletError> {
+        let sponsor = self.provider.default_signer_address();
+        self.retry(|| async {
+            self.provider.get_transaction_count(sponsor).block_id(BlockId::pending()).await
+        })
+        .await
+        .map_err(|err| TraverseWalletError::InternalError(err.into()))
+    }
+
     async fn get_code(&self, address: Address) -> Result<Bytes, TraverseWalletError> {
-        self.provider
-            .get_code_at(address)
+        self.retry(|| async { self.provider.get_code_at(address).await })
+            .await
+            .map_err(|err| TraverseWalletError::InternalError(err.into()))
+    }
+
+    async fn code_changed_within(
+        &self,
+        address: Address,
+        blocks: u64,
+    ) -> Result<Option<BlockNumber>, TraverseWalletError> {
+        let latest = self
+            .retry(|| async { self.provider.get_block_number().await })
+            .await
+            .map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+
+        last_code_change(latest, blocks, |number| async move {
+            self.retry(|| async {
+                self.provider.get_code_at(address).block_id(BlockId::number(number)).await
+            })
             .await
             .map_err(|err| TraverseWalletError::InternalError(err.into()))
+        })
+        .await
     }
 
     async fn estimate(
         &self,
         tx: &TransactionRequest,
     ) -> Result<(u64, Eip1559Estimation), TraverseWalletError> {
-        let (estimate, fee_estimate) =
-            tokio::join!(self.provider.estimate_gas(tx), self.provider.estimate_eip1559_fees(None));
+        let (estimate, fee_estimate) = tokio::join!(
+            self.retry(|| async { self.provider.estimate_gas(tx).await }),
+            self.retry(|| async { self.provider.estimate_eip1559_fees(None).await })
+        );
 
         Ok((
             estimate.map_err(|err| TraverseWalletError::InternalError(err.into()))?,
@@ -104,6 +363,29 @@ where
         ))
     }
 
+    async fn fee_history(
+        &self,
+        block_count: u64,
+        percentile: f64,
+    ) -> Result<FeeHistory, TraverseWalletError> {
+        self.retry(|| async {
+            self.provider
+                .get_fee_history(block_count, BlockNumberOrTag::Latest, &[percentile])
+                .await
+        })
+        .await
+        .map_err(|err| TraverseWalletError::InternalError(err.into()))
+    }
+
+    async fn create_access_list(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<AccessListResult, TraverseWalletError> {
+        self.retry(|| async { self.provider.create_access_list(tx).await })
+            .await
+            .map_err(|err| TraverseWalletError::InternalError(err.into()))
+    }
+
     async fn sign_and_send(&self, tx: TransactionRequest) -> Result<TxHash, TraverseWalletError> {
         self.provider
             .send_transaction(tx)
@@ -111,32 +393,146 @@ where
             .map_err(|err| TraverseWalletError::InternalError(err.into()))
             .map(|pending| *pending.tx_hash())
     }
+
+    async fn l1_data_fee(&self, tx: &TransactionRequest) -> Result<U256, TraverseWalletError> {
+        let call = l1_fee_call(tx)?;
+        let output = self
+            .retry(|| async { self.provider.call(&call).await })
+            .await
+            .map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+        decode_l1_fee(&output)
+    }
+
+    async fn trace_call(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<TraceResult, TraverseWalletError> {
+        let options = GethDebugTracingCallOptions {
+            tracing_options: GethDebugTracingOptions::default()
+                .with_tracer(GethDebugBuiltInTracerType::CallTracer.into())
+                .with_call_config(CallConfig::default()),
+            ..Default::default()
+        };
+        let frame: CallFrame = self
+            .retry(|| async {
+                self.provider
+                    .client()
+                    .request("debug_traceCall", (tx, BlockId::latest(), &options))
+                    .await
+            })
+            .await
+            .map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+        Ok(TraceResult::Success { result: frame.into(), tx_hash: None })
+    }
+}
+
+/// The signer of sponsored transactions.
+///
+/// This decouples the sponsor key from the [`RethUpstream`], e.g. to back it with an HSM or a
+/// remote signer.
+#[async_trait]
+pub trait SponsorSigner {
+    /// Returns the address of the sponsor.
+    fn address(&self) -> Address;
+
+    /// Builds and signs the transaction of the given request.
+    async fn sign(&self, tx: TransactionRequest) -> Result<TxEnvelope, TraverseWalletError>;
+
+    /// Replaces the signer with the given wallet.
+    ///
+    /// By default, the signer cannot be rotated, see [`RotatingSigner`].
+    fn rotate(&self, _wallet: EthereumWallet) -> Result<(), TraverseWalletError> {
+        Err(TraverseWalletError::SignerRotationUnsupported)
+    }
+}
+
+#[async_trait]
+impl SponsorSigner for EthereumWallet {
+    fn address(&self) -> Address {
+        NetworkWallet::<Ethereum>::default_signer_address(self)
+    }
+
+    async fn sign(&self, tx: TransactionRequest) -> Result<TxEnvelope, TraverseWalletError> {
+        <TransactionRequest as TransactionBuilder<Ethereum>>::build::<Self>(tx, self)
+            .await
+            .map_err(|err| TraverseWalletError::InternalError(err.into()))
+    }
+}
+
+/// A [`SponsorSigner`] whose wallet can be replaced at runtime, e.g. to rotate the sponsor key
+/// without restarting the node.
+#[derive(Debug)]
+pub struct RotatingSigner(parking_lot::RwLock<EthereumWallet>);
+
+impl RotatingSigner {
+    /// Create a new [`RotatingSigner`] that signs with the given wallet until it is rotated.
+    pub fn new(wallet: EthereumWallet) -> Self {
+        Self(parking_lot::RwLock::new(wallet))
+    }
+}
+
+#[async_trait]
+impl SponsorSigner for RotatingSigner {
+    fn address(&self) -> Address {
+        SponsorSigner::address(&*self.0.read())
+    }
+
+    async fn sign(&self, tx: TransactionRequest) -> Result<TxEnvelope, TraverseWalletError> {
+        let wallet = self.0.read().clone();
+        wallet.sign(tx).await
+    }
+
+    fn rotate(&self, wallet: EthereumWallet) -> Result<(), TraverseWalletError> {
+        *self.0.write() = wallet;
+        Ok(())
+    }
+}
+
+/// Signs the transaction of the given request with the given nonce.
+async fn sign_with_nonce<S: SponsorSigner + ?Sized>(
+    signer: &S,
+    mut tx: TransactionRequest,
+    nonce: u64,
+) -> Result<TxEnvelope, TraverseWalletError> {
+    tx.nonce = Some(nonce);
+    signer.sign(tx).await
 }
 
 /// A handle to a Reth upstream that signs transactions and injects them directly into the
 /// transaction pool.
 #[derive(Debug)]
-pub struct RethUpstream<Provider, Eth> {
+pub struct RethUpstream<Provider, Eth, Signer = EthereumWallet> {
     provider: Provider,
     eth_api: Eth,
-    wallet: EthereumWallet,
+    signer: Signer,
 }
 
-impl<Provider, Eth> RethUpstream<Provider, Eth> {
+impl<Provider, Eth, Signer> RethUpstream<Provider, Eth, Signer> {
     /// Create a new [`RethUpstream`].
-    pub const fn new(provider: Provider, eth_api: Eth, wallet: EthereumWallet) -> Self {
-        Self { provider, eth_api, wallet }
+    pub const fn new(provider: Provider, eth_api: Eth, signer: Signer) -> Self {
+        Self { provider, eth_api, signer }
     }
 }
 
 #[async_trait]
-impl<Provider, Eth> Upstream for RethUpstream<Provider, Eth>
+impl<Provider, Eth, Signer> Upstream for RethUpstream<Provider, Eth, Signer>
 where
     Provider: StateProviderFactory + Send + Sync,
     Eth: FullEthApi + Send + Sync,
+    Signer: SponsorSigner + Send + Sync,
 {
     fn default_signer_address(&self) -> Address {
-        NetworkWallet::<Ethereum>::default_signer_address(&self.wallet)
+        self.signer.address()
+    }
+
+    async fn chain_id(&self) -> Result<ChainId, TraverseWalletError> {
+        Ok(EthApiSpec::chain_id(&self.eth_api).to())
+    }
+
+    async fn pending_nonce(&self) -> Result<u64, TraverseWalletError> {
+        LoadState::next_available_nonce(&self.eth_api, self.signer.address())
+            .await
+            .map_err(|err| TraverseWalletError::InternalError(eyre::Report::new(err)))
     }
 
     async fn get_code(&self, address: Address) -> Result<Bytes, TraverseWalletError> {
@@ -151,6 +547,30 @@ where
             .unwrap_or_default())
     }
 
+    async fn code_changed_within(
+        &self,
+        address: Address,
+        blocks: u64,
+    ) -> Result<Option<BlockNumber>, TraverseWalletError> {
+        let latest = self
+            .provider
+            .best_block_number()
+            .map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+
+        last_code_change(latest, blocks, |number| async move {
+            let state = self
+                .provider
+                .history_by_block_number(number)
+                .map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+
+            let code = state
+                .account_code(&address)
+                .map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+            Ok(code.map(|code| code.0.bytes()).unwrap_or_default())
+        })
+        .await
+    }
+
     async fn estimate(
         &self,
         tx: &TransactionRequest,
@@ -173,308 +593,4882 @@ where
         ))
     }
 
-    async fn sign_and_send(
+    async fn fee_history(
         &self,
-        mut tx: TransactionRequest,
-    ) -> Result<TxHash, TraverseWalletError> {
-        let next_nonce = LoadState::next_available_nonce(
+        block_count: u64,
+        percentile: f64,
+    ) -> Result<FeeHistory, TraverseWalletError> {
+        EthFees::fee_history(
+            &self.eth_api,
+            block_count,
+            BlockNumberOrTag::Latest,
+            Some(vec![percentile]),
+        )
+        .await
+        .map_err(|err| TraverseWalletError::InternalError(eyre::Report::new(err)))
+    }
+
+    async fn create_access_list(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<AccessListResult, TraverseWalletError> {
+        EthCall::create_access_list_at(&self.eth_api, tx.clone(), Some(BlockId::latest()))
+            .await
+            .map_err(|err| TraverseWalletError::InternalError(eyre::Report::new(err)))
+    }
+
+    async fn sign_and_send(&self, tx: TransactionRequest) -> Result<TxHash, TraverseWalletError> {
+        self.sign_and_send_raw(tx).await.map(|(tx_hash, _)| tx_hash)
+    }
+
+    async fn l1_data_fee(&self, tx: &TransactionRequest) -> Result<U256, TraverseWalletError> {
+        let output = EthCall::call(
             &self.eth_api,
-            NetworkWallet::<Ethereum>::default_signer_address(&self.wallet),
+            l1_fee_call(tx)?,
+            Some(BlockId::latest()),
+            Default::default(),
         )
         .await
         .map_err(|err| TraverseWalletError::InternalError(eyre::Report::new(err)))?;
-        tx.nonce = Some(next_nonce);
+        decode_l1_fee(&output)
+    }
+
+    async fn trace_call(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<TraceResult, TraverseWalletError> {
+        // this mirrors the call tracer of `debug_traceCall`
+        let eth_api = self.eth_api.clone();
+        let config = CallConfig::default();
+        let mut inspector =
+            TracingInspector::new(TracingInspectorConfig::from_geth_call_config(&config));
+        let frame = Call::spawn_with_call_at(
+            &self.eth_api,
+            tx.clone(),
+            BlockId::latest(),
+            Default::default(),
+            move |db, env| {
+                let (res, env) = eth_api.inspect(db, env, &mut inspector)?;
+                Ok(inspector
+                    .with_transaction_gas_limit(env.tx.gas_limit)
+                    .into_geth_builder()
+                    .geth_call_traces(config, res.result.gas_used()))
+            },
+        )
+        .await
+        .map_err(|err| TraverseWalletError::InternalError(eyre::Report::new(err)))?;
+        Ok(TraceResult::Success { result: frame.into(), tx_hash: None })
+    }
+
+    fn rotate_signer(&self, signer: EthereumWallet) -> Result<(), TraverseWalletError> {
+        // the nonces are looked up for the address of the signer, so the new signer starts from
+        // its own pending nonce
+        self.signer.rotate(signer)
+    }
+
+    async fn sign_and_send_raw(
+        &self,
+        tx: TransactionRequest,
+    ) -> Result<(TxHash, Option<Bytes>), TraverseWalletError> {
+        let next_nonce = self.pending_nonce().await?;
 
         // build and sign
-        let envelope =
-            <TransactionRequest as TransactionBuilder<Ethereum>>::build::<EthereumWallet>(
-                tx,
-                &self.wallet,
-            )
-            .await
-            .map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+        let envelope = sign_with_nonce(&self.signer, tx, next_nonce).await?;
 
         // this uses the internal `OpEthApi` to either forward the tx to the sequencer, or add it to
         // the txpool
         //
         // see: https://github.com/paradigmxyz/reth/blob/b67f004fbe8e1b7c05f84f314c4c9f2ed9be1891/crates/optimism/rpc/src/eth/transaction.rs#L35-L57
-        EthTransactions::send_raw_transaction(&self.eth_api, envelope.encoded_2718().into())
+        let raw = Bytes::from(envelope.encoded_2718());
+        EthTransactions::send_raw_transaction(&self.eth_api, raw.clone())
             .await
+            .map(|tx_hash| (tx_hash, Some(raw)))
             .map_err(|err| TraverseWalletError::InternalError(eyre::Report::new(err)))
     }
 }
 
-/// The capability to perform [EIP-7702][eip-7702] delegations, sponsored by the service.
-///
-/// The service will only perform delegations, and act on behalf of delegated accounts, if the
-/// account delegates to one of the addresses specified within this capability.
+/// A handle to a Reth upstream that signs transactions and adds them to the local transaction
+/// pool, without forwarding them to the sequencer.
 ///
-/// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
-#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
-pub struct DelegationCapability {
-    /// A list of valid delegation contracts.
-    pub addresses: Vec<Address>,
-}
-
-/// Traverse `wallet_` RPC namespace.
-#[cfg_attr(not(test), rpc(server, namespace = "wallet"))]
-#[cfg_attr(test, rpc(server, client, namespace = "wallet"))]
-pub trait TraverseWalletApi {
-    /// Send a sponsored transaction.
-    ///
-    /// The transaction will only be processed if:
-    ///
-    /// - The transaction is an [EIP-7702][eip-7702] transaction.
-    /// - The transaction is an [EIP-1559][eip-1559] transaction to an EOA that is currently
-    ///   delegated to one of the addresses above
-    /// - The value in the transaction is exactly 0.
-    ///
-    /// The service will sign the transaction and inject it into the transaction pool, provided it
-    /// is valid. The nonce is managed by the service.
-    ///
-    /// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
-    /// [eip-1559]: https://eips.ethereum.org/EIPS/eip-1559
-    #[method(name = "sendTransaction", aliases = ["traverse_sendTransaction"])]
-    async fn send_transaction(&self, request: TransactionRequest) -> RpcResult<TxHash>;
-}
-
-/// Errors returned by the wallet API.
-#[derive(Debug, thiserror::Error)]
-pub enum TraverseWalletError {
-    /// The transaction value is not 0.
-    ///
-    /// The value should be 0 to prevent draining the service.
-    #[error("tx value not zero")]
-    ValueNotZero,
-    /// The from field is set on the transaction.
-    ///
-    /// Requests with the from field are rejected, since it is implied that it will always be the
-    /// service.
-    #[error("tx from field is set")]
-    FromSet,
-    /// The nonce field is set on the transaction.
-    ///
-    /// Requests with the nonce field set are rejected, as this is managed by the service.
-    #[error("tx nonce is set")]
-    NonceSet,
-    /// The to field of the transaction was invalid.
-    ///
-    /// The destination is invalid if:
-    ///
-    /// - There is no bytecode at the destination, or
-    /// - The bytecode is not an EIP-7702 delegation designator
-    #[error("the destination of the transaction is not a delegated account")]
-    IllegalDestination,
-    /// The transaction request was invalid.
-    ///
-    /// This is likely an internal error, as most of the request is built by the service.
-    #[error("invalid tx request")]
-    InvalidTransactionRequest,
-    /// The request was estimated to consume too much gas.
-    ///
-    /// The gas usage by each request is limited to counteract draining the services funds.
-    #[error("request would use too much gas: estimated {estimate}")]
-    GasEstimateTooHigh {
-        /// The amount of gas the request was estimated to consume.
-        estimate: u64,
-    },
-    /// An internal error occurred.
-    #[error(transparent)]
-    InternalError(#[from] eyre::Error),
+/// This is intended for non-sequencer nodes that include the sponsored transactions themselves.
+/// Everything but sending is delegated to the wrapped [`RethUpstream`].
+#[derive(Debug)]
+pub struct PoolUpstream<Provider, Eth, Pool, Signer = EthereumWallet> {
+    inner: RethUpstream<Provider, Eth, Signer>,
+    pool: Pool,
 }
 
-impl From<TraverseWalletError> for jsonrpsee::types::error::ErrorObject<'static> {
-    fn from(error: TraverseWalletError) -> Self {
-        jsonrpsee::types::error::ErrorObject::owned::<()>(
-            jsonrpsee::types::error::INVALID_PARAMS_CODE,
-            error.to_string(),
-            None,
-        )
+impl<Provider, Eth, Pool, Signer> PoolUpstream<Provider, Eth, Pool, Signer> {
+    /// Create a new [`PoolUpstream`] adding the sponsored transactions to the given pool.
+    pub const fn new(provider: Provider, eth_api: Eth, pool: Pool, signer: Signer) -> Self {
+        Self { inner: RethUpstream::new(provider, eth_api, signer), pool }
     }
 }
 
-/// Implementation of the Traverse `wallet_` namespace.
-#[derive(Debug)]
-pub struct TraverseWallet<T> {
-    inner: Arc<TraverseWalletInner<T>>,
+#[async_trait]
+impl<Provider, Eth, Pool, Signer> Upstream for PoolUpstream<Provider, Eth, Pool, Signer>
+where
+    Provider: StateProviderFactory + Send + Sync,
+    Eth: FullEthApi + Send + Sync,
+    Pool: TransactionPool<Transaction: PoolTransaction<Consensus: Decodable2718>>,
+    <Pool::Transaction as PoolTransaction>::TryFromConsensusError:
+        std::error::Error + Send + Sync + 'static,
+    Signer: SponsorSigner + Send + Sync,
+{
+    fn default_signer_address(&self) -> Address {
+        self.inner.default_signer_address()
+    }
+
+    async fn chain_id(&self) -> Result<ChainId, TraverseWalletError> {
+        self.inner.chain_id().await
+    }
+
+    async fn pending_nonce(&self) -> Result<u64, TraverseWalletError> {
+        self.inner.pending_nonce().await
+    }
+
+    async fn get_code(&self, address: Address) -> Result<Bytes, TraverseWalletError> {
+        self.inner.get_code(address).await
+    }
+
+    async fn code_changed_within(
+        &self,
+        address: Address,
+        blocks: u64,
+    ) -> Result<Option<BlockNumber>, TraverseWalletError> {
+        self.inner.code_changed_within(address, blocks).await
+    }
+
+    async fn estimate(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<(u64, Eip1559Estimation), TraverseWalletError> {
+        self.inner.estimate(tx).await
+    }
+
+    async fn fee_history(
+        &self,
+        block_count: u64,
+        percentile: f64,
+    ) -> Result<FeeHistory, TraverseWalletError> {
+        self.inner.fee_history(block_count, percentile).await
+    }
+
+    async fn create_access_list(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<AccessListResult, TraverseWalletError> {
+        self.inner.create_access_list(tx).await
+    }
+
+    async fn sign_and_send(&self, tx: TransactionRequest) -> Result<TxHash, TraverseWalletError> {
+        self.sign_and_send_raw(tx).await.map(|(tx_hash, _)| tx_hash)
+    }
+
+    async fn l1_data_fee(&self, tx: &TransactionRequest) -> Result<U256, TraverseWalletError> {
+        self.inner.l1_data_fee(tx).await
+    }
+
+    async fn trace_call(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<TraceResult, TraverseWalletError> {
+        self.inner.trace_call(tx).await
+    }
+
+    fn rotate_signer(&self, signer: EthereumWallet) -> Result<(), TraverseWalletError> {
+        self.inner.rotate_signer(signer)
+    }
+
+    async fn sign_and_send_raw(
+        &self,
+        tx: TransactionRequest,
+    ) -> Result<(TxHash, Option<Bytes>), TraverseWalletError> {
+        let next_nonce = self.pending_nonce().await?;
+
+        // build and sign
+        let envelope = sign_with_nonce(&self.inner.signer, tx, next_nonce).await?;
+
+        let raw = Bytes::from(envelope.encoded_2718());
+        add_to_pool(&self.pool, &raw, self.default_signer_address())
+            .await
+            .map(|tx_hash| (tx_hash, Some(raw)))
+    }
+}
+
+/// Decodes the signed transaction, and adds it to the pool as a local transaction.
+///
+/// The transaction is signed by the sponsor, so the given signer is used instead of recovering it.
+async fn add_to_pool<Pool>(
+    pool: &Pool,
+    raw: &[u8],
+    signer: Address,
+) -> Result<TxHash, TraverseWalletError>
+where
+    Pool: TransactionPool<Transaction: PoolTransaction<Consensus: Decodable2718>>,
+    <Pool::Transaction as PoolTransaction>::TryFromConsensusError:
+        std::error::Error + Send + Sync + 'static,
+{
+    let tx = <Pool::Transaction as PoolTransaction>::Consensus::decode_2718(&mut &raw[..])
+        .map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+    let tx =
+        Pool::Transaction::try_from_consensus(RecoveredTx::from_signed_transaction(tx, signer))
+            .map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+
+    pool.add_transaction(TransactionOrigin::Local, tx)
+        .await
+        .map_err(|err| TraverseWalletError::InternalError(err.into()))
+}
+
+/// The body of the request to sign and send a sponsored transaction, posted to the relay of a
+/// [`RelayUpstream`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelaySignRequest {
+    /// The address of the sponsor the relay signs with.
+    pub sponsor: Address,
+    /// The transaction to sign and send.
+    ///
+    /// The gas limit, fees and chain id are set, the relay is expected to set the nonce.
+    pub transaction: TransactionRequest,
+}
+
+/// The body of a successful response of the relay of a [`RelayUpstream`].
+///
+/// Responses with a status other than `2xx` are errors, and their body is not interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelaySignResponse {
+    /// The hash of the signed and sent transaction.
+    pub tx_hash: TxHash,
+}
+
+/// An upstream that signs and sends transactions through an external relay, e.g. to keep the
+/// sponsor key in custody outside of the node.
+///
+/// Estimation and code lookups are served by the inner upstream, whose signer is never used.
+/// Signing and sending posts a [`RelaySignRequest`] to the relay endpoint, which responds with a
+/// [`RelaySignResponse`].
+#[derive(Debug)]
+pub struct RelayUpstream<U> {
+    inner: U,
+    /// The address of the sponsor the relay signs with.
+    sponsor: Address,
+    /// The endpoint of the relay.
+    endpoint: Url,
+    client: reqwest::Client,
+}
+
+impl<U> RelayUpstream<U> {
+    /// Create a new [`RelayUpstream`] that signs with the given sponsor at the given relay
+    /// endpoint.
+    pub fn new(inner: U, sponsor: Address, endpoint: Url) -> Self {
+        Self { inner, sponsor, endpoint, client: reqwest::Client::new() }
+    }
+
+    /// Create a new [`RelayUpstream`] whose client is configured with the given
+    /// [`HttpClientConfig`].
+    pub fn with_client_config(
+        inner: U,
+        sponsor: Address,
+        endpoint: Url,
+        config: HttpClientConfig,
+    ) -> Result<Self, TraverseWalletError> {
+        let client =
+            config.build().map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+        Ok(Self { inner, sponsor, endpoint, client })
+    }
+
+    /// Returns the inner upstream.
+    pub const fn inner(&self) -> &U {
+        &self.inner
+    }
+}
+
+#[async_trait]
+impl<U> Upstream for RelayUpstream<U>
+where
+    U: Upstream + Send + Sync,
+{
+    fn default_signer_address(&self) -> Address {
+        self.sponsor
+    }
+
+    async fn chain_id(&self) -> Result<ChainId, TraverseWalletError> {
+        self.inner.chain_id().await
+    }
+
+    async fn pending_nonce(&self) -> Result<u64, TraverseWalletError> {
+        // the nonces of the sponsor are assigned by the relay, and the inner signer is never used
+        Err(TraverseWalletError::InternalError(eyre::eyre!(
+            "the pending nonce of the sponsor {} is managed by the relay",
+            self.sponsor
+        )))
+    }
+
+    async fn get_code(&self, address: Address) -> Result<Bytes, TraverseWalletError> {
+        self.inner.get_code(address).await
+    }
+
+    async fn code_changed_within(
+        &self,
+        address: Address,
+        blocks: u64,
+    ) -> Result<Option<BlockNumber>, TraverseWalletError> {
+        self.inner.code_changed_within(address, blocks).await
+    }
+
+    async fn estimate(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<(u64, Eip1559Estimation), TraverseWalletError> {
+        self.inner.estimate(tx).await
+    }
+
+    async fn fee_history(
+        &self,
+        block_count: u64,
+        percentile: f64,
+    ) -> Result<FeeHistory, TraverseWalletError> {
+        self.inner.fee_history(block_count, percentile).await
+    }
+
+    async fn create_access_list(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<AccessListResult, TraverseWalletError> {
+        self.inner.create_access_list(tx).await
+    }
+
+    async fn sign_and_send(&self, tx: TransactionRequest) -> Result<TxHash, TraverseWalletError> {
+        let request = RelaySignRequest { sponsor: self.sponsor, transaction: tx };
+        let response = self
+            .client
+            .post(self.endpoint.clone())
+            .json(&request)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .map_err(|err| TraverseWalletError::InternalError(err.into()))?;
+
+        response
+            .json::<RelaySignResponse>()
+            .await
+            .map(|response| response.tx_hash)
+            .map_err(|err| TraverseWalletError::InternalError(err.into()))
+    }
+
+    async fn l1_data_fee(&self, tx: &TransactionRequest) -> Result<U256, TraverseWalletError> {
+        self.inner.l1_data_fee(tx).await
+    }
+
+    async fn trace_call(
+        &self,
+        tx: &TransactionRequest,
+    ) -> Result<TraceResult, TraverseWalletError> {
+        self.inner.trace_call(tx).await
+    }
+}
+
+/// Returns a block at which the code returned by `code_at` changed to the code of the `latest`
+/// block, if it changed within the given number of blocks before the `latest` block.
+///
+/// The code of the `latest` block is compared to the code at the start of the window, and the
+/// change is only searched for on a mismatch, with a binary search. This bounds the lookups to
+/// `2 + log2(blocks)`, but changes that were reverted within the window, e.g. delegating to
+/// another contract and back, are not found.
+async fn last_code_change<F, Fut>(
+    latest: BlockNumber,
+    blocks: u64,
+    code_at: F,
+) -> Result<Option<BlockNumber>, TraverseWalletError>
+where
+    F: Fn(BlockNumber) -> Fut,
+    Fut: Future<Output = Result<Bytes, TraverseWalletError>>,
+{
+    let (mut unchanged, mut changed) = (latest, latest.saturating_sub(blocks));
+    if changed == unchanged {
+        return Ok(None);
+    }
+
+    let code = code_at(latest).await?;
+    if code_at(changed).await? == code {
+        return Ok(None);
+    }
+
+    // the code differs at `changed` and equals the latest code at `unchanged`
+    while unchanged - changed > 1 {
+        let mid = changed + (unchanged - changed) / 2;
+        if code_at(mid).await? == code {
+            unchanged = mid;
+        } else {
+            changed = mid;
+        }
+    }
+
+    Ok(Some(unchanged))
+}
+
+/// Returns the delegation target of the given code, if it is an [EIP-7702][eip-7702] delegation
+/// designator.
+///
+/// The target of a cleared delegation is the zero address.
+///
+/// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
+fn delegation_designator(code: &[u8]) -> Option<Address> {
+    match code {
+        [0xef, 0x01, 0x00, address @ ..] if address.len() == Address::len_bytes() => {
+            Some(Address::from_slice(address))
+        }
+        _ => None,
+    }
+}
+
+/// Follows the chain of delegation designators from `account`, which delegates to `delegation`,
+/// with the code returned by `code_at`.
+///
+/// Returns an error if the chain has more than `max_depth` delegations, or if it loops back to an
+/// account of the chain.
+async fn check_delegation_chain<F, Fut>(
+    account: Address,
+    delegation: Address,
+    max_depth: usize,
+    code_at: F,
+) -> Result<(), TraverseWalletError>
+where
+    F: Fn(Address) -> Fut,
+    Fut: Future<Output = Result<Bytes, TraverseWalletError>>,
+{
+    let mut chain = vec![account, delegation];
+    loop {
+        // the chain ends at an account that is not delegated
+        let Some(next) = delegation_designator(&code_at(chain[chain.len() - 1]).await?) else {
+            return Ok(());
+        };
+        if chain.len() > max_depth || chain.contains(&next) {
+            return Err(TraverseWalletError::DelegationChainTooDeep { max_depth });
+        }
+        chain.push(next);
+    }
+}
+
+/// The capability to perform [EIP-7702][eip-7702] delegations, sponsored by the service.
+///
+/// The service will only perform delegations, and act on behalf of delegated accounts, if the
+/// account delegates to one of the addresses specified within this capability.
+///
+/// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
+#[derive(Debug, Clone, Eq, PartialEq, Deserialize, Serialize)]
+pub struct DelegationCapability {
+    /// A list of valid delegation contracts.
+    pub addresses: Vec<Address>,
+}
+
+/// Traverse `wallet_` RPC namespace.
+#[cfg_attr(not(test), rpc(server, namespace = "wallet"))]
+#[cfg_attr(test, rpc(server, client, namespace = "wallet"))]
+pub trait TraverseWalletApi {
+    /// Send a sponsored transaction.
+    ///
+    /// The transaction will only be processed if:
+    ///
+    /// - The transaction is an [EIP-7702][eip-7702] transaction.
+    /// - The transaction is an [EIP-1559][eip-1559] transaction to an EOA that is currently
+    ///   delegated to one of the addresses above
+    /// - The value in the transaction is exactly 0.
+    ///
+    /// The service will sign the transaction and inject it into the transaction pool, provided it
+    /// is valid. The nonce is managed by the service.
+    ///
+    /// The gas limit is managed by the service as well. A gas limit hint raises the gas limit of
+    /// the transaction above the estimate, e.g. to tolerate state changes between estimation and
+    /// inclusion. The hint is bounded by the same gas ceiling as the estimate.
+    ///
+    /// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
+    /// [eip-1559]: https://eips.ethereum.org/EIPS/eip-1559
+    #[method(name = "sendTransaction", aliases = ["traverse_sendTransaction"])]
+    async fn send_transaction(
+        &self,
+        request: TransactionRequest,
+        gas_limit_hint: Option<u64>,
+    ) -> RpcResult<TxHash>;
+
+    /// Returns the current limits of the service, see [`WalletLimits`].
+    ///
+    /// The chain id is required if the service sponsors transactions on multiple chains.
+    #[method(name = "getLimits")]
+    async fn get_limits(&self, chain_id: Option<ChainId>) -> RpcResult<WalletLimits>;
+
+    /// Returns a summary of the calls to `wallet_sendTransaction`, see [`WalletStats`].
+    ///
+    /// The chain id is required if the service sponsors transactions on multiple chains.
+    #[method(name = "getStats")]
+    async fn get_stats(&self, chain_id: Option<ChainId>) -> RpcResult<WalletStats>;
+
+    /// Returns the delegation target of the given account, or `None` if the account is not
+    /// delegated, e.g. because it has no code, its delegation was cleared, or it is a contract.
+    ///
+    /// The code of the account is parsed like the destination of `wallet_sendTransaction`. The
+    /// chain id is required if the service sponsors transactions on multiple chains.
+    #[method(name = "getDelegation")]
+    async fn get_delegation(
+        &self,
+        address: Address,
+        chain_id: Option<ChainId>,
+    ) -> RpcResult<Option<Address>>;
+
+    /// Simulates the sponsored transaction of the request at the latest block with a call
+    /// tracer, and returns the call trace without signing or broadcasting the transaction.
+    ///
+    /// The request and the delegation of its destination are checked like in
+    /// `wallet_sendTransaction`, and the request is simulated as sent by the sponsor, so that
+    /// reverts in delegation contracts can be diagnosed. Calls to this method are not counted in
+    /// the wallet stats.
+    ///
+    /// This is disabled unless [`TraverseWalletConfig::trace_sponsored_transactions`] is set.
+    #[method(name = "traceSponsoredTransaction")]
+    async fn trace_sponsored_transaction(
+        &self,
+        request: TransactionRequest,
+    ) -> RpcResult<TraceResult>;
+
+    /// Subscribe to the transactions sponsored by the service, see [`SponsoredTxEvent`].
+    ///
+    /// Subscribers that fall behind by more than
+    /// [`TraverseWalletConfig::sponsored_tx_event_capacity`] events are dropped, and the events
+    /// they missed are counted in the wallet metrics.
+    #[subscription(
+        name = "subscribeSponsoredTransactions",
+        unsubscribe = "unsubscribeSponsoredTransactions",
+        item = SponsoredTxEvent
+    )]
+    async fn subscribe_sponsored_transactions(&self) -> SubscriptionResult;
+}
+
+/// Traverse `walletAdmin_` RPC namespace, for operators of the service.
+#[cfg_attr(not(test), rpc(server, namespace = "walletAdmin"))]
+#[cfg_attr(test, rpc(server, client, namespace = "walletAdmin"))]
+pub trait TraverseWalletAdminApi {
+    /// Stop sponsoring transactions until sponsoring is resumed.
+    ///
+    /// Calls to `wallet_sendTransaction` are rejected while sponsoring is paused.
+    #[method(name = "pauseSponsoring")]
+    async fn pause_sponsoring(&self) -> RpcResult<()>;
+
+    /// Resume sponsoring transactions.
+    #[method(name = "resumeSponsoring")]
+    async fn resume_sponsoring(&self) -> RpcResult<()>;
+
+    /// Mark the account as delegated to the given delegation contract.
+    ///
+    /// The code of known delegated accounts is not looked up when sponsoring transactions to
+    /// them, see [`TraverseWallet::add_known_delegation`].
+    #[method(name = "addKnownDelegation")]
+    async fn add_known_delegation(&self, account: Address, delegation: Address) -> RpcResult<()>;
+
+    /// Forget the known delegation of the account, returning its delegation contract if it was
+    /// known.
+    ///
+    /// The code of the account is looked up again when sponsoring transactions to it.
+    #[method(name = "removeKnownDelegation")]
+    async fn remove_known_delegation(&self, account: Address) -> RpcResult<Option<Address>>;
+
+    /// Returns the message of the last internal error of `wallet_sendTransaction`, if any.
+    ///
+    /// Internal errors may include details of the upstream, so they are not part of the public
+    /// [`WalletStats`].
+    #[method(name = "getLastInternalError")]
+    async fn get_last_internal_error(&self) -> RpcResult<Option<String>>;
+
+    /// Replace the sponsor signer with the signer of the given secret key, returning its address.
+    ///
+    /// The transaction that is being signed and sent, if any, is drained first, see
+    /// [`TraverseWallet::rotate_signer`].
+    #[method(name = "rotateSigner")]
+    async fn rotate_signer(&self, secret_key: B256) -> RpcResult<Address>;
+}
+
+/// The default number of [`SponsoredTxEvent`]s buffered for each subscriber.
+pub const SPONSORED_TX_EVENT_CAPACITY: usize = 256;
+
+/// The maximum number of known delegated accounts, see [`TraverseWallet::add_known_delegation`].
+pub const MAX_KNOWN_DELEGATIONS: usize = 10_000;
+
+/// The maximum minimum age of delegations in blocks, see
+/// [`TraverseWalletConfig::min_delegation_age`].
+pub const MAX_MIN_DELEGATION_AGE: u64 = 43_200;
+
+/// A transaction that was sponsored and sent by the [`TraverseWallet`].
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SponsoredTxEvent {
+    /// The chain of the sponsored transaction.
+    pub chain_id: ChainId,
+    /// The hash of the sponsored transaction.
+    pub tx_hash: TxHash,
+    /// The destination of the transaction.
+    pub to: Option<Address>,
+    /// The delegation contract of the destination, if the transaction is an
+    /// [EIP-1559][eip-1559] transaction.
+    ///
+    /// [eip-1559]: https://eips.ethereum.org/EIPS/eip-1559
+    pub delegation: Option<Address>,
+    /// The gas limit of the transaction.
+    pub gas: u64,
+    /// The [EIP-2718][eip-2718] encoded signed transaction.
+    ///
+    /// This is only set if [`TraverseWalletConfig::raw_tx_events`] is enabled, and the upstream
+    /// returns the encoded transaction.
+    ///
+    /// [eip-2718]: https://eips.ethereum.org/EIPS/eip-2718
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_transaction: Option<Bytes>,
+}
+
+/// The limits of sponsored transactions, as returned by `wallet_getLimits`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletLimits {
+    /// The default gas ceiling of a sponsored transaction.
+    pub gas_ceiling: u64,
+    /// The gas of sponsored transactions that are submitted, but not settled yet.
+    pub in_flight_gas: u64,
+    /// The remaining gas budget for sponsored transactions, if the budget is limited.
+    pub in_flight_gas_remaining: Option<u64>,
+}
+
+/// A summary of the calls to `wallet_sendTransaction`, as returned by `wallet_getStats`.
+///
+/// The stats are kept in memory since the service started.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WalletStats {
+    /// The number of calls that sponsored a transaction.
+    pub valid_calls: u64,
+    /// The number of calls that were rejected, or failed with an internal error.
+    pub invalid_calls: u64,
+    /// The reason of the last rejection, see [`TraverseWalletError::reason`].
+    ///
+    /// Internal errors are not included, their messages are returned by
+    /// `walletAdmin_getLastInternalError` instead.
+    pub last_rejection_reason: Option<String>,
+}
+
+impl WalletStats {
+    fn record(&mut self, result: &Result<TxHash, TraverseWalletError>) {
+        match result {
+            Ok(_) => self.valid_calls += 1,
+            Err(err) => {
+                self.invalid_calls += 1;
+                if !err.is_internal() {
+                    self.last_rejection_reason = Some(err.reason().to_string());
+                }
+            }
+        }
+    }
 }
 
-impl<T> TraverseWallet<T> {
-    /// Create a new Traverse wallet module.
-    pub fn new(upstream: T, chain_id: ChainId) -> Self {
-        let inner = TraverseWalletInner {
-            upstream,
-            chain_id,
-            permit: Default::default(),
-            metrics: WalletMetrics::default(),
+/// Errors returned by the wallet API.
+#[derive(Debug, thiserror::Error)]
+pub enum TraverseWalletError {
+    /// The transaction value is not 0.
+    ///
+    /// The value should be 0 to prevent draining the service.
+    #[error("tx value not zero")]
+    ValueNotZero,
+    /// The from field is set on the transaction.
+    ///
+    /// Requests with the from field are rejected, since it is implied that it will always be the
+    /// service.
+    #[error("tx from field is set")]
+    FromSet,
+    /// The nonce field is set on the transaction.
+    ///
+    /// Requests with the nonce field set are rejected, as this is managed by the service.
+    #[error("tx nonce is set")]
+    NonceSet,
+    /// The gas field is set on the transaction.
+    ///
+    /// Requests with the gas field set are rejected, as the gas limit is estimated by the
+    /// service.
+    #[error("tx gas is set")]
+    GasSet,
+    /// The to field of the transaction was invalid.
+    ///
+    /// Create transactions are rejected with [`Self::ContractCreationUnsupported`], and
+    /// destinations that are not delegated with [`Self::DestinationNotDelegated`] and
+    /// [`Self::DestinationNotADelegation`].
+    #[error("the destination of the transaction is not a delegated account")]
+    IllegalDestination,
+    /// The transaction would create a contract, which is not sponsored.
+    #[error("contract creation is not sponsored")]
+    ContractCreationUnsupported,
+    /// There is no bytecode at the destination of the transaction, i.e. it is an EOA without a
+    /// delegation.
+    #[error("the destination of the transaction is not delegated")]
+    DestinationNotDelegated,
+    /// The bytecode at the destination of the transaction is not an EIP-7702 delegation
+    /// designator, e.g. because the destination is a contract.
+    #[error("the destination of the transaction is not a delegation")]
+    DestinationNotADelegation,
+    /// The destination of the transaction is not in [`TraverseWalletConfig::destinations`].
+    #[error("the destination {0} is not allowed")]
+    DestinationNotAllowed(Address),
+    /// The delegation of the destination was cleared, i.e. it delegates to the zero address.
+    ///
+    /// The account has to be delegated again before it can receive sponsored transactions.
+    #[error("the delegation of the destination was cleared")]
+    DelegationCleared,
+    /// The delegation of the destination changed too recently.
+    ///
+    /// See [`TraverseWalletConfig::min_delegation_age`].
+    #[error("the delegation of the destination changed too recently, at block {changed_at}")]
+    DelegationTooRecent {
+        /// The block at which the delegation changed.
+        changed_at: BlockNumber,
+    },
+    /// The delegation of the destination delegates further, in a chain that is longer than
+    /// [`TraverseWalletConfig::max_delegation_depth`] or that loops back to one of its accounts.
+    #[error("the delegation chain of the destination exceeds depth {max_depth} or loops")]
+    DelegationChainTooDeep {
+        /// The maximum depth of a delegation chain.
+        max_depth: usize,
+    },
+    /// An authorization in the transaction is invalid.
+    ///
+    /// An authorization is invalid if its signature cannot be recovered, or if the recovered
+    /// authority is not the destination of the transaction.
+    #[error("invalid authorization")]
+    InvalidAuthorization,
+    /// An authorization in the transaction delegates to a contract that is not supported by the
+    /// service.
+    #[error("delegation to {0} is not supported")]
+    UnsupportedDelegation(Address),
+    /// The transaction request was invalid.
+    ///
+    /// This is likely an internal error, as most of the request is built by the service.
+    #[error("invalid tx request")]
+    InvalidTransactionRequest,
+    /// The gas estimate of the request is below the intrinsic gas of the transaction.
+    ///
+    /// A transaction with this gas limit could never be included.
+    #[error("invalid gas estimate {estimate}, intrinsic gas is {intrinsic_gas}")]
+    InvalidGasEstimate {
+        /// The gas estimate of the upstream.
+        estimate: u64,
+        /// The intrinsic gas of the transaction.
+        intrinsic_gas: u64,
+    },
+    /// The request was estimated to consume too much gas.
+    ///
+    /// The gas usage by each request is limited to counteract draining the services funds.
+    #[error("request would use too much gas: estimated {estimate}")]
+    GasEstimateTooHigh {
+        /// The amount of gas the request was estimated to consume.
+        estimate: u64,
+    },
+    /// The gas limit hint of the request is at or above the gas ceiling.
+    #[error("gas limit hint {hint} is too high: the ceiling is {ceiling}")]
+    GasLimitHintTooHigh {
+        /// The gas limit hint of the request.
+        hint: u64,
+        /// The gas ceiling of the request.
+        ceiling: u64,
+    },
+    /// The max fee per gas of the request is above
+    /// [`TraverseWalletConfig::max_fee_per_gas_cap`], or the max fee per gas supplied by the
+    /// client is above the cap of [`FeePolicy::Client`].
+    #[error("max fee per gas {max_fee} is too high")]
+    FeeTooHigh {
+        /// The max fee per gas the transaction would have been signed with.
+        max_fee: u128,
+    },
+    /// The max priority fee per gas supplied by the client is above the cap of
+    /// [`FeePolicy::Client`].
+    ///
+    /// Unlike estimated priority fees, client fees are rejected rather than clamped.
+    #[error("max priority fee per gas {max_priority_fee} is too high")]
+    PriorityFeeTooHigh {
+        /// The max priority fee per gas supplied by the client.
+        max_priority_fee: u128,
+    },
+    /// The fees supplied by the client are inconsistent, i.e. the max priority fee per gas is
+    /// above the max fee per gas, or the max fee per gas is below the base fee of the next block.
+    ///
+    /// See [`FeePolicy::Client`].
+    #[error("inconsistent fees: max fee {max_fee}, max priority fee {max_priority_fee}")]
+    InconsistentFees {
+        /// The max fee per gas supplied by the client.
+        max_fee: u128,
+        /// The max priority fee per gas supplied by the client.
+        max_priority_fee: u128,
+    },
+    /// The request would exceed the gas budget for sponsored transactions that are not mined yet.
+    ///
+    /// This limits how much the service commits to at once, see
+    /// [`TraverseWalletConfig::max_in_flight_gas`].
+    #[error("sponsor budget exceeded")]
+    SponsorBudgetExceeded,
+    /// The request would exceed the budget of the sender within the [`SENDER_BUDGET_WINDOW`].
+    ///
+    /// See [`TraverseWalletConfig::sender_daily_budget`].
+    #[error("budget of sender {0} exceeded")]
+    SenderBudgetExceeded(Address),
+    /// The upstream did not respond in time.
+    ///
+    /// See [`TraverseWalletConfig::upstream_timeout`].
+    #[error("upstream timed out")]
+    UpstreamTimeout,
+    /// The upstream did not estimate the request in time.
+    ///
+    /// See [`TraverseWalletConfig::estimate_timeout`].
+    #[error("gas estimation timed out")]
+    EstimateTimeout,
+    /// The type of the transaction is not sponsored by the service.
+    ///
+    /// See [`TraverseWalletConfig::accepted_types`].
+    #[error("{0} transactions are not sponsored")]
+    TransactionTypeNotAccepted(SponsoredTxType),
+    /// The chain id of the request is not set, or not supported by the service.
+    #[error("unsupported chain id: {0:?}")]
+    UnsupportedChainId(Option<ChainId>),
+    /// The chain id of the upstream differs from the chain id the wallet is configured with.
+    #[error("chain id mismatch: configured {configured}, upstream {upstream}")]
+    ChainIdMismatch {
+        /// The chain id the wallet is configured with.
+        configured: ChainId,
+        /// The chain id reported by the upstream.
+        upstream: ChainId,
+    },
+    /// Sponsoring transactions is paused by the operator of the service.
+    #[error("sponsoring is paused")]
+    SponsoringPaused,
+    /// Tracing sponsored transactions is disabled.
+    ///
+    /// See [`TraverseWalletConfig::trace_sponsored_transactions`].
+    #[error("tracing sponsored transactions is disabled")]
+    TracingDisabled,
+    /// The input of the request is too large.
+    ///
+    /// See [`TraverseWalletConfig::max_input_size`].
+    #[error("request too large")]
+    RequestTooLarge,
+    /// The authorization list of the request has too many authorizations.
+    ///
+    /// See [`TraverseWalletConfig::max_authorizations`].
+    #[error("too many authorizations: {count} exceeds {max}")]
+    TooManyAuthorizations {
+        /// The number of authorizations of the request.
+        count: usize,
+        /// The maximum number of authorizations of a request.
+        max: usize,
+    },
+    /// The authorization list of the request is empty.
+    ///
+    /// See [`TraverseWalletConfig::reject_empty_authorization_list`].
+    #[error("empty authorization list")]
+    EmptyAuthorizationList,
+    /// There are already [`MAX_KNOWN_DELEGATIONS`] known delegated accounts.
+    #[error("too many known delegations")]
+    TooManyKnownDelegations,
+    /// The signer of the upstream cannot be rotated.
+    #[error("signer rotation unsupported")]
+    SignerRotationUnsupported,
+    /// The secret key of a rotated signer is invalid.
+    #[error("invalid signer")]
+    InvalidSigner,
+    /// An internal error occurred.
+    #[error(transparent)]
+    InternalError(#[from] eyre::Error),
+}
+
+impl TraverseWalletError {
+    /// Returns a stable, machine-readable reason for the error.
+    pub const fn reason(&self) -> &'static str {
+        match self {
+            Self::ValueNotZero => "value_not_zero",
+            Self::FromSet => "from_set",
+            Self::NonceSet => "nonce_set",
+            Self::GasSet => "gas_set",
+            Self::IllegalDestination => "illegal_destination",
+            Self::ContractCreationUnsupported => "contract_creation_unsupported",
+            Self::DestinationNotDelegated => "destination_not_delegated",
+            Self::DestinationNotADelegation => "destination_not_a_delegation",
+            Self::DestinationNotAllowed(_) => "destination_not_allowed",
+            Self::DelegationCleared => "delegation_cleared",
+            Self::DelegationTooRecent { .. } => "delegation_too_recent",
+            Self::DelegationChainTooDeep { .. } => "delegation_chain_too_deep",
+            Self::InvalidAuthorization => "invalid_authorization",
+            Self::UnsupportedDelegation(_) => "unsupported_delegation",
+            Self::InvalidTransactionRequest => "invalid_transaction_request",
+            Self::InvalidGasEstimate { .. } => "invalid_gas_estimate",
+            Self::GasEstimateTooHigh { .. } => "gas_estimate_too_high",
+            Self::GasLimitHintTooHigh { .. } => "gas_limit_hint_too_high",
+            Self::FeeTooHigh { .. } => "fee_too_high",
+            Self::PriorityFeeTooHigh { .. } => "priority_fee_too_high",
+            Self::InconsistentFees { .. } => "inconsistent_fees",
+            Self::SponsorBudgetExceeded => "sponsor_budget_exceeded",
+            Self::SenderBudgetExceeded(_) => "sender_budget_exceeded",
+            Self::UpstreamTimeout => "upstream_timeout",
+            Self::EstimateTimeout => "estimate_timeout",
+            Self::TransactionTypeNotAccepted(_) => "transaction_type_not_accepted",
+            Self::UnsupportedChainId(_) => "unsupported_chain_id",
+            Self::ChainIdMismatch { .. } => "chain_id_mismatch",
+            Self::SponsoringPaused => "sponsoring_paused",
+            Self::TracingDisabled => "tracing_disabled",
+            Self::RequestTooLarge => "request_too_large",
+            Self::TooManyAuthorizations { .. } => "too_many_authorizations",
+            Self::EmptyAuthorizationList => "empty_authorization_list",
+            Self::TooManyKnownDelegations => "too_many_known_delegations",
+            Self::SignerRotationUnsupported => "signer_rotation_unsupported",
+            Self::InvalidSigner => "invalid_signer",
+            Self::InternalError(_) => "internal_error",
+        }
+    }
+
+    /// Returns `true` if the error was caused by the service rather than the request.
+    pub const fn is_internal(&self) -> bool {
+        matches!(self, Self::UpstreamTimeout | Self::EstimateTimeout | Self::InternalError(_))
+    }
+}
+
+/// The `data` field of a JSON-RPC error returned by the wallet API.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct TraverseWalletErrorData {
+    /// A stable, machine-readable reason for the error.
+    ///
+    /// See [`TraverseWalletError::reason`].
+    pub reason: &'static str,
+}
+
+impl From<TraverseWalletError> for jsonrpsee::types::error::ErrorObject<'static> {
+    fn from(error: TraverseWalletError) -> Self {
+        let code = if error.is_internal() {
+            jsonrpsee::types::error::INTERNAL_ERROR_CODE
+        } else {
+            jsonrpsee::types::error::INVALID_PARAMS_CODE
+        };
+
+        jsonrpsee::types::error::ErrorObject::owned(
+            code,
+            error.to_string(),
+            Some(TraverseWalletErrorData { reason: error.reason() }),
+        )
+    }
+}
+
+/// Implementation of the Traverse `wallet_` namespace.
+#[derive(Debug)]
+pub struct TraverseWallet<T> {
+    inner: Arc<TraverseWalletInner<T>>,
+}
+
+impl<T> TraverseWallet<T> {
+    /// Create a new Traverse wallet module.
+    pub fn new(upstream: T, chain_id: ChainId) -> Self {
+        Self::with_config(upstream, chain_id, TraverseWalletConfig::default())
+    }
+
+    /// Create a new Traverse wallet module with the given [`TraverseWalletConfig`].
+    pub fn with_config(upstream: T, chain_id: ChainId, config: TraverseWalletConfig) -> Self {
+        let (events, _) = broadcast::channel(config.sponsored_tx_event_capacity.max(1));
+        let inner = TraverseWalletInner {
+            upstream,
+            chain_id,
+            config,
+            permit: Default::default(),
+            in_flight: Default::default(),
+            sender_spend: Default::default(),
+            known_delegations: Default::default(),
+            stats: Default::default(),
+            last_internal_error: Default::default(),
+            paused: AtomicBool::new(false),
+            events,
+            metrics: WalletMetrics::default(),
+        };
+        Self { inner: Arc::new(inner) }
+    }
+
+    /// Marks the given sponsored transactions as settled, i.e. mined or dropped.
+    ///
+    /// Their gas no longer counts towards [`TraverseWalletConfig::max_in_flight_gas`].
+    pub fn settle_transactions(&self, hashes: impl IntoIterator<Item = TxHash>) {
+        let mut in_flight = self.inner.in_flight.lock();
+        for hash in hashes {
+            in_flight.release(&hash);
+        }
+    }
+
+    /// Stops sponsoring transactions, see [`TraverseWalletAdminApiServer::pause_sponsoring`].
+    pub fn pause(&self) {
+        self.inner.paused.store(true, Ordering::Relaxed);
+    }
+
+    /// Resumes sponsoring transactions.
+    pub fn resume(&self) {
+        self.inner.paused.store(false, Ordering::Relaxed);
+    }
+
+    /// Returns `true` if sponsoring transactions is paused.
+    pub fn is_paused(&self) -> bool {
+        self.inner.paused.load(Ordering::Relaxed)
+    }
+
+    /// Marks the account as delegated to the given delegation contract.
+    ///
+    /// Transactions to known delegated accounts skip the code lookup of the destination, which
+    /// trades latency for the risk of sponsoring an account whose delegation changed since.
+    ///
+    /// The delegation contract must be one of [`TraverseWalletConfig::delegations`], if
+    /// configured, and at most [`MAX_KNOWN_DELEGATIONS`] accounts are known at a time.
+    pub fn add_known_delegation(
+        &self,
+        account: Address,
+        delegation: Address,
+    ) -> Result<(), TraverseWalletError> {
+        if account.is_zero() {
+            return Err(TraverseWalletError::IllegalDestination);
+        }
+        if delegation.is_zero() {
+            return Err(TraverseWalletError::DelegationCleared);
+        }
+        if self
+            .inner
+            .config
+            .delegations
+            .as_ref()
+            .is_some_and(|delegations| !delegations.addresses.contains(&delegation))
+        {
+            return Err(TraverseWalletError::UnsupportedDelegation(delegation));
+        }
+
+        let mut known_delegations = self.inner.known_delegations.write();
+        if known_delegations.len() >= MAX_KNOWN_DELEGATIONS
+            && !known_delegations.contains_key(&account)
+        {
+            return Err(TraverseWalletError::TooManyKnownDelegations);
+        }
+        known_delegations.insert(account, delegation);
+        Ok(())
+    }
+
+    /// Forgets the known delegation of the account, returning its delegation contract if it was
+    /// known.
+    pub fn remove_known_delegation(&self, account: Address) -> Option<Address> {
+        self.inner.known_delegations.write().remove(&account)
+    }
+
+    /// Returns the delegation contract of the account, if it is a known delegated account.
+    pub fn known_delegation(&self, account: Address) -> Option<Address> {
+        self.inner.known_delegations.read().get(&account).copied()
+    }
+
+    /// Checks whether the request passes the validation of the wallet that does not require
+    /// network access.
+    ///
+    /// This covers the fields managed by the service, the size and type of the request, and the
+    /// authorizations of [EIP-7702][eip-7702] requests. A request that passes may still be rejected
+    /// by `wallet_sendTransaction`, e.g. because of its destination or gas estimate.
+    ///
+    /// ```
+    /// use alloy_primitives::{Address, U256};
+    /// use alloy_rpc_types::TransactionRequest;
+    /// use traverse_wallet::{TraverseWallet, TraverseWalletError};
+    ///
+    /// let wallet = TraverseWallet::new((), 1);
+    ///
+    /// let request = TransactionRequest::default().to(Address::with_last_byte(1));
+    /// assert!(wallet.precheck(&request).is_ok());
+    ///
+    /// let request = request.value(U256::from(1));
+    /// assert!(matches!(wallet.precheck(&request), Err(TraverseWalletError::ValueNotZero)));
+    /// ```
+    ///
+    /// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
+    pub fn precheck(&self, request: &TransactionRequest) -> Result<(), TraverseWalletError> {
+        // validate fields common to eip-7702 and eip-1559
+        validate_tx_request(request, &self.inner.config)?;
+
+        // ensure the type of the transaction is sponsored
+        let tx_type = SponsoredTxType::of(request);
+        if !self.inner.config.accepted_types.contains(&tx_type) {
+            return Err(TraverseWalletError::TransactionTypeNotAccepted(tx_type));
+        }
+
+        match (request.authorization_list.is_some(), request.to) {
+            // create tx's disallowed, for both eip-7702 and eip-1559 tx's
+            (_, None | Some(TxKind::Create)) => {
+                Err(TraverseWalletError::ContractCreationUnsupported)
+            }
+            // the destination must be allowed, for both eip-7702 and eip-1559 tx's
+            (_, Some(TxKind::Call(to)))
+                if self
+                    .inner
+                    .config
+                    .destinations
+                    .as_ref()
+                    .is_some_and(|destinations| !destinations.contains(&to)) =>
+            {
+                Err(TraverseWalletError::DestinationNotAllowed(to))
+            }
+            // if it's an eip-7702 tx, ensure that the authorizations are valid
+            (true, Some(TxKind::Call(_))) => {
+                validate_authorizations(request, self.inner.config.delegations.as_ref())
+            }
+            // the delegation of the destination of an eip-1559 tx is checked with the upstream
+            (false, Some(TxKind::Call(_))) => Ok(()),
+        }
+    }
+
+    /// Returns a receiver of the [`SponsoredTxEvent`]s of this wallet.
+    pub fn sponsored_transactions(&self) -> broadcast::Receiver<SponsoredTxEvent> {
+        self.inner.events.subscribe()
+    }
+
+    #[allow(clippy::missing_const_for_fn)]
+    fn chain_id(&self) -> ChainId {
+        self.inner.chain_id
+    }
+}
+
+impl<T> TraverseWallet<T>
+where
+    T: Upstream,
+{
+    /// Create a new wallet with the given configuration, after ensuring that the upstream is
+    /// connected to the given chain.
+    ///
+    /// Fails with [`TraverseWalletError::ChainIdMismatch`] if the upstream reports a different
+    /// chain id, which would otherwise sign transactions for the wrong chain.
+    pub async fn connect(
+        upstream: T,
+        chain_id: ChainId,
+        config: TraverseWalletConfig,
+    ) -> Result<Self, TraverseWalletError> {
+        let upstream_chain_id = upstream.chain_id().await?;
+        if upstream_chain_id != chain_id {
+            return Err(TraverseWalletError::ChainIdMismatch {
+                configured: chain_id,
+                upstream: upstream_chain_id,
+            });
+        }
+
+        Ok(Self::with_config(upstream, chain_id, config))
+    }
+
+    /// Replaces the signer of sponsored transactions with the given wallet, returning its address.
+    ///
+    /// This waits for the transaction that is being signed and sent, if any, so that no
+    /// transaction is signed by the previous signer afterwards. The upstream looks up the nonces
+    /// of the new signer from its own pending state.
+    pub async fn rotate_signer(
+        &self,
+        signer: EthereumWallet,
+    ) -> Result<Address, TraverseWalletError> {
+        let _permit = self.inner.permit.lock().await;
+        self.inner.upstream.rotate_signer(signer)?;
+        Ok(self.inner.upstream.default_signer_address())
+    }
+
+    /// Awaits the upstream call, failing with [`TraverseWalletError::UpstreamTimeout`] if it does
+    /// not complete within [`TraverseWalletConfig::upstream_timeout`].
+    async fn upstream_call<R>(
+        &self,
+        call: impl Future<Output = Result<R, TraverseWalletError>>,
+    ) -> Result<R, TraverseWalletError> {
+        tokio::time::timeout(self.inner.config.upstream_timeout, call)
+            .await
+            .unwrap_or(Err(TraverseWalletError::UpstreamTimeout))
+    }
+
+    /// Estimates the request with the upstream, failing with
+    /// [`TraverseWalletError::EstimateTimeout`] if the estimate does not complete within
+    /// [`TraverseWalletConfig::estimate_timeout`].
+    async fn estimate(
+        &self,
+        request: &TransactionRequest,
+    ) -> Result<(u64, Eip1559Estimation), TraverseWalletError> {
+        let estimate = self.inner.upstream.estimate(request);
+        match tokio::time::timeout(self.inner.config.estimate_timeout, estimate).await {
+            Ok(estimate) => estimate,
+            Err(_) => {
+                self.inner.metrics.estimate_timeouts.increment(1);
+                Err(TraverseWalletError::EstimateTimeout)
+            }
+        }
+    }
+
+    /// Adds an access list to the request if it lowers the gas estimate of the request.
+    ///
+    /// Returns the gas estimate of the request, with the access list if it was added.
+    async fn apply_access_list(
+        &self,
+        request: &mut TransactionRequest,
+        estimate: u64,
+    ) -> Result<u64, TraverseWalletError> {
+        let result = self.upstream_call(self.inner.upstream.create_access_list(request)).await?;
+        if result.error.is_some() || result.access_list.0.is_empty() {
+            return Ok(estimate);
+        }
+
+        // the access list costs gas itself, so we only keep it if it pays off
+        let previous = request.access_list.replace(result.access_list);
+        let (with_access_list, _) = self.estimate(request).await?;
+        if with_access_list < estimate {
+            return Ok(with_access_list);
+        }
+
+        request.access_list = previous;
+        Ok(estimate)
+    }
+
+    /// Validates the destination of the request, and resolves the delegation target of the
+    /// destination if any.
+    ///
+    /// This applies the delegation checks of [`TraverseWalletConfig`], and is shared by
+    /// sponsoring and tracing transactions.
+    async fn validate_delegation(
+        &self,
+        request: &TransactionRequest,
+    ) -> Result<Option<Address>, TraverseWalletError> {
+        let known_delegation = match request.to {
+            Some(TxKind::Call(addr)) => self.known_delegation(addr),
+            _ => None,
+        };
+        let delegation = match (request.authorization_list.is_some(), request.to) {
+            // the code of known delegated accounts is not looked up
+            (false, Some(TxKind::Call(_))) if known_delegation.is_some() => known_delegation,
+            // if this is an eip-1559 tx, ensure that it is an account that delegates to a
+            // whitelisted address
+            (false, Some(TxKind::Call(addr))) => {
+                let code = self.inner.upstream.get_code(addr).await?;
+                match delegation_designator(&code) {
+                    // the delegation was cleared
+                    Some(addr) if addr.is_zero() => {
+                        return Err(TraverseWalletError::DelegationCleared);
+                    }
+                    // A valid EIP-7702 delegation
+                    Some(addr) => Some(addr),
+                    // No code, e.g. an EOA without a delegation or an empty (cleared) delegation
+                    None if code.is_empty() => {
+                        return Err(TraverseWalletError::DestinationNotDelegated);
+                    }
+                    // Not an EIP-7702 delegation, or a malformed designator
+                    None => {
+                        return Err(TraverseWalletError::DestinationNotADelegation);
+                    }
+                }
+            }
+            // the destination of eip-7702 txs must be an EOA or a delegated account, if enabled
+            (true, Some(TxKind::Call(addr)))
+                if self.inner.config.validate_authorization_destination
+                    && known_delegation.is_none() =>
+            {
+                let code = self.inner.upstream.get_code(addr).await?;
+                if !code.is_empty() && delegation_designator(&code).is_none() {
+                    return Err(TraverseWalletError::DestinationNotADelegation);
+                }
+                None
+            }
+            // the authorizations of eip-7702 txs are validated in the precheck, and create tx's
+            // are rejected there
+            _ => None,
+        };
+
+        // ensure the delegation of the destination is not too recent
+        if let (Some(min_age), Some(TxKind::Call(destination)), Some(_)) =
+            (self.inner.config.min_delegation_age, request.to, delegation)
+        {
+            let min_age = min_age.min(MAX_MIN_DELEGATION_AGE);
+            let changed_at = self
+                .upstream_call(self.inner.upstream.code_changed_within(destination, min_age))
+                .await?;
+            if let Some(changed_at) = changed_at {
+                return Err(TraverseWalletError::DelegationTooRecent { changed_at });
+            }
+        }
+
+        // ensure the delegation of the destination does not delegate further than allowed
+        let max_depth = self.inner.config.max_delegation_depth;
+        if let (true, Some(TxKind::Call(destination)), Some(delegation)) =
+            (max_depth > 1, request.to, delegation)
+        {
+            let chain = check_delegation_chain(destination, delegation, max_depth, |address| {
+                self.inner.upstream.get_code(address)
+            });
+            self.upstream_call(chain).await?;
+        }
+
+        Ok(delegation)
+    }
+
+    /// Validates, signs and sends the sponsored transaction of the request, see
+    /// [`TraverseWalletApiServer::send_transaction`].
+    async fn sponsor(
+        &self,
+        mut request: TransactionRequest,
+        gas_limit_hint: Option<u64>,
+    ) -> Result<TxHash, TraverseWalletError> {
+        if self.is_paused() {
+            self.inner.metrics.invalid_send_transaction_calls.increment(1);
+            return Err(TraverseWalletError::SponsoringPaused);
+        }
+
+        // validate the request without network access
+        if let Err(err) = self.precheck(&request) {
+            self.inner.metrics.invalid_send_transaction_calls.increment(1);
+            return Err(err);
+        }
+
+        // keep the fees of the client, if they are honored and within the caps of the policy
+        let client_fees = match self.inner.config.fee_policy.client_fees(&request) {
+            Ok(client_fees) => client_fees,
+            Err(err) => {
+                self.inner.metrics.invalid_send_transaction_calls.increment(1);
+                return Err(err);
+            }
+        };
+
+        // clear the fields managed by the service
+        sanitize_request(&mut request, self.inner.config.access_list_policy);
+
+        // validate destination, and resolve the delegation target of the destination if any
+        let delegation = self
+            .validate_delegation(&request)
+            .await
+            .inspect_err(|_| self.inner.metrics.invalid_send_transaction_calls.increment(1))?;
+
+        // we acquire the permit here so that all following operations are performed exclusively
+        let _permit = self.inner.permit.lock().await;
+
+        // set chain id
+        request.chain_id = Some(self.chain_id());
+
+        // set gas limit
+        // note: we also set the `from` field here to correctly estimate for contracts that use e.g.
+        // `tx.origin`
+        request.from = Some(self.inner.upstream.default_signer_address());
+        let (mut estimate, fee_estimate) = self
+            .estimate(&request)
+            .await
+            .inspect_err(|_| self.inner.metrics.invalid_send_transaction_calls.increment(1))?;
+
+        // add an access list if it lowers the gas usage of the request
+        if self.inner.config.access_list {
+            estimate = self
+                .apply_access_list(&mut request, estimate)
+                .await
+                .inspect_err(|_| self.inner.metrics.invalid_send_transaction_calls.increment(1))?;
+        }
+
+        // a transaction with a gas limit below its intrinsic gas can never be included
+        let intrinsic_gas = intrinsic_gas(&request);
+        if estimate < intrinsic_gas {
+            self.inner.metrics.invalid_send_transaction_calls.increment(1);
+            return Err(TraverseWalletError::InvalidGasEstimate { estimate, intrinsic_gas });
+        }
+
+        // price the transaction with the fees of the client, or with the fees derived by the
+        // service
+        let fee_estimate = match client_fees {
+            Some(fees) => {
+                // the max fee must cover the priority fee, and the base fee of the next block
+                let max_priority_fee = fees.max_priority_fee_per_gas;
+                let max_fee = fees.max_fee_per_gas;
+                let history = self.upstream_call(self.inner.upstream.fee_history(1, 0.0));
+                let history = match history.await {
+                    Ok(history) => history,
+                    Err(err) => {
+                        self.inner.metrics.invalid_send_transaction_calls.increment(1);
+                        return Err(err);
+                    }
+                };
+                let below_base_fee =
+                    history.next_block_base_fee().is_some_and(|base_fee| max_fee < base_fee);
+                if max_priority_fee > max_fee || below_base_fee {
+                    self.inner.metrics.invalid_send_transaction_calls.increment(1);
+                    return Err(TraverseWalletError::InconsistentFees {
+                        max_fee,
+                        max_priority_fee,
+                    });
+                }
+                fees
+            }
+            None => self
+                .service_fees(fee_estimate)
+                .await
+                .inspect_err(|_| self.inner.metrics.invalid_send_transaction_calls.increment(1))?,
+        };
+        // add headroom for differences in execution between estimation and inclusion, the ceiling
+        // applies to the buffered gas limit
+        let gas = self.inner.config.buffered_gas(estimate);
+        let destination = request.to.and_then(|to| to.to().copied());
+        let ceiling = self.inner.config.gas_ceiling(destination, delegation);
+        if gas >= ceiling {
+            self.inner.metrics.invalid_send_transaction_calls.increment(1);
+            return Err(TraverseWalletError::GasEstimateTooHigh { estimate: gas });
+        }
+        // the hint of the client can only raise the gas limit, up to the same ceiling
+        if let Some(hint) = gas_limit_hint.filter(|hint| *hint >= ceiling) {
+            self.inner.metrics.invalid_send_transaction_calls.increment(1);
+            return Err(TraverseWalletError::GasLimitHintTooHigh { hint, ceiling });
+        }
+        let gas = gas.max(gas_limit_hint.unwrap_or_default());
+        request.gas = Some(gas);
+
+        // the max fee is capped absolutely, independent of estimation
+        let max_fee = fee_estimate.max_fee_per_gas;
+        if self.inner.config.max_fee_per_gas_cap.is_some_and(|cap| max_fee > cap) {
+            self.inner.metrics.invalid_send_transaction_calls.increment(1);
+            return Err(TraverseWalletError::FeeTooHigh { max_fee });
+        }
+        request.max_fee_per_gas = Some(fee_estimate.max_fee_per_gas);
+        request.max_priority_fee_per_gas = Some(fee_estimate.max_priority_fee_per_gas);
+        request.gas_price = None;
+
+        // the L1 data fee is charged on top of the gas, it is accounted as gas at the max fee
+        let l1_data_fee = if self.inner.config.l1_data_fee {
+            self.upstream_call(self.inner.upstream.l1_data_fee(&request))
+                .await
+                .inspect_err(|_| self.inner.metrics.invalid_send_transaction_calls.increment(1))?
+        } else {
+            U256::ZERO
+        };
+        let sponsored_gas = sponsored_gas(gas, l1_data_fee, max_fee);
+
+        // ensure we stay within the budget of sponsored gas that is not mined yet
+        if self.inner.config.max_in_flight_gas.is_some_and(|max_in_flight_gas| {
+            self.inner.in_flight.lock().total.saturating_add(sponsored_gas) > max_in_flight_gas
+        }) {
+            self.inner.metrics.invalid_send_transaction_calls.increment(1);
+            return Err(TraverseWalletError::SponsorBudgetExceeded);
+        }
+
+        // ensure the sender stays within its budget, the cost is bounded by the max fee
+        let cost = U256::from(gas) * U256::from(max_fee) + l1_data_fee;
+        let now = Instant::now();
+        if let (Some(budget), Some(sender)) = (self.inner.config.sender_daily_budget, destination) {
+            if self.inner.sender_spend.lock().spent(sender, now).saturating_add(cost) > budget {
+                self.inner.metrics.invalid_send_transaction_calls.increment(1);
+                return Err(TraverseWalletError::SenderBudgetExceeded(sender));
+            }
+        }
+
+        // all checks passed, increment the valid calls counter
+        self.inner.metrics.valid_send_transaction_calls.increment(1);
+
+        let sent = async {
+            if self.inner.config.raw_tx_events {
+                self.inner.upstream.sign_and_send_raw(request).await
+            } else {
+                self.inner.upstream.sign_and_send(request).await.map(|tx_hash| (tx_hash, None))
+            }
+        };
+        let (tx_hash, raw_transaction) = self.upstream_call(sent).await.inspect_err(
+            |err| warn!(target: "rpc::wallet", ?err, "Error adding sponsored tx to pool"),
+        )?;
+        self.inner.in_flight.lock().reserve(tx_hash, sponsored_gas);
+        self.inner.metrics.sponsored_gas_used.increment(sponsored_gas);
+        if let (Some(_), Some(sender)) = (self.inner.config.sender_daily_budget, destination) {
+            self.inner.sender_spend.lock().record(sender, cost, now);
+        }
+
+        // notify subscribers, if there are any
+        let _ = self.inner.events.send(SponsoredTxEvent {
+            chain_id: self.chain_id(),
+            tx_hash,
+            to: destination,
+            delegation,
+            gas,
+            raw_transaction,
+        });
+
+        Ok(tx_hash)
+    }
+
+    /// Simulates the sponsored transaction of the request with the call tracer of the upstream,
+    /// see [`TraverseWalletApiServer::trace_sponsored_transaction`].
+    async fn trace_sponsored(
+        &self,
+        mut request: TransactionRequest,
+    ) -> Result<TraceResult, TraverseWalletError> {
+        if !self.inner.config.trace_sponsored_transactions {
+            return Err(TraverseWalletError::TracingDisabled);
+        }
+        if self.is_paused() {
+            return Err(TraverseWalletError::SponsoringPaused);
+        }
+        self.precheck(&request)?;
+
+        // the transaction is simulated as sent by the sponsor
+        sanitize_request(&mut request, self.inner.config.access_list_policy);
+        self.validate_delegation(&request).await?;
+        request.chain_id = Some(self.chain_id());
+        request.from = Some(self.inner.upstream.default_signer_address());
+
+        self.upstream_call(self.inner.upstream.trace_call(&request)).await
+    }
+
+    /// Derives the fees of a sponsored transaction from the point estimate of the upstream.
+    ///
+    /// The estimate is raised to the recent fee history if configured, then the
+    /// [`FeeStrategy`] is applied, and the priority fee is capped to avoid over-tipping.
+    async fn service_fees(
+        &self,
+        estimate: Eip1559Estimation,
+    ) -> Result<Eip1559Estimation, TraverseWalletError> {
+        // consult the recent fee history, and use it if it is higher than the point estimate
+        let estimate = match self.inner.config.fee_history {
+            Some(FeeHistoryConfig { block_count, percentile }) => {
+                let history = self
+                    .upstream_call(self.inner.upstream.fee_history(block_count, percentile))
+                    .await?;
+                max_fee_estimate(estimate, fee_history_estimate(&history))
+            }
+            None => estimate,
+        };
+        // apply the fee strategy, the percentile strategy consults the fee history of its own
+        // percentile
+        let history = match self.inner.config.fee_strategy {
+            FeeStrategy::Percentile(percentile) => {
+                let block_count = self
+                    .inner
+                    .config
+                    .fee_history
+                    .map_or(DEFAULT_FEE_STRATEGY_BLOCK_COUNT, |config| config.block_count);
+                let percentile = f64::from(percentile.min(100));
+                Some(
+                    self.upstream_call(self.inner.upstream.fee_history(block_count, percentile))
+                        .await?,
+                )
+            }
+            _ => None,
+        };
+        let estimate = self.inner.config.fee_strategy.apply(estimate, history.as_ref());
+        Ok(self.inner.config.cap_priority_fee(estimate))
+    }
+}
+
+impl<T> Clone for TraverseWallet<T> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+#[async_trait]
+impl<T> TraverseWalletApiServer for TraverseWallet<T>
+where
+    T: Upstream + Sync + Send + 'static,
+{
+    async fn send_transaction(
+        &self,
+        request: TransactionRequest,
+        gas_limit_hint: Option<u64>,
+    ) -> RpcResult<TxHash> {
+        let started_at = Instant::now();
+        trace!(
+            target: "rpc::wallet",
+            ?request,
+            ?gas_limit_hint,
+            "Serving traverse_sendTransaction"
+        );
+
+        let result = self.sponsor(request, gas_limit_hint).await;
+        self.inner.stats.lock().record(&result);
+        if let Err(err) = &result {
+            if err.is_internal() {
+                *self.inner.last_internal_error.lock() = Some(err.to_string());
+            }
+            debug!(target: "rpc::wallet", reason = err.reason(), %err, "Rejected sponsored tx");
+            metrics::counter!(REJECTED_SEND_TRANSACTION_CALLS, "reason" => err.reason())
+                .increment(1);
+        }
+        let outcome = if result.is_ok() { "success" } else { "failure" };
+        metrics::histogram!(SEND_TRANSACTION_DURATION, "outcome" => outcome)
+            .record(started_at.elapsed());
+        Ok(result?)
+    }
+
+    async fn get_limits(&self, chain_id: Option<ChainId>) -> RpcResult<WalletLimits> {
+        trace!(target: "rpc::wallet", ?chain_id, "Serving wallet_getLimits");
+
+        if chain_id.is_some_and(|chain_id| chain_id != self.chain_id()) {
+            return Err(TraverseWalletError::UnsupportedChainId(chain_id).into());
+        }
+
+        let in_flight_gas = self.inner.in_flight.lock().total;
+        Ok(WalletLimits {
+            gas_ceiling: self.inner.config.default_gas_ceiling,
+            in_flight_gas,
+            in_flight_gas_remaining: self
+                .inner
+                .config
+                .max_in_flight_gas
+                .map(|max_in_flight_gas| max_in_flight_gas.saturating_sub(in_flight_gas)),
+        })
+    }
+
+    async fn get_stats(&self, chain_id: Option<ChainId>) -> RpcResult<WalletStats> {
+        trace!(target: "rpc::wallet", ?chain_id, "Serving wallet_getStats");
+
+        if chain_id.is_some_and(|chain_id| chain_id != self.chain_id()) {
+            return Err(TraverseWalletError::UnsupportedChainId(chain_id).into());
+        }
+
+        Ok(self.inner.stats.lock().clone())
+    }
+
+    async fn get_delegation(
+        &self,
+        address: Address,
+        chain_id: Option<ChainId>,
+    ) -> RpcResult<Option<Address>> {
+        trace!(target: "rpc::wallet", ?address, ?chain_id, "Serving wallet_getDelegation");
+
+        if chain_id.is_some_and(|chain_id| chain_id != self.chain_id()) {
+            return Err(TraverseWalletError::UnsupportedChainId(chain_id).into());
+        }
+
+        let code = self.upstream_call(self.inner.upstream.get_code(address)).await?;
+        Ok(delegation_designator(&code).filter(|delegation| !delegation.is_zero()))
+    }
+
+    async fn trace_sponsored_transaction(
+        &self,
+        request: TransactionRequest,
+    ) -> RpcResult<TraceResult> {
+        trace!(target: "rpc::wallet", ?request, "Serving wallet_traceSponsoredTransaction");
+
+        Ok(self.trace_sponsored(request).await?)
+    }
+
+    async fn subscribe_sponsored_transactions(
+        &self,
+        pending: PendingSubscriptionSink,
+    ) -> SubscriptionResult {
+        trace!(target: "rpc::wallet", "Serving wallet_subscribeSponsoredTransactions");
+
+        let events = event_stream(
+            self.sponsored_transactions(),
+            self.inner.metrics.dropped_sponsored_tx_events.clone(),
+        );
+        pipe_sponsored_transactions(pending, events).await
+    }
+}
+
+/// Turns the receiver of [`SponsoredTxEvent`]s into a stream, which ends once the channel is
+/// closed.
+///
+/// The events the receiver misses by lagging behind are counted in the given counter.
+fn event_stream(
+    receiver: broadcast::Receiver<SponsoredTxEvent>,
+    dropped: Counter,
+) -> impl Stream<Item = Result<SponsoredTxEvent, RecvError>> + Send + Unpin {
+    Box::pin(futures::stream::unfold(receiver, move |mut receiver| {
+        let dropped = dropped.clone();
+        async move {
+            match receiver.recv().await {
+                Err(RecvError::Closed) => None,
+                Err(RecvError::Lagged(skipped)) => {
+                    dropped.increment(skipped);
+                    Some((Err(RecvError::Lagged(skipped)), receiver))
+                }
+                event => Some((event, receiver)),
+            }
+        }
+    }))
+}
+
+/// Sends the [`SponsoredTxEvent`]s to the subscriber, until it unsubscribes or falls behind.
+async fn pipe_sponsored_transactions(
+    pending: PendingSubscriptionSink,
+    mut events: impl Stream<Item = Result<SponsoredTxEvent, RecvError>> + Unpin,
+) -> SubscriptionResult {
+    let sink = pending.accept().await?;
+
+    loop {
+        tokio::select! {
+            _ = sink.closed() => break,
+            event = events.next() => match event {
+                Some(Ok(event)) => sink.send(SubscriptionMessage::from_json(&event)?).await?,
+                // the subscriber is too slow, drop it instead of buffering indefinitely
+                Some(Err(RecvError::Lagged(skipped))) => {
+                    warn!(
+                        target: "rpc::wallet",
+                        skipped,
+                        "Dropping lagging sponsored tx subscriber"
+                    );
+                    break
+                }
+                Some(Err(RecvError::Closed)) | None => break,
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// A wallet that sponsors transactions on multiple chains.
+///
+/// Requests are dispatched to the [`TraverseWallet`] of the chain id set in the request, instead
+/// of overwriting it. Each chain has its own upstream and [`TraverseWalletConfig`], and thus its
+/// own signer, delegation allowlist and gas ceilings.
+#[derive(Debug)]
+pub struct MultiChainWallet<T> {
+    wallets: HashMap<ChainId, TraverseWallet<T>>,
+}
+
+impl<T> Default for MultiChainWallet<T> {
+    fn default() -> Self {
+        Self { wallets: HashMap::new() }
+    }
+}
+
+impl<T> Clone for MultiChainWallet<T> {
+    fn clone(&self) -> Self {
+        Self { wallets: self.wallets.clone() }
+    }
+}
+
+impl<T> MultiChainWallet<T> {
+    /// Create a new multi-chain wallet without any chains.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds the wallet for its chain, replacing any wallet for the same chain.
+    pub fn with_chain(mut self, wallet: TraverseWallet<T>) -> Self {
+        self.wallets.insert(wallet.chain_id(), wallet);
+        self
+    }
+
+    /// Returns the wallet of the given chain, if any.
+    pub fn wallet(&self, chain_id: ChainId) -> Option<&TraverseWallet<T>> {
+        self.wallets.get(&chain_id)
+    }
+
+    /// Returns the wallet of the given chain, or an error if the chain is not supported.
+    fn dispatch(
+        &self,
+        chain_id: Option<ChainId>,
+    ) -> Result<&TraverseWallet<T>, TraverseWalletError> {
+        chain_id
+            .and_then(|chain_id| self.wallet(chain_id))
+            .ok_or(TraverseWalletError::UnsupportedChainId(chain_id))
+    }
+}
+
+#[async_trait]
+impl<T> TraverseWalletApiServer for MultiChainWallet<T>
+where
+    T: Upstream + Sync + Send + 'static,
+{
+    async fn send_transaction(
+        &self,
+        request: TransactionRequest,
+        gas_limit_hint: Option<u64>,
+    ) -> RpcResult<TxHash> {
+        self.dispatch(request.chain_id)?.send_transaction(request, gas_limit_hint).await
+    }
+
+    async fn get_limits(&self, chain_id: Option<ChainId>) -> RpcResult<WalletLimits> {
+        self.dispatch(chain_id)?.get_limits(chain_id).await
+    }
+
+    async fn get_stats(&self, chain_id: Option<ChainId>) -> RpcResult<WalletStats> {
+        self.dispatch(chain_id)?.get_stats(chain_id).await
+    }
+
+    async fn get_delegation(
+        &self,
+        address: Address,
+        chain_id: Option<ChainId>,
+    ) -> RpcResult<Option<Address>> {
+        self.dispatch(chain_id)?.get_delegation(address, chain_id).await
+    }
+
+    async fn trace_sponsored_transaction(
+        &self,
+        request: TransactionRequest,
+    ) -> RpcResult<TraceResult> {
+        self.dispatch(request.chain_id)?.trace_sponsored_transaction(request).await
+    }
+
+    async fn subscribe_sponsored_transactions(
+        &self,
+        pending: PendingSubscriptionSink,
+    ) -> SubscriptionResult {
+        trace!(target: "rpc::wallet", "Serving wallet_subscribeSponsoredTransactions");
+
+        let events = futures::stream::select_all(self.wallets.values().map(|wallet| {
+            event_stream(
+                wallet.sponsored_transactions(),
+                wallet.inner.metrics.dropped_sponsored_tx_events.clone(),
+            )
+        }));
+        pipe_sponsored_transactions(pending, events).await
+    }
+}
+
+#[async_trait]
+impl<T> TraverseWalletAdminApiServer for TraverseWallet<T>
+where
+    T: Upstream + Send + Sync + 'static,
+{
+    async fn pause_sponsoring(&self) -> RpcResult<()> {
+        warn!(target: "rpc::wallet", "Pausing sponsored transactions");
+        self.pause();
+        Ok(())
+    }
+
+    async fn resume_sponsoring(&self) -> RpcResult<()> {
+        warn!(target: "rpc::wallet", "Resuming sponsored transactions");
+        self.resume();
+        Ok(())
+    }
+
+    async fn add_known_delegation(&self, account: Address, delegation: Address) -> RpcResult<()> {
+        debug!(target: "rpc::wallet", %account, %delegation, "Adding known delegation");
+        Ok(self.add_known_delegation(account, delegation)?)
+    }
+
+    async fn remove_known_delegation(&self, account: Address) -> RpcResult<Option<Address>> {
+        debug!(target: "rpc::wallet", %account, "Removing known delegation");
+        Ok(self.remove_known_delegation(account))
+    }
+
+    async fn get_last_internal_error(&self) -> RpcResult<Option<String>> {
+        trace!(target: "rpc::wallet", "Serving walletAdmin_getLastInternalError");
+        Ok(self.inner.last_internal_error.lock().clone())
+    }
+
+    async fn rotate_signer(&self, secret_key: B256) -> RpcResult<Address> {
+        let signer = PrivateKeySigner::from_bytes(&secret_key)
+            .map_err(|_| TraverseWalletError::InvalidSigner)?;
+        let address = self.rotate_signer(EthereumWallet::from(signer)).await?;
+        warn!(target: "rpc::wallet", %address, "Rotated the sponsor signer");
+        Ok(address)
+    }
+}
+
+/// The default gas ceiling of a sponsored transaction.
+pub const DEFAULT_GAS_CEILING: u64 = 350_000;
+
+/// The rolling window of [`TraverseWalletConfig::sender_daily_budget`].
+pub const SENDER_BUDGET_WINDOW: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The default timeout of calls to the [`Upstream`].
+pub const DEFAULT_UPSTREAM_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default timeout of gas estimates of the [`Upstream`].
+pub const DEFAULT_ESTIMATE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// The default maximum size of the input of a sponsored transaction, in bytes.
+pub const DEFAULT_MAX_INPUT_SIZE: usize = 128 * 1024;
+
+/// The default maximum number of authorizations of a sponsored transaction.
+pub const DEFAULT_MAX_AUTHORIZATIONS: usize = 16;
+
+/// How the access list of a sponsored request is treated.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessListPolicy {
+    /// The access list of the request is used for estimation and signing.
+    #[default]
+    Honor,
+    /// The access list of the request is removed, as it is managed by the service.
+    Strip,
+}
+
+/// Whether the fees supplied in a sponsored request are honored.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FeePolicy {
+    /// The fees of the request are cleared, and set by the service.
+    #[default]
+    Service,
+    /// If the request supplies both `maxFeePerGas` and `maxPriorityFeePerGas`, they are used
+    /// instead of the fees derived by the service.
+    ///
+    /// Client fees are not clamped: requests with a priority fee above `max_priority_fee_cap`
+    /// are rejected with [`TraverseWalletError::PriorityFeeTooHigh`], and requests with a max
+    /// fee above `max_fee_per_gas_cap` with [`TraverseWalletError::FeeTooHigh`]. Requests with a
+    /// priority fee above the max fee, or a max fee below the base fee of the next block, are
+    /// rejected with [`TraverseWalletError::InconsistentFees`]. Requests that supply only one of
+    /// the fees are priced by the service.
+    #[serde(rename_all = "camelCase")]
+    Client {
+        /// The maximum priority fee per gas supplied by a client.
+        max_priority_fee_cap: u128,
+        /// The maximum fee per gas supplied by a client.
+        ///
+        /// [`TraverseWalletConfig::max_fee_per_gas_cap`] still applies on top of it.
+        max_fee_per_gas_cap: u128,
+    },
+}
+
+impl FeePolicy {
+    /// Returns the fees supplied in the request, if they are honored by this policy.
+    ///
+    /// Returns an error if the honored fees are above the caps of [`Self::Client`].
+    pub const fn client_fees(
+        self,
+        request: &TransactionRequest,
+    ) -> Result<Option<Eip1559Estimation>, TraverseWalletError> {
+        let Self::Client { max_priority_fee_cap, max_fee_per_gas_cap } = self else {
+            return Ok(None);
+        };
+        let (Some(max_fee), Some(max_priority_fee)) =
+            (request.max_fee_per_gas, request.max_priority_fee_per_gas)
+        else {
+            return Ok(None);
+        };
+        if max_priority_fee > max_priority_fee_cap {
+            return Err(TraverseWalletError::PriorityFeeTooHigh { max_priority_fee });
+        }
+        if max_fee > max_fee_per_gas_cap {
+            return Err(TraverseWalletError::FeeTooHigh { max_fee });
+        }
+        Ok(Some(Eip1559Estimation {
+            max_fee_per_gas: max_fee,
+            max_priority_fee_per_gas: max_priority_fee,
+        }))
+    }
+}
+
+/// The default number of recent blocks consulted by [`FeeStrategy::Percentile`], unless a
+/// [`FeeHistoryConfig`] is set.
+pub const DEFAULT_FEE_STRATEGY_BLOCK_COUNT: u64 = 10;
+
+/// How the fees of a sponsored transaction are derived from the fee estimate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum FeeStrategy {
+    /// The fees are the estimate.
+    #[default]
+    Estimate,
+    /// Both fees of the estimate are multiplied by the given factor.
+    ///
+    /// Factors below `1.0` are treated as `1.0`.
+    Bump(f64),
+    /// The priority fee is fixed to the given tip, and the max fee leaves the same room for the
+    /// base fee as the estimate.
+    FixedTip(u128),
+    /// The fees are derived from the given percentile of the priority fees paid in recent blocks.
+    ///
+    /// Falls back to the estimate if the fee history is empty.
+    Percentile(u8),
+}
+
+impl FeeStrategy {
+    /// Applies the strategy to the given estimate.
+    ///
+    /// [`Self::Percentile`] derives the fees from the given fee history, which is expected to
+    /// contain the rewards of the percentile.
+    pub fn apply(
+        &self,
+        estimate: Eip1559Estimation,
+        history: Option<&FeeHistory>,
+    ) -> Eip1559Estimation {
+        match *self {
+            Self::Estimate => estimate,
+            Self::Bump(factor) => {
+                let bump = |fee: u128| (fee as f64 * factor.max(1.0)) as u128;
+                Eip1559Estimation {
+                    max_fee_per_gas: bump(estimate.max_fee_per_gas),
+                    max_priority_fee_per_gas: bump(estimate.max_priority_fee_per_gas),
+                }
+            }
+            Self::FixedTip(tip) => Eip1559Estimation {
+                max_fee_per_gas: estimate
+                    .max_fee_per_gas
+                    .saturating_sub(estimate.max_priority_fee_per_gas)
+                    .saturating_add(tip),
+                max_priority_fee_per_gas: tip,
+            },
+            Self::Percentile(_) => history.and_then(fee_history_estimate).unwrap_or(estimate),
+        }
+    }
+}
+
+/// The types of transactions the service can sponsor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SponsoredTxType {
+    /// An [EIP-1559][eip-1559] transaction to an account with delegated code.
+    ///
+    /// [eip-1559]: https://eips.ethereum.org/EIPS/eip-1559
+    Eip1559,
+    /// An [EIP-7702][eip-7702] transaction, delegating one or more accounts.
+    ///
+    /// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
+    Eip7702,
+}
+
+impl SponsoredTxType {
+    /// Returns the type of transaction the request would be sponsored as.
+    pub const fn of(request: &TransactionRequest) -> Self {
+        if request.authorization_list.is_some() {
+            Self::Eip7702
+        } else {
+            Self::Eip1559
+        }
+    }
+}
+
+impl std::fmt::Display for SponsoredTxType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Eip1559 => f.write_str("EIP-1559"),
+            Self::Eip7702 => f.write_str("EIP-7702"),
+        }
+    }
+}
+
+/// Configuration of pricing sponsored transactions based on the recent fee history.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeHistoryConfig {
+    /// The number of recent blocks to consult.
+    pub block_count: u64,
+    /// The percentile of the priority fees paid in each block.
+    pub percentile: f64,
+}
+
+/// Configuration for the [`TraverseWallet`].
+#[derive(Debug, Clone)]
+pub struct TraverseWalletConfig {
+    /// The delegation contracts the service sponsors delegations to.
+    ///
+    /// If this is not set, delegations to any contract are sponsored.
+    pub delegations: Option<DelegationCapability>,
+    /// The gas ceiling of a sponsored transaction, unless a specific ceiling is configured in
+    /// [`Self::gas_ceilings`].
+    ///
+    /// Requests estimated to use this much gas or more are rejected.
+    pub default_gas_ceiling: u64,
+    /// Gas ceilings for specific destinations or delegation targets.
+    ///
+    /// A ceiling configured for the destination of the transaction takes precedence over a
+    /// ceiling configured for the delegation target of the destination.
+    pub gas_ceilings: HashMap<Address, u64>,
+    /// The maximum amount of gas of sponsored transactions that are submitted, but not settled
+    /// yet.
+    ///
+    /// Transactions are settled with [`TraverseWallet::settle_transactions`]. If this is not
+    /// set, the amount of gas in flight is not limited.
+    pub max_in_flight_gas: Option<u64>,
+    /// The percentage added to the gas estimate of a request to derive its gas limit.
+    ///
+    /// Defaults to zero, i.e. the gas limit is the estimate.
+    pub gas_buffer_percent: u64,
+    /// The timeout of signing and sending a transaction, and of the other calls to the upstream
+    /// except gas estimates.
+    ///
+    /// A transaction that times out while being sent may still be included.
+    pub upstream_timeout: Duration,
+    /// The timeout of gas estimates of the upstream.
+    ///
+    /// This is separate from [`Self::upstream_timeout`], so that slow estimates are told apart
+    /// from slow signing, see [`TraverseWalletError::EstimateTimeout`].
+    pub estimate_timeout: Duration,
+    /// Whether to consult the recent fee history when pricing sponsored transactions.
+    ///
+    /// If this is set, the fees of a transaction are the maximum of the point estimate of the
+    /// upstream and the fees derived from the fee history.
+    pub fee_history: Option<FeeHistoryConfig>,
+    /// The types of transactions that are sponsored.
+    ///
+    /// Defaults to all [`SponsoredTxType`]s.
+    pub accepted_types: HashSet<SponsoredTxType>,
+    /// Whether to add an access list to sponsored transactions.
+    ///
+    /// The access list is generated by the upstream, and only added if it lowers the gas
+    /// estimate of the transaction. Defaults to `false`.
+    pub access_list: bool,
+    /// The maximum size of the input of a request, in bytes.
+    ///
+    /// Larger requests are rejected before any call to the upstream.
+    pub max_input_size: usize,
+    /// The maximum number of authorizations in the authorization list of a request.
+    ///
+    /// Requests with more authorizations are rejected with
+    /// [`TraverseWalletError::TooManyAuthorizations`] before any call to the upstream, which
+    /// bounds the cost of processing the authorizations of a sponsored transaction.
+    pub max_authorizations: usize,
+    /// Whether requests with an empty authorization list are rejected.
+    ///
+    /// An [EIP-7702][eip-7702] transaction without authorizations does not delegate anything, and
+    /// is invalid. If this is set, such requests are rejected with
+    /// [`TraverseWalletError::EmptyAuthorizationList`] before any call to the upstream. Defaults
+    /// to `true`.
+    ///
+    /// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
+    pub reject_empty_authorization_list: bool,
+    /// Whether the code of the destination of [EIP-7702][eip-7702] requests is validated.
+    ///
+    /// If this is set, requests with an authorization list are only sponsored if the destination
+    /// is an EOA, i.e. has no code, or is already delegated. Requests to other contracts are
+    /// rejected with [`TraverseWalletError::DestinationNotADelegation`]. Defaults to `false`.
+    ///
+    /// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
+    pub validate_authorization_destination: bool,
+    /// The minimum number of blocks the delegation of the destination must have existed for.
+    ///
+    /// Protects against delegating, draining through a sponsored transaction and undelegating in
+    /// quick succession. The code of the destination at the latest block is compared to its code
+    /// at the start of the window, and the block of the change is only searched for on a
+    /// mismatch, so the check costs `2 + log2(age)` code lookups in historical state, which must
+    /// be available for the whole window. Delegations that were changed and reverted within the
+    /// window, e.g. delegating to another contract and back, are not detected. The age is capped
+    /// at [`MAX_MIN_DELEGATION_AGE`]. If this is not set, the age of delegations is not checked.
+    pub min_delegation_age: Option<u64>,
+    /// The maximum number of delegations followed from the destination of a request.
+    ///
+    /// If the delegation target of the destination is itself delegated, the chain of delegation
+    /// designators is followed up to this depth. Chains that are longer, or that loop back to one
+    /// of their accounts, are rejected with [`TraverseWalletError::DelegationChainTooDeep`].
+    /// Defaults to `1`, i.e. the chain is not followed past the delegation of the destination.
+    pub max_delegation_depth: usize,
+    /// The maximum priority fee per gas of a sponsored transaction.
+    ///
+    /// Estimated priority fees above the cap are clamped to it. If this is not set, the priority
+    /// fee is not capped.
+    pub max_priority_fee_cap: Option<u128>,
+    /// How the access list of a request is treated.
+    ///
+    /// Defaults to [`AccessListPolicy::Honor`].
+    pub access_list_policy: AccessListPolicy,
+    /// How the fees of sponsored transactions are derived from the fee estimate.
+    ///
+    /// The strategy is applied after the fee history is consulted, and before the priority fee is
+    /// capped. Defaults to [`FeeStrategy::Estimate`].
+    pub fee_strategy: FeeStrategy,
+    /// The maximum fee per gas of a sponsored transaction.
+    ///
+    /// Unlike [`Self::max_priority_fee_cap`], requests priced above the cap are rejected rather
+    /// than clamped. This is checked after the [`Self::fee_strategy`] is applied, right before
+    /// signing. If this is not set, the max fee is not limited.
+    pub max_fee_per_gas_cap: Option<u128>,
+    /// Whether the fees supplied in a request are honored.
+    ///
+    /// Defaults to [`FeePolicy::Service`], i.e. the fees are always set by the service.
+    pub fee_policy: FeePolicy,
+    /// Whether the L1 data fee of sponsored transactions is accounted for.
+    ///
+    /// OP chains charge an L1 data fee on top of the L2 gas, which is estimated with
+    /// [`Upstream::l1_data_fee`] if this is set. The fee is converted to gas at the max fee per
+    /// gas, and included in the in-flight gas, the `wallet.sponsored_gas_used` metric, and the
+    /// cost charged to the [`Self::sender_daily_budget`]. Defaults to `false`.
+    pub l1_data_fee: bool,
+    /// Whether [`SponsoredTxEvent`]s include the encoded signed transaction.
+    ///
+    /// This increases the size of every event by the size of the transaction, which is dominated
+    /// by its input and authorization list. Defaults to `false`.
+    pub raw_tx_events: bool,
+    /// The number of [`SponsoredTxEvent`]s buffered for each subscriber.
+    ///
+    /// Subscribers that fall behind by more events miss them, which is counted in the wallet
+    /// metrics. A larger capacity tolerates slower subscribers at the cost of memory. Defaults to
+    /// [`SPONSORED_TX_EVENT_CAPACITY`].
+    pub sponsored_tx_event_capacity: usize,
+    /// The maximum cost of the transactions sponsored for a single sender within the
+    /// [`SENDER_BUDGET_WINDOW`], in wei.
+    ///
+    /// The sender of a sponsored transaction is its destination, i.e. the delegated account. The
+    /// cost of a transaction is its gas limit times its max fee per gas, plus its L1 data fee if
+    /// [`Self::l1_data_fee`] is set. Spends are tracked in memory only. If this is not set, the
+    /// cost per sender is not limited.
+    pub sender_daily_budget: Option<U256>,
+    /// The destinations the service sponsors transactions to.
+    ///
+    /// This applies to the destination of [EIP-1559][eip-1559] transactions, and to the `to`
+    /// field of [EIP-7702][eip-7702] transactions. If this is not set, transactions to any
+    /// destination are sponsored, subject to the other checks.
+    ///
+    /// [eip-1559]: https://eips.ethereum.org/EIPS/eip-1559
+    /// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
+    pub destinations: Option<HashSet<Address>>,
+    /// Whether `wallet_traceSponsoredTransaction` is enabled.
+    ///
+    /// Traces expose the execution of the delegation contracts, and every call costs the
+    /// upstream a traced execution, so this is opt-in. Defaults to `false`.
+    pub trace_sponsored_transactions: bool,
+}
+
+impl Default for TraverseWalletConfig {
+    fn default() -> Self {
+        Self {
+            delegations: None,
+            default_gas_ceiling: DEFAULT_GAS_CEILING,
+            gas_ceilings: HashMap::new(),
+            max_in_flight_gas: None,
+            gas_buffer_percent: 0,
+            upstream_timeout: DEFAULT_UPSTREAM_TIMEOUT,
+            estimate_timeout: DEFAULT_ESTIMATE_TIMEOUT,
+            fee_history: None,
+            accepted_types: HashSet::from([SponsoredTxType::Eip1559, SponsoredTxType::Eip7702]),
+            access_list: false,
+            max_input_size: DEFAULT_MAX_INPUT_SIZE,
+            max_authorizations: DEFAULT_MAX_AUTHORIZATIONS,
+            reject_empty_authorization_list: true,
+            validate_authorization_destination: false,
+            min_delegation_age: None,
+            max_delegation_depth: 1,
+            max_priority_fee_cap: None,
+            access_list_policy: AccessListPolicy::Honor,
+            fee_strategy: FeeStrategy::Estimate,
+            max_fee_per_gas_cap: None,
+            fee_policy: FeePolicy::Service,
+            l1_data_fee: false,
+            raw_tx_events: false,
+            sponsored_tx_event_capacity: SPONSORED_TX_EVENT_CAPACITY,
+            sender_daily_budget: None,
+            destinations: None,
+            trace_sponsored_transactions: false,
+        }
+    }
+}
+
+impl TraverseWalletConfig {
+    /// Sets the default gas ceiling of a sponsored transaction.
+    pub const fn with_default_gas_ceiling(mut self, ceiling: u64) -> Self {
+        self.default_gas_ceiling = ceiling;
+        self
+    }
+
+    /// Sets the percentage added to gas estimates, see [`Self::gas_buffer_percent`].
+    pub const fn with_gas_buffer_percent(mut self, percent: u64) -> Self {
+        self.gas_buffer_percent = percent;
+        self
+    }
+
+    /// Sets the timeout of calls to the upstream, see [`Self::upstream_timeout`].
+    pub const fn with_upstream_timeout(mut self, timeout: Duration) -> Self {
+        self.upstream_timeout = timeout;
+        self
+    }
+
+    /// Sets the timeout of gas estimates, see [`Self::estimate_timeout`].
+    pub const fn with_estimate_timeout(mut self, timeout: Duration) -> Self {
+        self.estimate_timeout = timeout;
+        self
+    }
+
+    /// Consults the fee history when pricing sponsored transactions, see [`Self::fee_history`].
+    pub const fn with_fee_history(mut self, fee_history: FeeHistoryConfig) -> Self {
+        self.fee_history = Some(fee_history);
+        self
+    }
+
+    /// Only sponsors transactions of the given types.
+    pub fn with_accepted_types(mut self, types: impl IntoIterator<Item = SponsoredTxType>) -> Self {
+        self.accepted_types = types.into_iter().collect();
+        self
+    }
+
+    /// Adds access lists to sponsored transactions, see [`Self::access_list`].
+    pub const fn with_access_list(mut self, access_list: bool) -> Self {
+        self.access_list = access_list;
+        self
+    }
+
+    /// Sets the maximum size of the input of a request, see [`Self::max_input_size`].
+    pub const fn with_max_input_size(mut self, max_input_size: usize) -> Self {
+        self.max_input_size = max_input_size;
+        self
+    }
+
+    /// Sets the maximum number of authorizations of a request, see
+    /// [`Self::max_authorizations`].
+    pub const fn with_max_authorizations(mut self, max_authorizations: usize) -> Self {
+        self.max_authorizations = max_authorizations;
+        self
+    }
+
+    /// Sets whether requests with an empty authorization list are rejected, see
+    /// [`Self::reject_empty_authorization_list`].
+    pub const fn with_reject_empty_authorization_list(mut self, reject: bool) -> Self {
+        self.reject_empty_authorization_list = reject;
+        self
+    }
+
+    /// Validates the code of the destination of [EIP-7702][eip-7702] requests, see
+    /// [`Self::validate_authorization_destination`].
+    ///
+    /// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
+    pub const fn with_validate_authorization_destination(mut self, enabled: bool) -> Self {
+        self.validate_authorization_destination = enabled;
+        self
+    }
+
+    /// Sets the minimum age of delegations in blocks, see [`Self::min_delegation_age`].
+    ///
+    /// The age is capped at [`MAX_MIN_DELEGATION_AGE`].
+    pub const fn with_min_delegation_age(mut self, blocks: u64) -> Self {
+        self.min_delegation_age =
+            Some(if blocks > MAX_MIN_DELEGATION_AGE { MAX_MIN_DELEGATION_AGE } else { blocks });
+        self
+    }
+
+    /// Sets the maximum number of delegations followed from the destination, see
+    /// [`Self::max_delegation_depth`].
+    pub const fn with_max_delegation_depth(mut self, depth: usize) -> Self {
+        self.max_delegation_depth = depth;
+        self
+    }
+
+    /// Sets how the access list of a request is treated, see [`Self::access_list_policy`].
+    pub const fn with_access_list_policy(mut self, policy: AccessListPolicy) -> Self {
+        self.access_list_policy = policy;
+        self
+    }
+
+    /// Sets how the fees of sponsored transactions are derived, see [`Self::fee_strategy`].
+    pub const fn with_fee_strategy(mut self, fee_strategy: FeeStrategy) -> Self {
+        self.fee_strategy = fee_strategy;
+        self
+    }
+
+    /// Caps the priority fee of sponsored transactions, see [`Self::max_priority_fee_cap`].
+    pub const fn with_max_priority_fee_cap(mut self, cap: u128) -> Self {
+        self.max_priority_fee_cap = Some(cap);
+        self
+    }
+
+    /// Rejects sponsored transactions priced above the given max fee per gas, see
+    /// [`Self::max_fee_per_gas_cap`].
+    pub const fn with_max_fee_per_gas_cap(mut self, cap: u128) -> Self {
+        self.max_fee_per_gas_cap = Some(cap);
+        self
+    }
+
+    /// Sets whether the fees supplied in a request are honored, see [`Self::fee_policy`].
+    pub const fn with_fee_policy(mut self, policy: FeePolicy) -> Self {
+        self.fee_policy = policy;
+        self
+    }
+
+    /// Accounts for the L1 data fee of sponsored transactions, see [`Self::l1_data_fee`].
+    pub const fn with_l1_data_fee(mut self, enabled: bool) -> Self {
+        self.l1_data_fee = enabled;
+        self
+    }
+
+    /// Includes the encoded signed transaction in [`SponsoredTxEvent`]s, see
+    /// [`Self::raw_tx_events`].
+    pub const fn with_raw_tx_events(mut self, enabled: bool) -> Self {
+        self.raw_tx_events = enabled;
+        self
+    }
+
+    /// Enables `wallet_traceSponsoredTransaction`, see [`Self::trace_sponsored_transactions`].
+    pub const fn with_trace_sponsored_transactions(mut self, enabled: bool) -> Self {
+        self.trace_sponsored_transactions = enabled;
+        self
+    }
+
+    /// Sets the number of [`SponsoredTxEvent`]s buffered for each subscriber, see
+    /// [`Self::sponsored_tx_event_capacity`].
+    pub const fn with_sponsored_tx_event_capacity(mut self, capacity: usize) -> Self {
+        self.sponsored_tx_event_capacity = capacity;
+        self
+    }
+
+    /// Clamps the priority fee of the given estimate to [`Self::max_priority_fee_cap`].
+    ///
+    /// The max fee is kept at least as high as the clamped priority fee.
+    pub fn cap_priority_fee(&self, estimate: Eip1559Estimation) -> Eip1559Estimation {
+        let max_priority_fee_per_gas =
+            self.max_priority_fee_cap.map_or(estimate.max_priority_fee_per_gas, |cap| {
+                estimate.max_priority_fee_per_gas.min(cap)
+            });
+
+        Eip1559Estimation {
+            max_fee_per_gas: estimate.max_fee_per_gas.max(max_priority_fee_per_gas),
+            max_priority_fee_per_gas,
+        }
+    }
+
+    /// Returns the gas limit for a request with the given gas estimate.
+    pub const fn buffered_gas(&self, estimate: u64) -> u64 {
+        estimate.saturating_add(estimate.saturating_mul(self.gas_buffer_percent) / 100)
+    }
+
+    /// Sets the gas ceiling for transactions to the given destination or delegation target.
+    pub fn with_gas_ceiling(mut self, target: Address, ceiling: u64) -> Self {
+        self.gas_ceilings.insert(target, ceiling);
+        self
+    }
+
+    /// Returns the gas ceiling for a transaction to the given destination, which is delegated to
+    /// the given delegation target.
+    pub fn gas_ceiling(&self, destination: Option<Address>, delegation: Option<Address>) -> u64 {
+        destination
+            .and_then(|destination| self.gas_ceilings.get(&destination))
+            .or_else(|| delegation.and_then(|delegation| self.gas_ceilings.get(&delegation)))
+            .copied()
+            .unwrap_or(self.default_gas_ceiling)
+    }
+
+    /// Only sponsor delegations to the contracts in the given [`DelegationCapability`].
+    pub fn with_delegations(mut self, delegations: DelegationCapability) -> Self {
+        self.delegations = Some(delegations);
+        self
+    }
+
+    /// Only sponsor transactions to the given destinations, see [`Self::destinations`].
+    pub fn with_destinations(mut self, destinations: impl IntoIterator<Item = Address>) -> Self {
+        self.destinations = Some(destinations.into_iter().collect());
+        self
+    }
+
+    /// Sets the maximum amount of gas of sponsored transactions in flight.
+    pub const fn with_max_in_flight_gas(mut self, max_in_flight_gas: u64) -> Self {
+        self.max_in_flight_gas = Some(max_in_flight_gas);
+        self
+    }
+
+    /// Limits the cost of the transactions sponsored per sender, see
+    /// [`Self::sender_daily_budget`].
+    pub const fn with_sender_daily_budget(mut self, budget: U256) -> Self {
+        self.sender_daily_budget = Some(budget);
+        self
+    }
+}
+
+/// Implementation of the Traverse `wallet_` namespace.
+#[derive(Debug)]
+struct TraverseWalletInner<T> {
+    upstream: T,
+    chain_id: ChainId,
+    config: TraverseWalletConfig,
+    /// Used to guard tx signing
+    permit: Mutex<()>,
+    /// Whether sponsoring transactions is paused.
+    paused: AtomicBool,
+    /// Sponsored transactions that are submitted, but not settled yet.
+    in_flight: parking_lot::Mutex<InFlightGas>,
+    /// The cost of the transactions sponsored per sender within the [`SENDER_BUDGET_WINDOW`].
+    sender_spend: parking_lot::Mutex<SenderSpend>,
+    /// The delegation contracts of accounts known to be delegated.
+    known_delegations: parking_lot::RwLock<HashMap<Address, Address>>,
+    /// A summary of the calls to `wallet_sendTransaction`.
+    stats: parking_lot::Mutex<WalletStats>,
+    /// The message of the last internal error of `wallet_sendTransaction`.
+    last_internal_error: parking_lot::Mutex<Option<String>>,
+    /// Sender of [`SponsoredTxEvent`]s to subscribers.
+    events: broadcast::Sender<SponsoredTxEvent>,
+    /// Metrics for the `wallet_` RPC namespace.
+    metrics: WalletMetrics,
+}
+
+/// Tracks the gas of sponsored transactions that are submitted, but not settled yet.
+#[derive(Debug, Default)]
+struct InFlightGas {
+    /// The gas of each transaction in flight.
+    txs: HashMap<TxHash, u64>,
+    /// The total gas of all transactions in flight.
+    total: u64,
+}
+
+impl InFlightGas {
+    fn reserve(&mut self, hash: TxHash, gas: u64) {
+        if self.txs.insert(hash, gas).is_none() {
+            self.total = self.total.saturating_add(gas);
+        }
+    }
+
+    fn release(&mut self, hash: &TxHash) {
+        if let Some(gas) = self.txs.remove(hash) {
+            self.total = self.total.saturating_sub(gas);
+        }
+    }
+}
+
+/// Tracks the cost of the transactions sponsored for each sender within the
+/// [`SENDER_BUDGET_WINDOW`].
+#[derive(Debug, Default)]
+struct SenderSpend {
+    /// The time and cost of each sponsored transaction, per sender, oldest first.
+    senders: HashMap<Address, VecDeque<(Instant, U256)>>,
+}
+
+impl SenderSpend {
+    /// Returns the cost sponsored for the sender within the window ending at `now`.
+    fn spent(&mut self, sender: Address, now: Instant) -> U256 {
+        self.evict(now);
+        self.senders.get(&sender).map_or(U256::ZERO, |spends| {
+            spends.iter().fold(U256::ZERO, |total, (_, cost)| total.saturating_add(*cost))
+        })
+    }
+
+    fn record(&mut self, sender: Address, cost: U256, now: Instant) {
+        self.senders.entry(sender).or_default().push_back((now, cost));
+    }
+
+    /// Drops the spends that left the window, and the senders without any spends in it.
+    fn evict(&mut self, now: Instant) {
+        self.senders.retain(|_, spends| {
+            while spends
+                .front()
+                .is_some_and(|(at, _)| now.duration_since(*at) >= SENDER_BUDGET_WINDOW)
+            {
+                spends.pop_front();
+            }
+            !spends.is_empty()
+        });
+    }
+}
+
+fn validate_tx_request(
+    request: &TransactionRequest,
+    config: &TraverseWalletConfig,
+) -> Result<(), TraverseWalletError> {
+    // reject transactions that have a non-zero value to prevent draining the service.
+    if request.value.is_some_and(|val| val > U256::ZERO) {
+        return Err(TraverseWalletError::ValueNotZero);
+    }
+
+    // reject transactions that have from set, as this will be the service.
+    if request.from.is_some() {
+        return Err(TraverseWalletError::FromSet);
+    }
+
+    // reject transaction requests that have nonce set, as this is managed by the service.
+    if request.nonce.is_some() {
+        return Err(TraverseWalletError::NonceSet);
+    }
+
+    // reject transaction requests that have gas set, as this is estimated by the service.
+    if request.gas.is_some() {
+        return Err(TraverseWalletError::GasSet);
+    }
+
+    // reject oversized requests before they reach the upstream.
+    if request.input.input().is_some_and(|input| input.len() > config.max_input_size) {
+        return Err(TraverseWalletError::RequestTooLarge);
+    }
+
+    // an eip-7702 transaction must carry at least one authorization.
+    if config.reject_empty_authorization_list
+        && request.authorization_list.as_ref().is_some_and(Vec::is_empty)
+    {
+        return Err(TraverseWalletError::EmptyAuthorizationList);
+    }
+
+    // the service pays for processing each authorization, so their number is bounded.
+    if let Some(count) = request
+        .authorization_list
+        .as_ref()
+        .map(Vec::len)
+        .filter(|count| *count > config.max_authorizations)
+    {
+        return Err(TraverseWalletError::TooManyAuthorizations {
+            count,
+            max: config.max_authorizations,
+        });
+    }
+
+    Ok(())
+}
+
+/// Returns the intrinsic gas of the transaction built from the request, i.e. the gas charged
+/// before any execution.
+///
+/// This covers the base cost of a call, the calldata, the access list and the authorizations of
+/// the request.
+fn intrinsic_gas(request: &TransactionRequest) -> u64 {
+    const TX_BASE_GAS: u64 = 21_000;
+    const ZERO_BYTE_GAS: u64 = 4;
+    const NON_ZERO_BYTE_GAS: u64 = 16;
+    const ACCESS_LIST_ADDRESS_GAS: u64 = 2_400;
+    const ACCESS_LIST_STORAGE_KEY_GAS: u64 = 1_900;
+
+    let calldata_gas = request.input.input().map_or(0, |input| {
+        input.iter().map(|byte| if *byte == 0 { ZERO_BYTE_GAS } else { NON_ZERO_BYTE_GAS }).sum()
+    });
+    let access_list_gas = request.access_list.as_ref().map_or(0, |access_list| {
+        access_list
+            .iter()
+            .map(|item| {
+                ACCESS_LIST_ADDRESS_GAS
+                    + item.storage_keys.len() as u64 * ACCESS_LIST_STORAGE_KEY_GAS
+            })
+            .sum()
+    });
+    let authorization_gas = request
+        .authorization_list
+        .as_ref()
+        .map_or(0, |authorizations| authorizations.len() as u64 * PER_EMPTY_ACCOUNT_COST);
+
+    TX_BASE_GAS + calldata_gas + access_list_gas + authorization_gas
+}
+
+/// The `GasPriceOracle` predeploy of OP chains, which prices the L1 data fee of transactions.
+pub const GAS_PRICE_ORACLE: Address = address!("420000000000000000000000000000000000000F");
+
+/// Returns the call of `GasPriceOracle.getL1Fee` for the transaction built from the request.
+///
+/// The transaction is encoded unsigned, the oracle accounts for the size of the signature.
+fn l1_fee_call(tx: &TransactionRequest) -> Result<TransactionRequest, TraverseWalletError> {
+    let mut tx = tx.clone();
+    tx.nonce.get_or_insert_default();
+    let tx = tx.build_typed_tx().map_err(|_| {
+        TraverseWalletError::InternalError(eyre::eyre!("incomplete sponsored transaction"))
+    })?;
+    let mut encoded = Vec::new();
+    match &tx {
+        TypedTransaction::Legacy(tx) => tx.encode_for_signing(&mut encoded),
+        TypedTransaction::Eip2930(tx) => tx.encode_for_signing(&mut encoded),
+        TypedTransaction::Eip1559(tx) => tx.encode_for_signing(&mut encoded),
+        TypedTransaction::Eip4844(tx) => tx.encode_for_signing(&mut encoded),
+        TypedTransaction::Eip7702(tx) => tx.encode_for_signing(&mut encoded),
+    }
+
+    // abi encoding of `getL1Fee(bytes)`
+    let mut input = keccak256("getL1Fee(bytes)")[..4].to_vec();
+    input.extend_from_slice(&U256::from(32).to_be_bytes::<32>());
+    input.extend_from_slice(&U256::from(encoded.len()).to_be_bytes::<32>());
+    input.extend_from_slice(&encoded);
+    input.resize(4 + 64 + encoded.len().next_multiple_of(32), 0);
+
+    Ok(TransactionRequest::default().to(GAS_PRICE_ORACLE).input(Bytes::from(input).into()))
+}
+
+/// Decodes the L1 data fee returned by `GasPriceOracle.getL1Fee`.
+fn decode_l1_fee(output: &[u8]) -> Result<U256, TraverseWalletError> {
+    U256::try_from_be_slice(output).filter(|_| output.len() == 32).ok_or_else(|| {
+        let output = Bytes::copy_from_slice(output);
+        TraverseWalletError::InternalError(eyre::eyre!("invalid L1 fee: {output}"))
+    })
+}
+
+/// Returns the gas accounted for a sponsored transaction with the given gas limit, including the
+/// L1 data fee converted to gas at the given max fee per gas, rounded up.
+fn sponsored_gas(gas: u64, l1_data_fee: U256, max_fee_per_gas: u128) -> u64 {
+    if max_fee_per_gas == 0 {
+        return gas;
+    }
+    let l1_gas = l1_data_fee.div_ceil(U256::from(max_fee_per_gas));
+    gas.saturating_add(l1_gas.saturating_to())
+}
+
+/// Derives a fee estimate from the fee history.
+///
+/// The priority fee is the highest reward in the history, and the max fee leaves room for the base
+/// fee of the next block to double.
+fn fee_history_estimate(history: &FeeHistory) -> Option<Eip1559Estimation> {
+    let base_fee = history.next_block_base_fee()?;
+    let max_priority_fee_per_gas = history
+        .reward
+        .iter()
+        .flatten()
+        .filter_map(|rewards| rewards.first().copied())
+        .max()
+        .unwrap_or_default();
+
+    Some(Eip1559Estimation {
+        max_fee_per_gas: base_fee.saturating_mul(2).saturating_add(max_priority_fee_per_gas),
+        max_priority_fee_per_gas,
+    })
+}
+
+/// Returns the higher of each fee of the two estimates.
+fn max_fee_estimate(
+    estimate: Eip1559Estimation,
+    other: Option<Eip1559Estimation>,
+) -> Eip1559Estimation {
+    let Some(other) = other else { return estimate };
+    Eip1559Estimation {
+        max_fee_per_gas: estimate.max_fee_per_gas.max(other.max_fee_per_gas),
+        max_priority_fee_per_gas: estimate
+            .max_priority_fee_per_gas
+            .max(other.max_priority_fee_per_gas),
+    }
+}
+
+/// Clears the fields of the transaction request that are managed by the service.
+///
+/// These are the fees and the chain id, and the access list if it is stripped by the
+/// [`AccessListPolicy`]. The service sets them before the transaction is signed, using the fees
+/// of the client only if they are honored by the [`FeePolicy`]. The gas limit, and fields that
+/// would change the semantics of the transaction, like `value`, `from` or `nonce`, are rejected
+/// in [`validate_tx_request`] instead.
+fn sanitize_request(request: &mut TransactionRequest, access_list_policy: AccessListPolicy) {
+    let strip_access_list = access_list_policy == AccessListPolicy::Strip;
+    let overridden = [
+        ("gasPrice", request.gas_price.take().is_some()),
+        ("maxFeePerGas", request.max_fee_per_gas.take().is_some()),
+        ("maxPriorityFeePerGas", request.max_priority_fee_per_gas.take().is_some()),
+        ("maxFeePerBlobGas", request.max_fee_per_blob_gas.take().is_some()),
+        ("chainId", request.chain_id.take().is_some()),
+        ("accessList", strip_access_list && request.access_list.take().is_some()),
+    ]
+    .into_iter()
+    .filter_map(|(field, set)| set.then_some(field))
+    .collect::<Vec<_>>();
+
+    if !overridden.is_empty() {
+        debug!(target: "rpc::wallet", ?overridden, "Cleared service-managed request fields");
+    }
+}
+
+/// Validates the authorizations of an [EIP-7702][eip-7702] transaction request.
+///
+/// Each authorization must be signed by the destination of the transaction, if any, and must
+/// delegate to one of the supported contracts, if configured.
+///
+/// [eip-7702]: https://eips.ethereum.org/EIPS/eip-7702
+fn validate_authorizations(
+    request: &TransactionRequest,
+    delegations: Option<&DelegationCapability>,
+) -> Result<(), TraverseWalletError> {
+    let destination = match request.to {
+        Some(TxKind::Call(addr)) => Some(addr),
+        _ => None,
+    };
+
+    for auth in request.authorization_list.iter().flatten() {
+        validate_authorization(auth, destination)?;
+
+        if delegations.is_some_and(|delegations| !delegations.addresses.contains(&auth.address)) {
+            return Err(TraverseWalletError::UnsupportedDelegation(auth.address));
+        }
+    }
+
+    Ok(())
+}
+
+/// Recovers the authority of the authorization and ensures it is the expected account.
+fn validate_authorization(
+    auth: &SignedAuthorization,
+    expected: Option<Address>,
+) -> Result<(), TraverseWalletError> {
+    let authority =
+        auth.recover_authority().map_err(|_| TraverseWalletError::InvalidAuthorization)?;
+
+    if expected.is_some_and(|expected| expected != authority) {
+        return Err(TraverseWalletError::InvalidAuthorization);
+    }
+
+    Ok(())
+}
+
+/// The number of rejected calls to `wallet_sendTransaction`, labeled by the
+/// [`TraverseWalletError::reason`] of the rejection.
+const REJECTED_SEND_TRANSACTION_CALLS: &str = "wallet.rejected_send_transaction_calls";
+
+/// The duration of calls to `wallet_sendTransaction`, in seconds, from validation to sending,
+/// labeled by whether the `outcome` of the call is a `success` or a `failure`.
+const SEND_TRANSACTION_DURATION: &str = "wallet.send_transaction_duration";
+
+/// Metrics for the `wallet_` RPC namespace.
+///
+/// The rejections are also counted per reason, see [`REJECTED_SEND_TRANSACTION_CALLS`], and the
+/// duration of each call is recorded by outcome, see [`SEND_TRANSACTION_DURATION`].
+#[derive(Metrics)]
+#[metrics(scope = "wallet")]
+struct WalletMetrics {
+    /// Number of invalid calls to `traverse_sendTransaction`
+    invalid_send_transaction_calls: Counter,
+    /// Number of valid calls to `traverse_sendTransaction`
+    valid_send_transaction_calls: Counter,
+    /// Number of sponsored transaction events missed by lagging subscribers
+    dropped_sponsored_tx_events: Counter,
+    /// Gas of the sponsored transactions, including their L1 data fee if it is accounted for
+    sponsored_gas_used: Counter,
+    /// Number of gas estimates of `wallet_sendTransaction` that timed out
+    estimate_timeouts: Counter,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        add_to_pool, check_delegation_chain, delegation_designator, event_stream, intrinsic_gas,
+        last_code_change, sanitize_request, sign_with_nonce, sponsored_gas,
+        validate_authorizations, validate_tx_request, AccessListPolicy, AlloyUpstream,
+        DelegationCapability, FeeHistoryConfig, FeePolicy, FeeStrategy, HttpClientConfig,
+        MultiChainWallet, RelaySignRequest, RelayUpstream, RotatingSigner, SponsorSigner,
+        SponsoredTxType, TraverseWallet, TraverseWalletAdminApiServer, TraverseWalletApiClient,
+        TraverseWalletApiServer, TraverseWalletConfig, TraverseWalletError, Upstream, WalletStats,
+        DEFAULT_GAS_CEILING, MAX_KNOWN_DELEGATIONS, MAX_MIN_DELEGATION_AGE, SENDER_BUDGET_WINDOW,
+    };
+    use alloy_consensus::TxEnvelope;
+    use alloy_eips::eip7702::{Authorization, SignedAuthorization};
+    use alloy_network::{eip2718::Encodable2718, EthereumWallet, TransactionBuilder7702};
+    use alloy_primitives::{Address, BlockNumber, Bytes, ChainId, TxHash, TxKind, B256, U256};
+    use alloy_provider::utils::Eip1559Estimation;
+    use alloy_rpc_types::{
+        trace::geth::{CallFrame, GethTrace, TraceResult},
+        AccessList, AccessListItem, AccessListResult, FeeHistory, TransactionRequest,
+    };
+    use alloy_signer::SignerSync;
+    use alloy_signer_local::PrivateKeySigner;
+    use futures::StreamExt;
+    use http_body_util::{BodyExt, Full};
+    use hyper::{
+        body::Incoming, server::conn::http1, service::service_fn, Request, Response, StatusCode,
+    };
+    use hyper_util::rt::TokioIo;
+    use jsonrpsee::{
+        core::async_trait,
+        server::Server,
+        types::error::{ErrorObject, INTERNAL_ERROR_CODE, INVALID_PARAMS_CODE},
+        ws_client::WsClientBuilder,
+    };
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use reth_chainspec::MAINNET;
+    use reth_evm_ethereum::EthEvmConfig;
+    use reth_network_api::noop::NoopNetwork;
+    use reth_provider::test_utils::{ExtendedAccount, MockEthProvider};
+    use reth_rpc::EthApi;
+    use reth_rpc_eth_types::{EthStateCache, FeeHistoryCache, GasPriceOracle};
+    use reth_rpc_server_types::constants::{
+        DEFAULT_ETH_PROOF_WINDOW, DEFAULT_MAX_SIMULATE_BLOCKS, DEFAULT_PROOF_PERMITS,
+    };
+    use reth_tasks::pool::BlockingTaskPool;
+    use reth_transaction_pool::{
+        test_utils::{testing_pool, MockTransaction},
+        TransactionOrigin, TransactionPool,
+    };
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use tokio::{net::TcpListener, sync::broadcast::error::RecvError, time::Instant};
+
+    /// The delegation contract of accounts in [`MockUpstream`].
+    const DELEGATION: Address = Address::with_last_byte(0xde);
+
+    /// An upstream where every account is delegated to [`DELEGATION`].
+    #[derive(Debug)]
+    struct MockUpstream {
+        /// The code of every account.
+        code: Bytes,
+        /// The gas estimate of every request.
+        gas: u64,
+        /// The fee estimate of every request.
+        fees: Eip1559Estimation,
+        /// The requests that were sent.
+        sent: parking_lot::Mutex<Vec<TransactionRequest>>,
+        /// How long signing and sending a request takes.
+        send_delay: Duration,
+        /// How long estimating a request takes.
+        estimate_delay: Duration,
+        /// The fee history of recent blocks.
+        fee_history: FeeHistory,
+        /// The access list of every request.
+        access_list: AccessList,
+        /// The block at which the code of every account changed, if recently.
+        code_changed_at: Option<BlockNumber>,
+        /// The chain id of the upstream.
+        chain_id: ChainId,
+        /// The pending nonce of the sponsor.
+        nonce: u64,
+        /// The address of the sponsor, which changes when the signer is rotated.
+        sponsor: parking_lot::Mutex<Address>,
+        /// The L1 data fee of every request.
+        l1_data_fee: U256,
+    }
+
+    impl Default for MockUpstream {
+        fn default() -> Self {
+            Self {
+                code: designator(DELEGATION),
+                gas: 100_000,
+                fees: Eip1559Estimation { max_fee_per_gas: 2, max_priority_fee_per_gas: 1 },
+                sent: Default::default(),
+                send_delay: Duration::ZERO,
+                estimate_delay: Duration::ZERO,
+                fee_history: FeeHistory::default(),
+                access_list: AccessList::default(),
+                code_changed_at: None,
+                chain_id: 1,
+                nonce: 0,
+                sponsor: parking_lot::Mutex::new(Address::with_last_byte(0x5f)),
+                l1_data_fee: U256::ZERO,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl Upstream for MockUpstream {
+        fn default_signer_address(&self) -> Address {
+            *self.sponsor.lock()
+        }
+
+        async fn chain_id(&self) -> Result<ChainId, TraverseWalletError> {
+            Ok(self.chain_id)
+        }
+
+        async fn pending_nonce(&self) -> Result<u64, TraverseWalletError> {
+            Ok(self.nonce + self.sent.lock().len() as u64)
+        }
+
+        async fn get_code(&self, _address: Address) -> Result<Bytes, TraverseWalletError> {
+            Ok(self.code.clone())
+        }
+
+        async fn code_changed_within(
+            &self,
+            _address: Address,
+            _blocks: u64,
+        ) -> Result<Option<BlockNumber>, TraverseWalletError> {
+            Ok(self.code_changed_at)
+        }
+
+        async fn estimate(
+            &self,
+            tx: &TransactionRequest,
+        ) -> Result<(u64, Eip1559Estimation), TraverseWalletError> {
+            tokio::time::sleep(self.estimate_delay).await;
+            // an access list saves some gas
+            let savings = if tx.access_list.is_some() { 1_000 } else { 0 };
+            Ok((self.gas - savings, self.fees))
+        }
+
+        async fn create_access_list(
+            &self,
+            _tx: &TransactionRequest,
+        ) -> Result<AccessListResult, TraverseWalletError> {
+            Ok(AccessListResult {
+                access_list: self.access_list.clone(),
+                gas_used: U256::from(self.gas),
+                error: None,
+            })
+        }
+
+        async fn fee_history(
+            &self,
+            _block_count: u64,
+            _percentile: f64,
+        ) -> Result<FeeHistory, TraverseWalletError> {
+            Ok(self.fee_history.clone())
+        }
+
+        async fn sign_and_send(
+            &self,
+            tx: TransactionRequest,
+        ) -> Result<TxHash, TraverseWalletError> {
+            tokio::time::sleep(self.send_delay).await;
+            let mut sent = self.sent.lock();
+            sent.push(tx);
+            Ok(TxHash::with_last_byte(sent.len() as u8))
+        }
+
+        async fn sign_and_send_raw(
+            &self,
+            tx: TransactionRequest,
+        ) -> Result<(TxHash, Option<Bytes>), TraverseWalletError> {
+            let tx_hash = self.sign_and_send(tx).await?;
+            Ok((tx_hash, Some(Bytes::from_static(&[0x02]))))
+        }
+
+        async fn l1_data_fee(&self, _tx: &TransactionRequest) -> Result<U256, TraverseWalletError> {
+            Ok(self.l1_data_fee)
+        }
+
+        async fn trace_call(
+            &self,
+            tx: &TransactionRequest,
+        ) -> Result<TraceResult, TraverseWalletError> {
+            // a canned trace of a reverted call
+            let frame = CallFrame {
+                from: tx.from.unwrap_or_default(),
+                to: tx.to.and_then(|to| to.to().copied()),
+                gas_used: U256::from(self.gas),
+                error: Some("execution reverted".to_string()),
+                ..Default::default()
+            };
+            Ok(TraceResult::Success { result: frame.into(), tx_hash: None })
+        }
+
+        fn rotate_signer(&self, signer: EthereumWallet) -> Result<(), TraverseWalletError> {
+            *self.sponsor.lock() = SponsorSigner::address(&signer);
+            Ok(())
+        }
+    }
+
+    /// Returns the EIP-7702 delegation designator of the given address.
+    fn designator(address: Address) -> Bytes {
+        [&[0xef, 0x01, 0x00][..], address.as_slice()].concat().into()
+    }
+
+    /// Returns the reason of a wallet RPC error.
+    fn reason(err: &ErrorObject<'_>) -> String {
+        serde_json::from_str::<serde_json::Value>(err.data().unwrap().get()).unwrap()["reason"]
+            .as_str()
+            .unwrap()
+            .to_string()
+    }
+
+    /// The delegated account called by [`delegated_call`].
+    const DESTINATION: Address = Address::with_last_byte(0xaa);
+
+    /// A sponsored call to a delegated account.
+    fn delegated_call() -> TransactionRequest {
+        TransactionRequest::default().to(DESTINATION)
+    }
+
+    fn sign_authorization(signer: &PrivateKeySigner, address: Address) -> SignedAuthorization {
+        let auth = Authorization { chain_id: 1, address, nonce: 0 };
+        let signature = signer.sign_hash_sync(&auth.signature_hash()).unwrap();
+        auth.into_signed(signature)
+    }
+
+    #[test]
+    fn no_value_allowed() {
+        let config = TraverseWalletConfig::default();
+        assert!(matches!(
+            validate_tx_request(&TransactionRequest::default().value(U256::from(1)), &config),
+            Err(TraverseWalletError::ValueNotZero)
+        ));
+
+        assert!(matches!(
+            validate_tx_request(&TransactionRequest::default().value(U256::from(0)), &config),
+            Ok(())
+        ));
+    }
+
+    #[test]
+    fn no_from_allowed() {
+        let config = TraverseWalletConfig::default();
+        assert!(matches!(
+            validate_tx_request(&TransactionRequest::default().from(Address::ZERO), &config),
+            Err(TraverseWalletError::FromSet)
+        ));
+
+        assert!(matches!(validate_tx_request(&TransactionRequest::default(), &config), Ok(())));
+    }
+
+    #[test]
+    fn no_nonce_allowed() {
+        let config = TraverseWalletConfig::default();
+        assert!(matches!(
+            validate_tx_request(&TransactionRequest::default().nonce(1), &config),
+            Err(TraverseWalletError::NonceSet)
+        ));
+
+        assert!(matches!(validate_tx_request(&TransactionRequest::default(), &config), Ok(())));
+    }
+
+    #[tokio::test]
+    async fn no_gas_allowed() {
+        let config = TraverseWalletConfig::default();
+        assert!(matches!(
+            validate_tx_request(&TransactionRequest::default().gas_limit(100_000), &config),
+            Err(TraverseWalletError::GasSet)
+        ));
+
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+        let err =
+            wallet.send_transaction(delegated_call().gas_limit(100_000), None).await.unwrap_err();
+        assert_eq!(reason(&err), "gas_set");
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+    }
+
+    #[test]
+    fn oversized_input() {
+        let config = TraverseWalletConfig::default().with_max_input_size(4);
+
+        let request = TransactionRequest::default().input(Bytes::from_static(&[0; 4]).into());
+        assert!(matches!(validate_tx_request(&request, &config), Ok(())));
+
+        let request = TransactionRequest::default().input(Bytes::from_static(&[0; 5]).into());
+        assert!(matches!(
+            validate_tx_request(&request, &config),
+            Err(TraverseWalletError::RequestTooLarge)
+        ));
+    }
+
+    #[test]
+    fn oversized_authorization_list() {
+        let config = TraverseWalletConfig::default().with_max_authorizations(1);
+        let signer = PrivateKeySigner::random();
+
+        let request = TransactionRequest::default()
+            .authorization_list(vec![sign_authorization(&signer, DELEGATION)]);
+        assert!(matches!(validate_tx_request(&request, &config), Ok(())));
+
+        let request = TransactionRequest::default().authorization_list(vec![
+            sign_authorization(&signer, DELEGATION),
+            sign_authorization(&signer, DELEGATION),
+        ]);
+        assert!(matches!(
+            validate_tx_request(&request, &config),
+            Err(TraverseWalletError::TooManyAuthorizations { count: 2, max: 1 })
+        ));
+    }
+
+    #[test]
+    fn empty_authorization_list() {
+        let config = TraverseWalletConfig::default();
+        let signer = PrivateKeySigner::random();
+
+        let request = TransactionRequest::default().authorization_list(vec![]);
+        assert!(matches!(
+            validate_tx_request(&request, &config),
+            Err(TraverseWalletError::EmptyAuthorizationList)
+        ));
+
+        let request = TransactionRequest::default()
+            .authorization_list(vec![sign_authorization(&signer, DELEGATION)]);
+        assert!(matches!(validate_tx_request(&request, &config), Ok(())));
+
+        // the check can be disabled
+        let config = config.with_reject_empty_authorization_list(false);
+        let request = TransactionRequest::default().authorization_list(vec![]);
+        assert!(matches!(validate_tx_request(&request, &config), Ok(())));
+    }
+
+    #[tokio::test]
+    async fn empty_authorization_list_rejected() {
+        let signer = PrivateKeySigner::random();
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+
+        let request = TransactionRequest::default().to(signer.address()).authorization_list(vec![]);
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "empty_authorization_list");
+        assert_eq!(err.code(), INVALID_PARAMS_CODE);
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+
+        // a single authorization is sponsored
+        let request = TransactionRequest::default()
+            .to(signer.address())
+            .authorization_list(vec![sign_authorization(&signer, DELEGATION)]);
+        wallet.send_transaction(request, None).await.unwrap();
+        assert_eq!(wallet.inner.upstream.sent.lock().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn authorization_destination_code() {
+        let signer = PrivateKeySigner::random();
+        let request = || {
+            TransactionRequest::default()
+                .to(signer.address())
+                .authorization_list(vec![sign_authorization(&signer, DELEGATION)])
+        };
+        let send = |code: Bytes, validate: bool| {
+            let wallet = TraverseWallet::with_config(
+                MockUpstream { code, ..Default::default() },
+                1,
+                TraverseWalletConfig::default().with_validate_authorization_destination(validate),
+            );
+            async move { wallet.send_transaction(request(), None).await }
+        };
+        let contract = Bytes::from_static(&[0x60, 0x00]);
+
+        // the code of the destination is not checked by default
+        send(Bytes::new(), false).await.unwrap();
+        send(contract.clone(), false).await.unwrap();
+
+        // if enabled, EOAs and delegated accounts are sponsored, but not contracts
+        send(Bytes::new(), true).await.unwrap();
+        send(designator(DELEGATION), true).await.unwrap();
+        let err = send(contract, true).await.unwrap_err();
+        assert_eq!(reason(&err), "destination_not_a_delegation");
+    }
+
+    #[tokio::test]
+    async fn too_many_authorizations() {
+        let signer = PrivateKeySigner::random();
+        let request = TransactionRequest::default()
+            .to(signer.address())
+            .authorization_list(vec![sign_authorization(&signer, DELEGATION); 17]);
+
+        // the default limit is 16 authorizations
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "too_many_authorizations");
+        assert_eq!(err.code(), INVALID_PARAMS_CODE);
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+    }
+
+    #[test]
+    fn sanitize_service_managed_fields() {
+        let mut request = TransactionRequest::default()
+            .to(DESTINATION)
+            .input(Bytes::from_static(&[0x01, 0x02]).into())
+            .max_fee_per_gas(100)
+            .max_priority_fee_per_gas(10);
+        request.gas_price = Some(100);
+        request.chain_id = Some(1337);
+        let expected = TransactionRequest::default()
+            .to(DESTINATION)
+            .input(Bytes::from_static(&[0x01, 0x02]).into());
+
+        sanitize_request(&mut request, AccessListPolicy::Honor);
+        assert_eq!(request, expected);
+    }
+
+    #[tokio::test]
+    async fn access_list_policy() {
+        let access_list = AccessList(vec![AccessListItem {
+            address: DELEGATION,
+            storage_keys: vec![Default::default()],
+        }]);
+        let request = delegated_call().access_list(access_list.clone());
+
+        // the access list of the client is honored by default
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+        wallet.send_transaction(request.clone(), None).await.unwrap();
+        assert_eq!(wallet.inner.upstream.sent.lock()[0].access_list, Some(access_list));
+
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_access_list_policy(AccessListPolicy::Strip),
+        );
+        wallet.send_transaction(request, None).await.unwrap();
+        assert_eq!(wallet.inner.upstream.sent.lock()[0].access_list, None);
+    }
+
+    #[test]
+    fn valid_authorization() {
+        let signer = PrivateKeySigner::random();
+        let delegation = Address::with_last_byte(1);
+        let request = TransactionRequest::default()
+            .with_authorization_list(vec![sign_authorization(&signer, delegation)])
+            .to(signer.address());
+
+        assert!(matches!(validate_authorizations(&request, None), Ok(())));
+        assert!(matches!(
+            validate_authorizations(
+                &request,
+                Some(&DelegationCapability { addresses: vec![delegation] })
+            ),
+            Ok(())
+        ));
+        assert!(matches!(
+            validate_authorizations(
+                &request,
+                Some(&DelegationCapability { addresses: vec![Address::ZERO] })
+            ),
+            Err(TraverseWalletError::UnsupportedDelegation(addr)) if addr == delegation
+        ));
+    }
+
+    #[test]
+    fn tampered_authorization() {
+        let signer = PrivateKeySigner::random();
+        let auth = sign_authorization(&signer, Address::with_last_byte(1));
+
+        // swap the delegation address after signing
+        let mut tampered = auth.clone().strip_signature();
+        tampered.address = Address::with_last_byte(2);
+        let tampered = tampered.into_signed(auth.signature().unwrap());
+
+        let request = TransactionRequest::default()
+            .with_authorization_list(vec![tampered])
+            .to(signer.address());
+        assert!(matches!(
+            validate_authorizations(&request, None),
+            Err(TraverseWalletError::InvalidAuthorization)
+        ));
+
+        // an authorization with a malformed signature
+        let malformed = SignedAuthorization::new_unchecked(
+            Authorization { chain_id: 1, address: Address::with_last_byte(1), nonce: 0 },
+            0,
+            U256::ZERO,
+            U256::ZERO,
+        );
+        let request = TransactionRequest::default()
+            .with_authorization_list(vec![malformed])
+            .to(signer.address());
+        assert!(matches!(
+            validate_authorizations(&request, None),
+            Err(TraverseWalletError::InvalidAuthorization)
+        ));
+    }
+
+    #[test]
+    fn error_object_reason() {
+        let err = ErrorObject::from(TraverseWalletError::ValueNotZero);
+        assert_eq!(err.code(), INVALID_PARAMS_CODE);
+        assert_eq!(err.data().unwrap().get(), r#"{"reason":"value_not_zero"}"#);
+
+        let err = ErrorObject::from(TraverseWalletError::IllegalDestination);
+        assert_eq!(err.code(), INVALID_PARAMS_CODE);
+        assert_eq!(err.data().unwrap().get(), r#"{"reason":"illegal_destination"}"#);
+
+        let err = ErrorObject::from(TraverseWalletError::InternalError(eyre::eyre!("oops")));
+        assert_eq!(err.code(), INTERNAL_ERROR_CODE);
+        assert_eq!(err.data().unwrap().get(), r#"{"reason":"internal_error"}"#);
+    }
+
+    /// Serves JSON-RPC requests over HTTP/1.1, responding with an empty result, and counts the
+    /// accepted connections.
+    async fn spawn_counting_rpc_server() -> (url::Url, Arc<AtomicUsize>) {
+        spawn_rpc_server(|_| Some(serde_json::json!("0x"))).await
+    }
+
+    /// Serves JSON-RPC requests over HTTP/1.1, responding with the result returned by `respond`
+    /// for each request, and counts the accepted connections.
+    ///
+    /// If `respond` returns `None`, the request fails with `503 Service Unavailable`.
+    async fn spawn_rpc_server(
+        respond: impl Fn(&serde_json::Value) -> Option<serde_json::Value> + Send + Sync + 'static,
+    ) -> (url::Url, Arc<AtomicUsize>) {
+        spawn_http_server(move |request| {
+            respond(request).map(|result| {
+                serde_json::json!({
+                    "jsonrpc": "2.0",
+                    "id": request["id"],
+                    "result": result,
+                })
+            })
+        })
+        .await
+    }
+
+    /// Serves JSON requests over HTTP/1.1 with [`hyper`], responding with the body returned by
+    /// `respond` for each request, and counts the accepted connections.
+    ///
+    /// If `respond` returns `None`, the request fails with `503 Service Unavailable`.
+    async fn spawn_http_server(
+        respond: impl Fn(&serde_json::Value) -> Option<serde_json::Value> + Send + Sync + 'static,
+    ) -> (url::Url, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap()).parse().unwrap();
+        let connections = Arc::new(AtomicUsize::new(0));
+        let respond = Arc::new(respond);
+
+        let accepted = connections.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                accepted.fetch_add(1, Ordering::SeqCst);
+                let respond = respond.clone();
+                let service = service_fn(move |request: Request<Incoming>| {
+                    let respond = respond.clone();
+                    async move {
+                        let body = request.into_body().collect().await?.to_bytes();
+                        let request: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                        let response = match respond(&request) {
+                            Some(response) => Response::new(Full::new(hyper::body::Bytes::from(
+                                response.to_string(),
+                            ))),
+                            None => {
+                                let mut response = Response::new(Full::default());
+                                *response.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+                                response
+                            }
+                        };
+                        Ok::<_, hyper::Error>(response)
+                    }
+                });
+                tokio::spawn(http1::Builder::new().serve_connection(TokioIo::new(stream), service));
+            }
+        });
+
+        (url, connections)
+    }
+
+    #[tokio::test]
+    async fn relay_upstream() {
+        let tx_hash = TxHash::with_last_byte(1);
+        let requests = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let received = requests.clone();
+        let (url, _) = spawn_http_server(move |request| {
+            received.lock().push(request.clone());
+            Some(serde_json::json!({ "txHash": tx_hash }))
+        })
+        .await;
+
+        let sponsor = Address::with_last_byte(0x53);
+        let wallet =
+            TraverseWallet::new(RelayUpstream::new(MockUpstream::default(), sponsor, url), 1);
+        assert_eq!(wallet.send_transaction(delegated_call(), None).await.unwrap(), tx_hash);
+
+        // the relay signs the estimated request of the sponsor
+        let request: RelaySignRequest = serde_json::from_value(requests.lock()[0].clone()).unwrap();
+        assert_eq!(request.sponsor, sponsor);
+        assert_eq!(request.transaction.from, Some(sponsor));
+        assert_eq!(request.transaction.chain_id, Some(1));
+        assert_eq!(request.transaction.gas, Some(100_000));
+        assert!(request.transaction.max_fee_per_gas.is_some());
+        assert!(request.transaction.nonce.is_none());
+        assert_eq!(requests.lock()[0]["transaction"]["to"], serde_json::json!(DESTINATION));
+
+        // relay errors are internal errors
+        let (url, _) = spawn_http_server(|_| None).await;
+        let wallet =
+            TraverseWallet::new(RelayUpstream::new(MockUpstream::default(), sponsor, url), 1);
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(err.code(), INTERNAL_ERROR_CODE);
+    }
+
+    #[tokio::test]
+    async fn alloy_upstream_reuses_connections() {
+        let (url, connections) = spawn_counting_rpc_server().await;
+        let upstream = AlloyUpstream::with_client_config(
+            url,
+            EthereumWallet::from(PrivateKeySigner::random()),
+            HttpClientConfig::default(),
+        )
+        .unwrap();
+
+        for _ in 0..3 {
+            assert!(upstream.get_code(Address::ZERO).await.unwrap().is_empty());
+        }
+
+        assert_eq!(connections.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn alloy_upstream_retries_read_only_calls() {
+        // estimates fail twice with a transport error before succeeding
+        let failures = Arc::new(AtomicUsize::new(0));
+        let estimate_failures = failures.clone();
+        let (url, _) = spawn_rpc_server(move |request| match request["method"].as_str() {
+            Some("eth_estimateGas") => (estimate_failures.fetch_add(1, Ordering::SeqCst) >= 2)
+                .then(|| serde_json::json!("0x5208")),
+            Some("eth_getBlockByNumber") => Some(serde_json::Value::Null),
+            Some("eth_feeHistory") => Some(serde_json::json!({
+                "oldestBlock": "0x1",
+                "baseFeePerGas": ["0x1", "0x1"],
+                "gasUsedRatio": [0.5],
+                "reward": [["0x1"]],
+            })),
+            _ => Some(serde_json::json!("0x")),
+        })
+        .await;
+        let upstream = AlloyUpstream::with_client_config(
+            url,
+            EthereumWallet::from(PrivateKeySigner::random()),
+            HttpClientConfig::default(),
+        )
+        .unwrap();
+        let upstream = AlloyUpstream::with_retries(upstream.provider, 2, Duration::from_millis(1));
+
+        let request = delegated_call().from(Address::ZERO);
+        let (estimate, _) = upstream.estimate(&request).await.unwrap();
+        assert_eq!(estimate, 21_000);
+        assert_eq!(failures.load(Ordering::SeqCst), 3);
+
+        // the retries are bounded
+        failures.store(0, Ordering::SeqCst);
+        let upstream = AlloyUpstream::with_retries(upstream.provider, 1, Duration::from_millis(1));
+        assert!(upstream.estimate(&request).await.is_err());
+
+        // every lookup of the delegation age check fails once before succeeding
+        let calls = Arc::new(AtomicUsize::new(0));
+        let lookups = calls.clone();
+        let (url, _) = spawn_rpc_server(move |request| {
+            let succeed = lookups.fetch_add(1, Ordering::SeqCst) % 2 == 1;
+            match request["method"].as_str() {
+                Some("eth_blockNumber") => succeed.then(|| serde_json::json!("0x64")),
+                _ => succeed.then(|| serde_json::json!("0x")),
+            }
+        })
+        .await;
+        let upstream = AlloyUpstream::with_client_config(
+            url,
+            EthereumWallet::from(PrivateKeySigner::random()),
+            HttpClientConfig::default(),
+        )
+        .unwrap();
+        let upstream = AlloyUpstream::with_retries(upstream.provider, 1, Duration::from_millis(1));
+        assert_eq!(upstream.code_changed_within(Address::ZERO, 10).await.unwrap(), None);
+        // the block number and the code at blocks 100 and 90
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn pending_nonce() {
+        // the mock assigns consecutive nonces to sent transactions
+        let wallet = TraverseWallet::new(MockUpstream { nonce: 7, ..Default::default() }, 1);
+        assert_eq!(wallet.inner.upstream.pending_nonce().await.unwrap(), 7);
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+        assert_eq!(wallet.inner.upstream.pending_nonce().await.unwrap(), 8);
+
+        // the alloy upstream queries the pending transaction count of the sponsor
+        let requests = Arc::new(parking_lot::Mutex::new(Vec::new()));
+        let received = requests.clone();
+        let (url, _) = spawn_rpc_server(move |request| {
+            received.lock().push(request.clone());
+            Some(serde_json::json!("0x2a"))
+        })
+        .await;
+        let signer = PrivateKeySigner::random();
+        let sponsor = signer.address();
+        let upstream = AlloyUpstream::with_client_config(
+            url.clone(),
+            EthereumWallet::from(signer),
+            HttpClientConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(upstream.pending_nonce().await.unwrap(), 42);
+        let request = requests.lock()[0].clone();
+        assert_eq!(request["method"], "eth_getTransactionCount");
+        assert_eq!(request["params"], serde_json::json!([sponsor, "pending"]));
+
+        // the nonces of a relayed sponsor are not known
+        let upstream =
+            RelayUpstream::new(MockUpstream::default(), Address::with_last_byte(0x53), url);
+        assert!(upstream.pending_nonce().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn reth_upstream_pending_nonce() {
+        let signer = PrivateKeySigner::random();
+        let sponsor = signer.address();
+        let provider = MockEthProvider::default();
+        provider.add_account(sponsor, ExtendedAccount::new(5, U256::MAX));
+        let pool = testing_pool();
+        let cache = EthStateCache::spawn(provider.clone(), Default::default());
+        let eth_api = EthApi::new(
+            provider.clone(),
+            pool.clone(),
+            NoopNetwork::default(),
+            cache.clone(),
+            GasPriceOracle::new(provider.clone(), Default::default(), cache),
+            30_000_000,
+            DEFAULT_MAX_SIMULATE_BLOCKS,
+            DEFAULT_ETH_PROOF_WINDOW,
+            BlockingTaskPool::build().unwrap(),
+            FeeHistoryCache::new(Default::default()),
+            EthEvmConfig::new(MAINNET.clone()),
+            DEFAULT_PROOF_PERMITS,
+        );
+        let upstream = RethUpstream::new(provider, eth_api, EthereumWallet::from(signer));
+
+        // the nonce of the sponsor in the latest state
+        assert_eq!(upstream.pending_nonce().await.unwrap(), 5);
+
+        // the transactions of the sponsor in the pool are pending
+        for nonce in [5, 6] {
+            let tx = MockTransaction::eip1559().with_sender(sponsor).with_nonce(nonce);
+            pool.add_transaction(TransactionOrigin::Local, tx).await.unwrap();
+        }
+        assert_eq!(upstream.pending_nonce().await.unwrap(), 7);
+    }
+
+    #[tokio::test]
+    async fn in_flight_gas_budget() {
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_max_in_flight_gas(250_000),
+        );
+
+        // fill the budget
+        let first = wallet.send_transaction(delegated_call(), None).await.unwrap();
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "sponsor_budget_exceeded");
+
+        // free the budget
+        wallet.settle_transactions([first]);
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn per_destination_gas_ceiling() {
+        let upstream = MockUpstream { gas: 400_000, ..Default::default() };
+
+        // rejected by the default ceiling
+        let wallet = TraverseWallet::new(upstream, 1);
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "gas_estimate_too_high");
+
+        // a higher ceiling for the delegation target
+        let wallet = TraverseWallet::with_config(
+            MockUpstream { gas: 400_000, ..Default::default() },
+            1,
+            TraverseWalletConfig::default().with_gas_ceiling(DELEGATION, 500_000),
+        );
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+
+        // a lower ceiling for the destination takes precedence
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default()
+                .with_gas_ceiling(DELEGATION, 500_000)
+                .with_gas_ceiling(DESTINATION, 50_000),
+        );
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "gas_estimate_too_high");
+    }
+
+    #[tokio::test]
+    async fn connect() {
+        let wallet =
+            TraverseWallet::connect(MockUpstream::default(), 1, TraverseWalletConfig::default())
+                .await
+                .unwrap();
+        assert!(wallet.send_transaction(delegated_call(), None).await.is_ok());
+
+        let upstream = MockUpstream { chain_id: 2, ..Default::default() };
+        let err = TraverseWallet::connect(upstream, 1, TraverseWalletConfig::default())
+            .await
+            .unwrap_err();
+        assert!(matches!(err, TraverseWalletError::ChainIdMismatch { configured: 1, upstream: 2 }));
+    }
+
+    #[tokio::test]
+    async fn limits_report_remaining_budget() {
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_max_in_flight_gas(250_000),
+        );
+
+        let limits = wallet.get_limits(None).await.unwrap();
+        assert_eq!(limits.gas_ceiling, DEFAULT_GAS_CEILING);
+        assert_eq!(limits.in_flight_gas, 0);
+        assert_eq!(limits.in_flight_gas_remaining, Some(250_000));
+
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+
+        let limits = wallet.get_limits(None).await.unwrap();
+        assert_eq!(limits.in_flight_gas, 100_000);
+        assert_eq!(limits.in_flight_gas_remaining, Some(150_000));
+    }
+
+    #[tokio::test]
+    async fn stats() {
+        let wallet = TraverseWallet::with_config(
+            MockUpstream { send_delay: Duration::from_secs(10), ..Default::default() },
+            1,
+            TraverseWalletConfig::default().with_upstream_timeout(Duration::from_millis(10)),
+        );
+        assert_eq!(wallet.get_stats(None).await.unwrap(), WalletStats::default());
+
+        let err =
+            wallet.send_transaction(delegated_call().value(U256::from(1)), None).await.unwrap_err();
+        assert_eq!(reason(&err), "value_not_zero");
+        let stats = wallet.get_stats(None).await.unwrap();
+        assert_eq!(stats.invalid_calls, 1);
+        assert_eq!(stats.last_rejection_reason.as_deref(), Some("value_not_zero"));
+        let last_internal_error = TraverseWalletAdminApiServer::get_last_internal_error(&wallet);
+        assert_eq!(last_internal_error.await.unwrap(), None);
+
+        // the upstream does not respond in time
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "upstream_timeout");
+        let stats = wallet.get_stats(None).await.unwrap();
+        assert_eq!(stats.valid_calls, 0);
+        assert_eq!(stats.invalid_calls, 2);
+        assert_eq!(stats.last_rejection_reason.as_deref(), Some("value_not_zero"));
+        // the message of the internal error is only returned to operators
+        assert!(!serde_json::to_string(&stats).unwrap().contains(err.message()));
+        let last_internal_error = TraverseWalletAdminApiServer::get_last_internal_error(&wallet);
+        assert_eq!(last_internal_error.await.unwrap(), Some(err.message().to_string()));
+
+        assert_eq!(reason(&wallet.get_stats(Some(2)).await.unwrap_err()), "unsupported_chain_id");
+    }
+
+    #[tokio::test]
+    async fn subscribe_sponsored_transactions() {
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+
+        let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let _handle = server.start(TraverseWalletApiServer::into_rpc(wallet.clone()));
+
+        let client = WsClientBuilder::default().build(format!("ws://{addr}")).await.unwrap();
+        let mut subscription = client.subscribe_sponsored_transactions().await.unwrap();
+
+        let tx_hash = wallet.send_transaction(delegated_call(), None).await.unwrap();
+
+        let event = subscription.next().await.unwrap().unwrap();
+        assert_eq!(event.chain_id, 1);
+        assert_eq!(event.tx_hash, tx_hash);
+        assert_eq!(event.to, Some(DESTINATION));
+        assert_eq!(event.delegation, Some(DELEGATION));
+        assert_eq!(event.gas, 100_000);
+        assert_eq!(event.raw_transaction, None);
+    }
+
+    #[tokio::test]
+    async fn dropped_sponsored_tx_events() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let wallet = metrics::with_local_recorder(&recorder, || {
+            TraverseWallet::with_config(
+                MockUpstream::default(),
+                1,
+                TraverseWalletConfig::default().with_sponsored_tx_event_capacity(2),
+            )
+        });
+        let dropped = || {
+            snapshotter.snapshot().into_vec().into_iter().find_map(|(key, _, _, value)| {
+                match (key.key().name(), value) {
+                    ("wallet.dropped_sponsored_tx_events", DebugValue::Counter(count)) => {
+                        Some(count)
+                    }
+                    _ => None,
+                }
+            })
+        };
+
+        // the subscriber falls behind by 3 events
+        let mut events = event_stream(
+            wallet.sponsored_transactions(),
+            wallet.inner.metrics.dropped_sponsored_tx_events.clone(),
+        );
+        let mut sent = Vec::new();
+        for _ in 0..5 {
+            sent.push(wallet.send_transaction(delegated_call(), None).await.unwrap());
+        }
+
+        assert!(matches!(events.next().await, Some(Err(RecvError::Lagged(3)))));
+        assert_eq!(dropped(), Some(3));
+
+        // the latest events are still buffered
+        assert_eq!(events.next().await.unwrap().unwrap().tx_hash, sent[3]);
+        assert_eq!(events.next().await.unwrap().unwrap().tx_hash, sent[4]);
+        assert_eq!(dropped(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn raw_tx_events() {
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+        let mut events = wallet.sponsored_transactions();
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+        assert_eq!(events.recv().await.unwrap().raw_transaction, None);
+
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_raw_tx_events(true),
+        );
+        let mut events = wallet.sponsored_transactions();
+        let tx_hash = wallet.send_transaction(delegated_call(), None).await.unwrap();
+        let event = events.recv().await.unwrap();
+        assert_eq!(event.tx_hash, tx_hash);
+        assert_eq!(event.raw_transaction, Some(Bytes::from_static(&[0x02])));
+    }
+
+    #[tokio::test]
+    async fn delegation_designator_length() {
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+        assert!(wallet.send_transaction(delegated_call(), None).await.is_ok());
+
+        let too_short =
+            MockUpstream { code: Bytes::from_static(&[0xef, 0x01, 0x00]), ..Default::default() };
+        let wallet = TraverseWallet::new(too_short, 1);
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "destination_not_a_delegation");
+
+        let mut code = designator(DELEGATION).to_vec();
+        code.push(0x00);
+        let too_long = MockUpstream { code: code.into(), ..Default::default() };
+        let wallet = TraverseWallet::new(too_long, 1);
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "destination_not_a_delegation");
+    }
+
+    #[tokio::test]
+    async fn undelegated_destination() {
+        // an EOA without code
+        let eoa = MockUpstream { code: Bytes::new(), ..Default::default() };
+        let wallet = TraverseWallet::new(eoa, 1);
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "destination_not_delegated");
+
+        // a plain contract
+        let contract = MockUpstream {
+            code: Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xf3]),
+            ..Default::default()
+        };
+        let wallet = TraverseWallet::new(contract, 1);
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "destination_not_a_delegation");
+    }
+
+    #[tokio::test]
+    async fn gas_buffer() {
+        // the estimate of 100k is buffered by 10%
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_gas_buffer_percent(10),
+        );
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+        assert_eq!(wallet.inner.upstream.sent.lock()[0].gas, Some(110_000));
+
+        // the ceiling applies to the buffered gas, even though the estimate is below it
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default()
+                .with_gas_buffer_percent(10)
+                .with_default_gas_ceiling(105_000),
+        );
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "gas_estimate_too_high");
+    }
+
+    #[tokio::test]
+    async fn gas_limit_hint() {
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_default_gas_ceiling(200_000),
+        );
+
+        // a hint above the estimate of 100k raises the gas limit
+        wallet.send_transaction(delegated_call(), Some(150_000)).await.unwrap();
+        // a hint below the estimate is ignored
+        wallet.send_transaction(delegated_call(), Some(50_000)).await.unwrap();
+        {
+            let sent = wallet.inner.upstream.sent.lock();
+            assert_eq!(sent[0].gas, Some(150_000));
+            assert_eq!(sent[1].gas, Some(100_000));
+        }
+        assert_eq!(wallet.inner.in_flight.lock().total, 250_000);
+
+        // a hint at or above the ceiling is rejected
+        let err = wallet.send_transaction(delegated_call(), Some(200_000)).await.unwrap_err();
+        assert_eq!(reason(&err), "gas_limit_hint_too_high");
+        assert_eq!(err.code(), INVALID_PARAMS_CODE);
+        assert_eq!(wallet.inner.upstream.sent.lock().len(), 2);
+    }
+
+    /// A [`SponsorSigner`] that records the requests it signs.
+    struct MockSigner {
+        wallet: EthereumWallet,
+        signed: parking_lot::Mutex<Vec<TransactionRequest>>,
+    }
+
+    #[async_trait]
+    impl SponsorSigner for MockSigner {
+        fn address(&self) -> Address {
+            self.wallet.address()
+        }
+
+        async fn sign(&self, tx: TransactionRequest) -> Result<TxEnvelope, TraverseWalletError> {
+            self.signed.lock().push(tx.clone());
+            self.wallet.sign(tx).await
+        }
+    }
+
+    #[tokio::test]
+    async fn sponsor_signer() {
+        let signer = MockSigner {
+            wallet: EthereumWallet::from(PrivateKeySigner::random()),
+            signed: Default::default(),
+        };
+        let mut request = delegated_call()
+            .from(signer.address())
+            .gas_limit(100_000)
+            .max_fee_per_gas(2)
+            .max_priority_fee_per_gas(1);
+        request.chain_id = Some(1);
+
+        let envelope = sign_with_nonce(&signer, request, 7).await.unwrap();
+        assert!(matches!(envelope, TxEnvelope::Eip1559(_)));
+        assert_eq!(envelope.recover_signer().unwrap(), signer.address());
+
+        let signed = signer.signed.lock();
+        assert_eq!(signed.len(), 1);
+        assert_eq!(signed[0].nonce, Some(7));
+    }
+
+    #[tokio::test]
+    async fn add_sponsored_transaction_to_pool() {
+        let signer = PrivateKeySigner::random();
+        let wallet = EthereumWallet::from(signer.clone());
+        let mut request = delegated_call()
+            .from(signer.address())
+            .gas_limit(100_000)
+            .max_fee_per_gas(2)
+            .max_priority_fee_per_gas(1);
+        request.chain_id = Some(1);
+        let envelope = sign_with_nonce(&wallet, request, 0).await.unwrap();
+
+        let pool = testing_pool();
+        let tx_hash = add_to_pool(&pool, &envelope.encoded_2718(), signer.address()).await.unwrap();
+        assert_eq!(tx_hash, *envelope.tx_hash());
+
+        // the transaction is in the local pool
+        let tx = pool.get(&tx_hash).unwrap();
+        assert_eq!(tx.sender(), signer.address());
+        assert!(tx.origin.is_local());
+        assert_eq!(pool.pool_size().total, 1);
+
+        // malformed transactions are rejected
+        let err = add_to_pool(&pool, &[0x02, 0x01], signer.address()).await.unwrap_err();
+        assert!(err.is_internal());
+        assert_eq!(pool.pool_size().total, 1);
+    }
+
+    #[tokio::test]
+    async fn rotating_signer() {
+        let previous = PrivateKeySigner::random();
+        let signer = RotatingSigner::new(EthereumWallet::from(previous.clone()));
+        assert_eq!(signer.address(), previous.address());
+
+        let mut request =
+            delegated_call().gas_limit(100_000).max_fee_per_gas(2).max_priority_fee_per_gas(1);
+        request.chain_id = Some(1);
+
+        let next = PrivateKeySigner::random();
+        signer.rotate(EthereumWallet::from(next.clone())).unwrap();
+        assert_eq!(signer.address(), next.address());
+        let envelope = sign_with_nonce(&signer, request.from(next.address()), 0).await.unwrap();
+        assert_eq!(envelope.recover_signer().unwrap(), next.address());
+
+        // plain wallets cannot be rotated
+        let err = EthereumWallet::from(previous).rotate(EthereumWallet::from(next)).unwrap_err();
+        assert_eq!(err.reason(), "signer_rotation_unsupported");
+    }
+
+    #[tokio::test]
+    async fn rotate_signer() {
+        let upstream = MockUpstream { send_delay: Duration::from_millis(50), ..Default::default() };
+        let previous = upstream.default_signer_address();
+        let wallet = TraverseWallet::new(upstream, 1);
+
+        // rotate while a transaction is signed and sent
+        let in_flight = tokio::spawn({
+            let wallet = wallet.clone();
+            async move { wallet.send_transaction(delegated_call(), None).await }
+        });
+        while wallet.inner.permit.try_lock().is_ok() {
+            tokio::task::yield_now().await;
+        }
+        let next = PrivateKeySigner::random();
+        let address =
+            TraverseWalletAdminApiServer::rotate_signer(&wallet, next.to_bytes()).await.unwrap();
+        assert_eq!(address, next.address());
+
+        // the in-flight transaction was drained before the rotation
+        assert_eq!(wallet.inner.upstream.sent.lock()[0].from, Some(previous));
+        in_flight.await.unwrap().unwrap();
+
+        // subsequent transactions are sent by the new signer
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+        assert_eq!(wallet.inner.upstream.sent.lock()[1].from, Some(next.address()));
+
+        let err =
+            TraverseWalletAdminApiServer::rotate_signer(&wallet, B256::ZERO).await.unwrap_err();
+        assert_eq!(reason(&err), "invalid_signer");
+
+        // the signer of a relayed sponsor is held by the relay
+        let wallet = TraverseWallet::new(
+            RelayUpstream::new(
+                MockUpstream::default(),
+                previous,
+                "http://localhost:1".parse().unwrap(),
+            ),
+            1,
+        );
+        let err = wallet.rotate_signer(EthereumWallet::from(next)).await.unwrap_err();
+        assert_eq!(err.reason(), "signer_rotation_unsupported");
+    }
+
+    #[tokio::test]
+    async fn delegation_too_recent() {
+        let upstream = MockUpstream { code_changed_at: Some(99), ..Default::default() };
+
+        // the age of delegations is not checked by default
+        let wallet = TraverseWallet::new(upstream, 1);
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+
+        let upstream = MockUpstream { code_changed_at: Some(99), ..Default::default() };
+        let wallet = TraverseWallet::with_config(
+            upstream,
+            1,
+            TraverseWalletConfig::default().with_min_delegation_age(10),
+        );
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "delegation_too_recent");
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_min_delegation_age(10),
+        );
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn last_code_change_search() {
+        // the code changed at block 95
+        let code_at = |number: BlockNumber| async move {
+            Ok(if number < 95 { Bytes::new() } else { designator(DELEGATION) })
+        };
+
+        assert_eq!(last_code_change(100, 10, code_at).await.unwrap(), Some(95));
+        assert_eq!(last_code_change(100, 5, code_at).await.unwrap(), Some(95));
+        assert_eq!(last_code_change(100, 4, code_at).await.unwrap(), None);
+        assert_eq!(last_code_change(100, 0, code_at).await.unwrap(), None);
+        assert_eq!(last_code_change(3, 10, code_at).await.unwrap(), None);
+
+        // the lookups are bounded by the logarithm of the window
+        let lookups = AtomicUsize::new(0);
+        let counted = |number: BlockNumber| {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            async move { Ok(if number < 95 { Bytes::new() } else { designator(DELEGATION) }) }
+        };
+        let window = MAX_MIN_DELEGATION_AGE;
+        assert_eq!(last_code_change(100_000, window, counted).await.unwrap(), None);
+        assert_eq!(lookups.swap(0, Ordering::SeqCst), 2);
+        assert_eq!(last_code_change(40_000, window, counted).await.unwrap(), Some(95));
+        assert!(lookups.load(Ordering::SeqCst) <= 2 + window.ilog2() as usize + 1);
+
+        // the code changed at block 95, and changed back at block 98
+        let code_at = |number: BlockNumber| async move {
+            let delegation = if (95..98).contains(&number) { Address::ZERO } else { DELEGATION };
+            Ok(designator(delegation))
+        };
+        assert_eq!(last_code_change(100, 4, code_at).await.unwrap(), Some(98));
+        // a change that was reverted within the window is not found
+        assert_eq!(last_code_change(100, 10, code_at).await.unwrap(), None);
+        assert_eq!(last_code_change(100, 2, code_at).await.unwrap(), None);
+
+        // errors of the lookup are returned
+        let code_at = |number: BlockNumber| async move {
+            if number == 90 {
+                return Err(TraverseWalletError::InternalError(eyre::eyre!("pruned")));
+            }
+            Ok(designator(DELEGATION))
+        };
+        assert!(last_code_change(100, 10, code_at).await.is_err());
+        assert_eq!(last_code_change(100, 4, code_at).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn get_delegation() {
+        let account = Address::with_last_byte(0xa);
+        let delegation = |code: Bytes| async move {
+            TraverseWallet::new(MockUpstream { code, ..Default::default() }, 1)
+                .get_delegation(account, None)
+                .await
+                .unwrap()
+        };
+
+        // a delegated account
+        assert_eq!(delegation(designator(DELEGATION)).await, Some(DELEGATION));
+        // a cleared delegation
+        assert_eq!(delegation(designator(Address::ZERO)).await, None);
+        // a plain contract, and an account without code
+        assert_eq!(delegation(Bytes::from_static(&[0x60, 0x00])).await, None);
+        assert_eq!(delegation(Bytes::new()).await, None);
+
+        // the designator is parsed like the destination of sponsored transactions
+        assert_eq!(delegation_designator(&designator(DELEGATION)), Some(DELEGATION));
+        assert_eq!(delegation_designator(&designator(DELEGATION)[..22]), None);
+    }
+
+    #[tokio::test]
+    async fn trace_sponsored_transaction() {
+        // tracing is opt-in
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+        let err = wallet.trace_sponsored_transaction(delegated_call()).await.unwrap_err();
+        assert_eq!(reason(&err), "tracing_disabled");
+
+        let config = TraverseWalletConfig::default().with_trace_sponsored_transactions(true);
+        let wallet = TraverseWallet::with_config(MockUpstream::default(), 1, config.clone());
+
+        // the trace of the upstream is returned, and the transaction is not sent
+        let result = wallet.trace_sponsored_transaction(delegated_call()).await.unwrap();
+        let TraceResult::Success { result: GethTrace::CallTracer(frame), tx_hash: None } = result
+        else {
+            panic!("expected a call trace");
+        };
+        assert_eq!(frame.from, wallet.inner.upstream.default_signer_address());
+        assert_eq!(frame.to, Some(DESTINATION));
+        assert_eq!(frame.error.as_deref(), Some("execution reverted"));
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+
+        // the request is checked like a sponsored transaction
+        let err = wallet
+            .trace_sponsored_transaction(delegated_call().value(U256::from(1)))
+            .await
+            .unwrap_err();
+        assert_eq!(reason(&err), "value_not_zero");
+        assert_eq!(wallet.get_stats(None).await.unwrap(), WalletStats::default());
+
+        // the delegation of the destination is checked like for a sponsored transaction
+        let upstream = MockUpstream { code: Bytes::new(), ..Default::default() };
+        let wallet = TraverseWallet::with_config(upstream, 1, config.clone());
+        let err = wallet.trace_sponsored_transaction(delegated_call()).await.unwrap_err();
+        assert_eq!(reason(&err), "destination_not_delegated");
+
+        let upstream = MockUpstream { code_changed_at: Some(1), ..Default::default() };
+        let config = config.with_min_delegation_age(10);
+        let wallet = TraverseWallet::with_config(upstream, 1, config);
+        let err = wallet.trace_sponsored_transaction(delegated_call()).await.unwrap_err();
+        assert_eq!(reason(&err), "delegation_too_recent");
+
+        // tracing is rejected while sponsoring is paused
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_trace_sponsored_transactions(true),
+        );
+        wallet.pause();
+        let err = wallet.trace_sponsored_transaction(delegated_call()).await.unwrap_err();
+        assert_eq!(reason(&err), "sponsoring_paused");
+    }
+
+    #[tokio::test]
+    async fn delegation_chain() {
+        let (a, b, c) = (Address::with_last_byte(0xa), Address::with_last_byte(0xb), DELEGATION);
+        let chain = |codes: HashMap<Address, Bytes>, max_depth| {
+            check_delegation_chain(a, b, max_depth, move |address| {
+                let code = codes.get(&address).cloned().unwrap_or_default();
+                async move { Ok(code) }
+            })
         };
-        Self { inner: Arc::new(inner) }
+
+        // a simple delegation, the delegation target is not delegated
+        let simple = HashMap::from([(a, designator(b))]);
+        assert!(chain(simple.clone(), 1).await.is_ok());
+        assert!(chain(simple, 2).await.is_ok());
+
+        // a 2-hop chain is only accepted within depth
+        let two_hops = HashMap::from([(a, designator(b)), (b, designator(c))]);
+        assert!(chain(two_hops.clone(), 2).await.is_ok());
+        assert!(matches!(
+            chain(two_hops, 1).await,
+            Err(TraverseWalletError::DelegationChainTooDeep { max_depth: 1 })
+        ));
+
+        // a cycle is rejected at any depth
+        let cycle = HashMap::from([(a, designator(b)), (b, designator(a))]);
+        assert!(matches!(
+            chain(cycle, 10).await,
+            Err(TraverseWalletError::DelegationChainTooDeep { max_depth: 10 })
+        ));
     }
 
-    #[allow(clippy::missing_const_for_fn)]
-    fn chain_id(&self) -> ChainId {
-        self.inner.chain_id
+    #[tokio::test]
+    async fn delegation_chain_rejected() {
+        // every account delegates to the delegation contract, including itself
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_max_delegation_depth(4),
+        );
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "delegation_chain_too_deep");
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+
+        // the chain is not followed by default
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
     }
-}
 
-#[async_trait]
-impl<T> TraverseWalletApiServer for TraverseWallet<T>
-where
-    T: Upstream + Sync + Send + 'static,
-{
-    async fn send_transaction(&self, mut request: TransactionRequest) -> RpcResult<TxHash> {
-        trace!(target: "rpc::wallet", ?request, "Serving traverse_sendTransaction");
+    #[tokio::test]
+    async fn priority_fee_cap() {
+        let upstream = MockUpstream {
+            fees: Eip1559Estimation { max_fee_per_gas: 100, max_priority_fee_per_gas: 50 },
+            ..Default::default()
+        };
+        let wallet = TraverseWallet::with_config(
+            upstream,
+            1,
+            TraverseWalletConfig::default().with_max_priority_fee_cap(10),
+        );
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
 
-        // validate fields common to eip-7702 and eip-1559
-        if let Err(err) = validate_tx_request(&request) {
-            self.inner.metrics.invalid_send_transaction_calls.increment(1);
-            return Err(err.into());
-        }
+        let sent = wallet.inner.upstream.sent.lock();
+        assert_eq!(sent[0].max_priority_fee_per_gas, Some(10));
+        assert_eq!(sent[0].max_fee_per_gas, Some(100));
+    }
 
-        // validate destination
-        match (request.authorization_list.is_some(), request.to) {
-            // if this is an eip-1559 tx, ensure that it is an account that delegates to a
-            // whitelisted address
-            (false, Some(TxKind::Call(addr))) => {
-                let code = self.inner.upstream.get_code(addr).await?;
-                match code.as_ref() {
-                    // A valid EIP-7702 delegation
-                    [0xef, 0x01, 0x00, address @ ..] => {
-                        let addr = Address::from_slice(address);
-                        // the delegation was cleared
-                        if addr.is_zero() {
-                            self.inner.metrics.invalid_send_transaction_calls.increment(1);
-                            return Err(TraverseWalletError::IllegalDestination.into());
-                        }
-                    }
-                    // Not an EIP-7702 delegation, or an empty (cleared) delegation
-                    _ => {
-                        self.inner.metrics.invalid_send_transaction_calls.increment(1);
-                        return Err(TraverseWalletError::IllegalDestination.into());
-                    }
+    #[test]
+    fn priority_fee_cap_keeps_max_fee() {
+        let config = TraverseWalletConfig::default().with_max_priority_fee_cap(10);
+        let estimate = config.cap_priority_fee(Eip1559Estimation {
+            max_fee_per_gas: 5,
+            max_priority_fee_per_gas: 20,
+        });
+        assert_eq!(estimate.max_priority_fee_per_gas, 10);
+        assert_eq!(estimate.max_fee_per_gas, 10);
+
+        // the estimate is unchanged without a cap
+        let estimate = TraverseWalletConfig::default().cap_priority_fee(Eip1559Estimation {
+            max_fee_per_gas: 5,
+            max_priority_fee_per_gas: 20,
+        });
+        assert_eq!(estimate.max_priority_fee_per_gas, 20);
+    }
+
+    #[tokio::test]
+    async fn zero_gas_estimate() {
+        let wallet = TraverseWallet::new(MockUpstream { gas: 0, ..Default::default() }, 1);
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "invalid_gas_estimate");
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn below_intrinsic_gas_estimate() {
+        let wallet = TraverseWallet::new(MockUpstream { gas: 21_000, ..Default::default() }, 1);
+
+        // a plain call costs exactly the base intrinsic gas
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+
+        // the calldata adds to the intrinsic gas
+        let request = delegated_call().input(Bytes::from_static(&[0x01]).into());
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "invalid_gas_estimate");
+        assert_eq!(wallet.inner.upstream.sent.lock().len(), 1);
+    }
+
+    #[test]
+    fn intrinsic_gas_of_request() {
+        assert_eq!(intrinsic_gas(&delegated_call()), 21_000);
+
+        let access_list = AccessList(vec![AccessListItem {
+            address: DELEGATION,
+            storage_keys: vec![Default::default()],
+        }]);
+        let request = delegated_call()
+            .input(Bytes::from_static(&[0x00, 0x01]).into())
+            .access_list(access_list);
+        assert_eq!(intrinsic_gas(&request), 21_000 + 4 + 16 + 2_400 + 1_900);
+
+        let signer = PrivateKeySigner::random();
+        let request = TransactionRequest::default()
+            .authorization_list(vec![sign_authorization(&signer, DELEGATION)]);
+        assert_eq!(intrinsic_gas(&request), 21_000 + 25_000);
+    }
+
+    #[tokio::test]
+    async fn upstream_timeout() {
+        let upstream = MockUpstream { send_delay: Duration::from_secs(10), ..Default::default() };
+        let wallet = TraverseWallet::with_config(
+            upstream,
+            1,
+            TraverseWalletConfig::default().with_upstream_timeout(Duration::from_millis(10)),
+        );
+
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "upstream_timeout");
+
+        // the permit was released
+        assert!(wallet.inner.permit.try_lock().is_ok());
+    }
+
+    #[tokio::test]
+    async fn estimate_timeout() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let wallet = metrics::with_local_recorder(&recorder, || {
+            TraverseWallet::with_config(
+                MockUpstream { estimate_delay: Duration::from_secs(10), ..Default::default() },
+                1,
+                TraverseWalletConfig::default().with_estimate_timeout(Duration::from_millis(10)),
+            )
+        });
+
+        // the estimate times out, while signing would be given the upstream timeout
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "estimate_timeout");
+        assert!(err.is_internal());
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+        assert!(wallet.inner.permit.try_lock().is_ok());
+
+        let timeouts =
+            snapshotter.snapshot().into_vec().into_iter().find_map(|(key, _, _, value)| {
+                match (key.key().name(), value) {
+                    ("wallet.estimate_timeouts", DebugValue::Counter(count)) => Some(count),
+                    _ => None,
                 }
-            }
-            // if it's an eip-7702 tx, let it through
-            (true, _) => (),
-            // create tx's disallowed
-            _ => {
-                self.inner.metrics.invalid_send_transaction_calls.increment(1);
-                return Err(TraverseWalletError::IllegalDestination.into());
-            }
-        }
+            });
+        assert_eq!(timeouts, Some(1));
+    }
 
-        // we acquire the permit here so that all following operations are performed exclusively
-        let _permit = self.inner.permit.lock().await;
+    #[tokio::test]
+    async fn cleared_delegation() {
+        let upstream = MockUpstream { code: designator(Address::ZERO), ..Default::default() };
+        let wallet = TraverseWallet::new(upstream, 1);
 
-        // set chain id
-        request.chain_id = Some(self.chain_id());
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "delegation_cleared");
+    }
 
-        // set gas limit
-        // note: we also set the `from` field here to correctly estimate for contracts that use e.g.
-        // `tx.origin`
-        request.from = Some(self.inner.upstream.default_signer_address());
-        let (estimate, fee_estimate) = self
-            .inner
-            .upstream
-            .estimate(&request)
-            .await
-            .inspect_err(|_| self.inner.metrics.invalid_send_transaction_calls.increment(1))?;
-        if estimate >= 350_000 {
-            self.inner.metrics.invalid_send_transaction_calls.increment(1);
-            return Err(TraverseWalletError::GasEstimateTooHigh { estimate }.into());
+    #[tokio::test]
+    async fn client_fees() {
+        let config = TraverseWalletConfig::default().with_fee_policy(FeePolicy::Client {
+            max_priority_fee_cap: 10,
+            max_fee_per_gas_cap: 100,
+        });
+        let wallet = TraverseWallet::with_config(MockUpstream::default(), 1, config);
+
+        // the fees of the client are used instead of the estimate of 2/1
+        let request = delegated_call().max_fee_per_gas(100).max_priority_fee_per_gas(10);
+        wallet.send_transaction(request, None).await.unwrap();
+        // a request with only one of the fees is priced by the service
+        wallet.send_transaction(delegated_call().max_fee_per_gas(50), None).await.unwrap();
+        {
+            let sent = wallet.inner.upstream.sent.lock();
+            assert_eq!(sent[0].max_fee_per_gas, Some(100));
+            assert_eq!(sent[0].max_priority_fee_per_gas, Some(10));
+            assert_eq!(sent[1].max_fee_per_gas, Some(2));
+            assert_eq!(sent[1].max_priority_fee_per_gas, Some(1));
         }
-        request.gas = Some(estimate);
 
-        // set gas price
-        request.max_fee_per_gas = Some(fee_estimate.max_fee_per_gas);
-        request.max_priority_fee_per_gas = Some(fee_estimate.max_priority_fee_per_gas);
-        request.gas_price = None;
+        // the fees of the client are ignored by default
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+        let request = delegated_call().max_fee_per_gas(100).max_priority_fee_per_gas(10);
+        wallet.send_transaction(request, None).await.unwrap();
+        assert_eq!(wallet.inner.upstream.sent.lock()[0].max_fee_per_gas, Some(2));
+    }
 
-        // all checks passed, increment the valid calls counter
-        self.inner.metrics.valid_send_transaction_calls.increment(1);
+    #[tokio::test]
+    async fn client_fees_above_caps() {
+        let config = TraverseWalletConfig::default().with_fee_policy(FeePolicy::Client {
+            max_priority_fee_cap: 10,
+            max_fee_per_gas_cap: 100,
+        });
+        let wallet = TraverseWallet::with_config(MockUpstream::default(), 1, config);
 
-        Ok(self.inner.upstream.sign_and_send(request).await.inspect_err(
-            |err| warn!(target: "rpc::wallet", ?err, "Error adding sponsored tx to pool"),
-        )?)
+        // a client priority fee above the cap is rejected instead of clamped
+        let request = delegated_call().max_fee_per_gas(100).max_priority_fee_per_gas(11);
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "priority_fee_too_high");
+
+        let request = delegated_call().max_fee_per_gas(101).max_priority_fee_per_gas(10);
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "fee_too_high");
+
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+        assert_eq!(wallet.inner.in_flight.lock().total, 0);
     }
-}
 
-/// Implementation of the Traverse `wallet_` namespace.
-#[derive(Debug)]
-struct TraverseWalletInner<T> {
-    upstream: T,
-    chain_id: ChainId,
-    /// Used to guard tx signing
-    permit: Mutex<()>,
-    /// Metrics for the `wallet_` RPC namespace.
-    metrics: WalletMetrics,
-}
+    #[tokio::test]
+    async fn inconsistent_client_fees() {
+        let config = TraverseWalletConfig::default().with_fee_policy(FeePolicy::Client {
+            max_priority_fee_cap: 10,
+            max_fee_per_gas_cap: 100,
+        });
+        let upstream = MockUpstream {
+            fee_history: FeeHistory { base_fee_per_gas: vec![10, 20], ..Default::default() },
+            ..Default::default()
+        };
+        let wallet = TraverseWallet::with_config(upstream, 1, config);
 
-fn validate_tx_request(request: &TransactionRequest) -> Result<(), TraverseWalletError> {
-    // reject transactions that have a non-zero value to prevent draining the service.
-    if request.value.is_some_and(|val| val > U256::ZERO) {
-        return Err(TraverseWalletError::ValueNotZero);
+        // the priority fee is above the max fee
+        let request = delegated_call().max_fee_per_gas(25).max_priority_fee_per_gas(26);
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "inconsistent_fees");
+
+        // the max fee is below the base fee of the next block
+        let request = delegated_call().max_fee_per_gas(19).max_priority_fee_per_gas(1);
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "inconsistent_fees");
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+
+        // the max fee covers the base fee and the priority fee
+        let request = delegated_call().max_fee_per_gas(20).max_priority_fee_per_gas(1);
+        wallet.send_transaction(request, None).await.unwrap();
     }
 
-    // reject transactions that have from set, as this will be the service.
-    if request.from.is_some() {
-        return Err(TraverseWalletError::FromSet);
+    #[tokio::test]
+    async fn client_fees_above_service_cap() {
+        let config = TraverseWalletConfig::default()
+            .with_fee_policy(FeePolicy::Client {
+                max_priority_fee_cap: 10,
+                max_fee_per_gas_cap: 100,
+            })
+            .with_max_fee_per_gas_cap(50);
+        let wallet = TraverseWallet::with_config(MockUpstream::default(), 1, config);
+
+        // the cap of the service applies to client fees within the caps of the policy
+        let request = delegated_call().max_fee_per_gas(51).max_priority_fee_per_gas(10);
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "fee_too_high");
+
+        let request = delegated_call().max_fee_per_gas(50).max_priority_fee_per_gas(10);
+        wallet.send_transaction(request, None).await.unwrap();
     }
 
-    // reject transaction requests that have nonce set, as this is managed by the service.
-    if request.nonce.is_some() {
-        return Err(TraverseWalletError::NonceSet);
+    #[test]
+    fn fee_policy_serde() {
+        let policy: FeePolicy =
+            serde_json::from_str(r#"{"client":{"maxPriorityFeeCap":10,"maxFeePerGasCap":100}}"#)
+                .unwrap();
+        assert_eq!(
+            policy,
+            FeePolicy::Client { max_priority_fee_cap: 10, max_fee_per_gas_cap: 100 }
+        );
+        assert_eq!(serde_json::from_str::<FeePolicy>(r#""service""#).unwrap(), FeePolicy::Service);
     }
 
-    Ok(())
-}
+    #[tokio::test]
+    async fn fee_history_pricing() {
+        let upstream = MockUpstream {
+            fee_history: FeeHistory {
+                base_fee_per_gas: vec![10, 20],
+                reward: Some(vec![vec![5], vec![7]]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let wallet = TraverseWallet::with_config(
+            upstream,
+            1,
+            TraverseWalletConfig::default()
+                .with_fee_history(FeeHistoryConfig { block_count: 2, percentile: 50.0 }),
+        );
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
 
-/// Metrics for the `wallet_` RPC namespace.
-#[derive(Metrics)]
-#[metrics(scope = "wallet")]
-struct WalletMetrics {
-    /// Number of invalid calls to `traverse_sendTransaction`
-    invalid_send_transaction_calls: Counter,
-    /// Number of valid calls to `traverse_sendTransaction`
-    valid_send_transaction_calls: Counter,
-}
+        // the fee history is higher than the point estimate of 2/1
+        let sent = wallet.inner.upstream.sent.lock();
+        assert_eq!(sent[0].max_priority_fee_per_gas, Some(7));
+        assert_eq!(sent[0].max_fee_per_gas, Some(2 * 20 + 7));
+    }
 
-#[cfg(test)]
-mod tests {
-    use crate::{validate_tx_request, TraverseWalletError};
-    use alloy_primitives::{Address, U256};
-    use alloy_rpc_types::TransactionRequest;
+    #[tokio::test]
+    async fn fee_too_high() {
+        let upstream = MockUpstream {
+            fees: Eip1559Estimation { max_fee_per_gas: 100, max_priority_fee_per_gas: 10 },
+            ..Default::default()
+        };
+        let wallet = TraverseWallet::with_config(
+            upstream,
+            1,
+            TraverseWalletConfig::default().with_max_fee_per_gas_cap(99),
+        );
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "fee_too_high");
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+        // the rejected request does not count towards the in-flight budget
+        assert_eq!(wallet.inner.in_flight.lock().total, 0);
+
+        // a max fee at the cap is sponsored
+        let upstream = MockUpstream {
+            fees: Eip1559Estimation { max_fee_per_gas: 100, max_priority_fee_per_gas: 10 },
+            ..Default::default()
+        };
+        let wallet = TraverseWallet::with_config(
+            upstream,
+            1,
+            TraverseWalletConfig::default().with_max_fee_per_gas_cap(100),
+        );
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+        assert_eq!(wallet.inner.upstream.sent.lock()[0].max_fee_per_gas, Some(100));
+    }
+
+    #[tokio::test]
+    async fn known_delegations() {
+        // the destination has no code, so the lookup would reject it
+        let wallet =
+            TraverseWallet::new(MockUpstream { code: Bytes::new(), ..Default::default() }, 1);
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "destination_not_delegated");
+
+        let mut events = wallet.sponsored_transactions();
+        TraverseWalletAdminApiServer::add_known_delegation(&wallet, DESTINATION, DELEGATION)
+            .await
+            .unwrap();
+        assert_eq!(wallet.known_delegation(DESTINATION), Some(DELEGATION));
+
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+        assert_eq!(events.recv().await.unwrap().delegation, Some(DELEGATION));
+
+        // the code is looked up again once the delegation is removed
+        let removed =
+            TraverseWalletAdminApiServer::remove_known_delegation(&wallet, DESTINATION).await;
+        assert_eq!(removed.unwrap(), Some(DELEGATION));
+        assert_eq!(wallet.known_delegation(DESTINATION), None);
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "destination_not_delegated");
+    }
 
     #[test]
-    fn no_value_allowed() {
+    fn invalid_known_delegations() {
+        let config = TraverseWalletConfig::default()
+            .with_delegations(DelegationCapability { addresses: vec![DELEGATION] });
+        let wallet = TraverseWallet::with_config(MockUpstream::default(), 1, config);
+
         assert!(matches!(
-            validate_tx_request(&TransactionRequest::default().value(U256::from(1))),
-            Err(TraverseWalletError::ValueNotZero)
+            wallet.add_known_delegation(Address::ZERO, DELEGATION),
+            Err(TraverseWalletError::IllegalDestination)
+        ));
+        assert!(matches!(
+            wallet.add_known_delegation(DESTINATION, Address::ZERO),
+            Err(TraverseWalletError::DelegationCleared)
+        ));
+        // only delegations to the configured contracts are known
+        let other = Address::with_last_byte(0xdd);
+        assert!(matches!(
+            wallet.add_known_delegation(DESTINATION, other),
+            Err(TraverseWalletError::UnsupportedDelegation(address)) if address == other
         ));
+        assert_eq!(wallet.known_delegation(DESTINATION), None);
 
+        // the number of known delegations is bounded, but known accounts can be updated
+        for i in 0..MAX_KNOWN_DELEGATIONS {
+            let account = Address::left_padding_from(&(i as u64 + 1).to_be_bytes());
+            wallet.add_known_delegation(account, DELEGATION).unwrap();
+        }
         assert!(matches!(
-            validate_tx_request(&TransactionRequest::default().value(U256::from(0))),
-            Ok(())
+            wallet.add_known_delegation(DESTINATION, DELEGATION),
+            Err(TraverseWalletError::TooManyKnownDelegations)
         ));
+        wallet.add_known_delegation(Address::with_last_byte(1), DELEGATION).unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn sender_budget() {
+        // each transaction costs 100_000 gas at a max fee of 2
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_sender_daily_budget(U256::from(400_000)),
+        );
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "sender_budget_exceeded");
+        assert_eq!(wallet.inner.upstream.sent.lock().len(), 2);
+
+        // spends leave the budget once they are out of the window
+        tokio::time::advance(SENDER_BUDGET_WINDOW).await;
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+        assert_eq!(wallet.inner.upstream.sent.lock().len(), 3);
+        assert_eq!(wallet.inner.sender_spend.lock().senders[&DESTINATION].len(), 1);
+
+        // senders without spends in the window are evicted
+        tokio::time::advance(SENDER_BUDGET_WINDOW).await;
+        wallet.inner.sender_spend.lock().evict(Instant::now());
+        assert!(wallet.inner.sender_spend.lock().senders.is_empty());
+    }
+
+    #[tokio::test]
+    async fn l1_data_fee_accounting() {
+        // the L1 data fee is converted to gas at the max fee, rounding up
+        assert_eq!(sponsored_gas(100_000, U256::ZERO, 2), 100_000);
+        assert_eq!(sponsored_gas(100_000, U256::from(2_001), 2), 101_001);
+        assert_eq!(sponsored_gas(100_000, U256::from(2_001), 0), 100_000);
+
+        // each transaction costs 100_000 gas at a max fee of 2, plus an L1 data fee of 20_000, so
+        // two transactions only fit the budget without the L1 data fee
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let wallet = metrics::with_local_recorder(&recorder, || {
+            TraverseWallet::with_config(
+                MockUpstream { l1_data_fee: U256::from(20_000), ..Default::default() },
+                1,
+                TraverseWalletConfig::default()
+                    .with_l1_data_fee(true)
+                    .with_sender_daily_budget(U256::from(400_000)),
+            )
+        });
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+        assert_eq!(wallet.inner.in_flight.lock().total, 110_000);
+
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "sender_budget_exceeded");
+        assert_eq!(wallet.inner.upstream.sent.lock().len(), 1);
+
+        let gas_used =
+            snapshotter.snapshot().into_vec().into_iter().find_map(|(key, _, _, value)| {
+                match (key.key().name(), value) {
+                    ("wallet.sponsored_gas_used", DebugValue::Counter(gas)) => Some(gas),
+                    _ => None,
+                }
+            });
+        assert_eq!(gas_used, Some(110_000));
     }
 
     #[test]
-    fn no_from_allowed() {
-        assert!(matches!(
-            validate_tx_request(&TransactionRequest::default().from(Address::ZERO)),
-            Err(TraverseWalletError::FromSet)
-        ));
+    fn fee_strategies() {
+        let estimate = Eip1559Estimation { max_fee_per_gas: 30, max_priority_fee_per_gas: 10 };
+
+        assert_eq!(FeeStrategy::default().apply(estimate, None), estimate);
+
+        let bumped = FeeStrategy::Bump(1.5).apply(estimate, None);
+        assert_eq!(bumped.max_fee_per_gas, 45);
+        assert_eq!(bumped.max_priority_fee_per_gas, 15);
+        // fees are never lowered
+        assert_eq!(FeeStrategy::Bump(0.5).apply(estimate, None), estimate);
+
+        let fixed = FeeStrategy::FixedTip(3).apply(estimate, None);
+        assert_eq!(fixed.max_priority_fee_per_gas, 3);
+        assert_eq!(fixed.max_fee_per_gas, 30 - 10 + 3);
+
+        let history = FeeHistory {
+            base_fee_per_gas: vec![10, 20],
+            reward: Some(vec![vec![5], vec![7]]),
+            ..Default::default()
+        };
+        let percentile = FeeStrategy::Percentile(90).apply(estimate, Some(&history));
+        assert_eq!(percentile.max_priority_fee_per_gas, 7);
+        assert_eq!(percentile.max_fee_per_gas, 2 * 20 + 7);
+        // an empty history falls back to the estimate
+        let empty = FeeHistory::default();
+        assert_eq!(FeeStrategy::Percentile(90).apply(estimate, Some(&empty)), estimate);
+    }
 
-        assert!(matches!(validate_tx_request(&TransactionRequest::default()), Ok(())));
+    #[tokio::test]
+    async fn fee_strategy_pricing() {
+        let upstream = MockUpstream {
+            fees: Eip1559Estimation { max_fee_per_gas: 30, max_priority_fee_per_gas: 10 },
+            fee_history: FeeHistory {
+                base_fee_per_gas: vec![10, 20],
+                reward: Some(vec![vec![5], vec![7]]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let wallet = TraverseWallet::with_config(
+            upstream,
+            1,
+            TraverseWalletConfig::default().with_fee_strategy(FeeStrategy::FixedTip(1)),
+        );
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+        {
+            let sent = wallet.inner.upstream.sent.lock();
+            assert_eq!(sent[0].max_priority_fee_per_gas, Some(1));
+            assert_eq!(sent[0].max_fee_per_gas, Some(21));
+        }
+
+        let upstream = MockUpstream {
+            fee_history: FeeHistory {
+                base_fee_per_gas: vec![10, 20],
+                reward: Some(vec![vec![5], vec![7]]),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let wallet = TraverseWallet::with_config(
+            upstream,
+            1,
+            TraverseWalletConfig::default().with_fee_strategy(FeeStrategy::Percentile(50)),
+        );
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+        let sent = wallet.inner.upstream.sent.lock();
+        assert_eq!(sent[0].max_priority_fee_per_gas, Some(7));
+        assert_eq!(sent[0].max_fee_per_gas, Some(2 * 20 + 7));
     }
 
     #[test]
-    fn no_nonce_allowed() {
-        assert!(matches!(
-            validate_tx_request(&TransactionRequest::default().nonce(1)),
-            Err(TraverseWalletError::NonceSet)
-        ));
+    fn rejections_per_reason() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+
+        // the request is rejected before reaching the upstream
+        let reject = |request: TransactionRequest| {
+            metrics::with_local_recorder(&recorder, || {
+                futures::executor::block_on(wallet.send_transaction(request, None)).unwrap_err()
+            })
+        };
+        reject(delegated_call().value(U256::from(1)));
+        reject(delegated_call().value(U256::from(1)));
+        reject(delegated_call().nonce(1));
+
+        let rejections = |reason: &str| {
+            snapshotter.snapshot().into_vec().into_iter().find_map(|(key, _, _, value)| {
+                let labeled = key.key().labels().any(|label| label.value() == reason);
+                match (key.key().name(), value) {
+                    ("wallet.rejected_send_transaction_calls", DebugValue::Counter(count))
+                        if labeled =>
+                    {
+                        Some(count)
+                    }
+                    _ => None,
+                }
+            })
+        };
+        assert_eq!(rejections("value_not_zero"), Some(2));
+        assert_eq!(rejections("nonce_set"), Some(1));
+        assert_eq!(rejections("gas_set"), None);
+    }
+
+    #[test]
+    fn send_transaction_duration() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+
+        metrics::with_local_recorder(&recorder, || {
+            futures::executor::block_on(
+                wallet.send_transaction(delegated_call().value(U256::from(1)), None),
+            )
+            .unwrap_err()
+        });
+
+        let durations = |outcome: &str| {
+            snapshotter.snapshot().into_vec().into_iter().find_map(|(key, _, _, value)| {
+                let labeled = key.key().labels().any(|label| label.value() == outcome);
+                match (key.key().name(), value) {
+                    ("wallet.send_transaction_duration", DebugValue::Histogram(durations))
+                        if labeled =>
+                    {
+                        Some(durations.len())
+                    }
+                    _ => None,
+                }
+            })
+        };
+        assert_eq!(durations("failure"), Some(1));
+        assert_eq!(durations("success"), None);
+    }
+
+    #[tokio::test]
+    async fn pause_and_resume_sponsoring() {
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+
+        // pausing is observed by all clones
+        wallet.clone().pause_sponsoring().await.unwrap();
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "sponsoring_paused");
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+
+        wallet.clone().resume_sponsoring().await.unwrap();
+        assert!(wallet.send_transaction(delegated_call(), None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn contract_creation() {
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+
+        // an eip-1559 create tx
+        let request = TransactionRequest { to: Some(TxKind::Create), ..Default::default() };
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "contract_creation_unsupported");
+
+        // an eip-7702 tx without a destination
+        let signer = PrivateKeySigner::random();
+        let request = TransactionRequest::default()
+            .with_authorization_list(vec![sign_authorization(&signer, DELEGATION)]);
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "contract_creation_unsupported");
+
+        // nothing was sent
+        assert!(wallet.inner.upstream.sent.lock().is_empty());
+    }
+
+    #[tokio::test]
+    async fn destinations() {
+        let allowed = PrivateKeySigner::random();
+        let disallowed = PrivateKeySigner::random();
+        let delegation = |signer: &PrivateKeySigner| {
+            TransactionRequest::default()
+                .with_authorization_list(vec![sign_authorization(signer, DELEGATION)])
+                .to(signer.address())
+        };
+
+        // destinations are not restricted by default
+        let wallet = TraverseWallet::new(MockUpstream::default(), 1);
+        assert!(wallet.send_transaction(delegation(&disallowed), None).await.is_ok());
+
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_destinations([allowed.address(), DESTINATION]),
+        );
+
+        // an eip-7702 tx with an allowed `to`
+        assert!(wallet.send_transaction(delegation(&allowed), None).await.is_ok());
+
+        // an eip-7702 tx with a disallowed `to`
+        let err = wallet.send_transaction(delegation(&disallowed), None).await.unwrap_err();
+        assert_eq!(reason(&err), "destination_not_allowed");
+
+        // an eip-7702 tx without a `to`
+        let request = TransactionRequest::default()
+            .with_authorization_list(vec![sign_authorization(&allowed, DELEGATION)]);
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "contract_creation_unsupported");
+
+        // eip-1559 txs are checked the same way
+        assert!(wallet.send_transaction(delegated_call(), None).await.is_ok());
+        let err = wallet
+            .send_transaction(delegated_call().to(disallowed.address()), None)
+            .await
+            .unwrap_err();
+        assert_eq!(reason(&err), "destination_not_allowed");
+
+        assert_eq!(wallet.inner.upstream.sent.lock().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn accepted_types() {
+        // only sponsor delegations
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_accepted_types([SponsoredTxType::Eip7702]),
+        );
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "transaction_type_not_accepted");
+
+        let signer = PrivateKeySigner::random();
+        let request = TransactionRequest::default()
+            .with_authorization_list(vec![sign_authorization(&signer, DELEGATION)])
+            .to(signer.address());
+        assert!(wallet.send_transaction(request.clone(), None).await.is_ok());
+
+        // only sponsor calls to delegated accounts
+        let wallet = TraverseWallet::with_config(
+            MockUpstream::default(),
+            1,
+            TraverseWalletConfig::default().with_accepted_types([SponsoredTxType::Eip1559]),
+        );
+        let err = wallet.send_transaction(request, None).await.unwrap_err();
+        assert_eq!(reason(&err), "transaction_type_not_accepted");
+        assert!(wallet.send_transaction(delegated_call(), None).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn access_list_generation() {
+        let access_list = AccessList(vec![AccessListItem {
+            address: DELEGATION,
+            storage_keys: vec![Default::default()],
+        }]);
+        let upstream = MockUpstream { access_list: access_list.clone(), ..Default::default() };
+        let wallet = TraverseWallet::with_config(
+            upstream,
+            1,
+            TraverseWalletConfig::default().with_access_list(true),
+        );
+        wallet.send_transaction(delegated_call(), None).await.unwrap();
+
+        let sent = wallet.inner.upstream.sent.lock();
+        assert_eq!(sent[0].access_list, Some(access_list));
+        assert_eq!(sent[0].gas, Some(99_000));
+    }
+
+    #[tokio::test]
+    async fn multi_chain_dispatch() {
+        let wallet = MultiChainWallet::new()
+            .with_chain(TraverseWallet::new(MockUpstream::default(), 1))
+            .with_chain(TraverseWallet::with_config(
+                MockUpstream::default(),
+                2,
+                TraverseWalletConfig::default().with_default_gas_ceiling(50_000),
+            ));
+
+        // the first chain sponsors the request with the chain id of the request
+        let mut request = delegated_call();
+        request.chain_id = Some(1);
+        wallet.send_transaction(request.clone(), None).await.unwrap();
+        let sent = wallet.wallet(1).unwrap().inner.upstream.sent.lock()[0].clone();
+        assert_eq!(sent.chain_id, Some(1));
+
+        // the second chain has a lower gas ceiling
+        request.chain_id = Some(2);
+        let err = wallet.send_transaction(request.clone(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "gas_estimate_too_high");
+        assert!(wallet.wallet(2).unwrap().inner.upstream.sent.lock().is_empty());
+
+        // unknown and missing chain ids are rejected
+        request.chain_id = Some(3);
+        let err = wallet.send_transaction(request.clone(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "unsupported_chain_id");
+        let err = wallet.send_transaction(delegated_call(), None).await.unwrap_err();
+        assert_eq!(reason(&err), "unsupported_chain_id");
 
-        assert!(matches!(validate_tx_request(&TransactionRequest::default()), Ok(())));
+        assert_eq!(wallet.get_limits(Some(2)).await.unwrap().gas_ceiling, 50_000);
     }
 }
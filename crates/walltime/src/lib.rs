@@ -1,21 +1,49 @@
 //! # Traverse walltime
 //!
 //! Returns the current walltime and the chain's tip timestamps.
+//!
+//! The recent [`BlockTimeData`] can optionally be persisted to an append-only log on disk, so that
+//! it survives restarts, see [`TraverseWallTime::spawn`].
 
 #![cfg_attr(not(test), warn(unused_crate_dependencies))]
 
 use alloy_consensus::BlockHeader;
 use futures::{Stream, StreamExt};
 use jsonrpsee::{
-    core::{async_trait, RpcResult},
+    core::{async_trait, RpcResult, SubscriptionResult},
     proc_macros::rpc,
-    types::{error::INTERNAL_ERROR_CODE, ErrorObject},
+    types::{
+        error::{INTERNAL_ERROR_CODE, INVALID_PARAMS_CODE},
+        ErrorObject,
+    },
+    PendingSubscriptionSink, SubscriptionMessage,
 };
+use metrics::{Counter, Gauge};
+use metrics_derive::Metrics;
+use parking_lot::Mutex;
 use reth_chain_state::CanonStateNotification;
 use reth_node_api::NodePrimitives;
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::{
+    collections::VecDeque,
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{self, BufRead, BufReader, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+use tokio::{
+    sync::{
+        broadcast::{self, error::RecvError},
+        mpsc, oneshot, RwLock,
+    },
+    task::JoinHandle,
+};
+use tracing::{debug, warn};
 
 /// The traverse walltime endpoint.
 #[derive(Debug, Clone)]
@@ -25,29 +53,358 @@ pub struct TraverseWallTime {
 
 impl TraverseWallTime {
     /// Creates a new instance with the connected stream.
-    pub fn spawn<St, N>(mut st: St) -> Self
+    ///
+    /// The stream is consumed by a spawned task until it ends, or until [`Self::shutdown`] is
+    /// called.
+    ///
+    /// If a persistence path is given, every recorded [`BlockTimeData`] is appended to the log at
+    /// that path, and the retained blocks are restored from it on startup. The log is rotated
+    /// every [`PERSISTED_SAMPLES_PER_FILE`] samples, keeping a single rotated file next to it.
+    /// The log is written by a blocking task, so that new blocks are not delayed by the disk. If
+    /// the log can not be opened, persistence is disabled with a warning.
+    pub fn spawn<St, N>(st: St, persistence: Option<PathBuf>) -> Self
     where
         St: Stream<Item = CanonStateNotification<N>> + Send + Unpin + 'static,
         N: NodePrimitives,
     {
-        let walltime = Self { inner: Default::default() };
+        let (log, history) = match persistence.map(|path| WallTimeLog::open(&path)) {
+            Some(Ok((log, history))) => (Some(log), history),
+            Some(Err(err)) => {
+                warn!(target: "rpc::walltime", %err, "Failed to open the walltime log");
+                (None, VecDeque::new())
+            }
+            None => (None, VecDeque::new()),
+        };
+        let (log, writer) = match log {
+            Some(log) => {
+                let (log_tx, log_rx) = mpsc::unbounded_channel();
+                (Some(log_tx), Some(tokio::task::spawn_blocking(move || log.write_all(log_rx))))
+            }
+            None => (None, None),
+        };
+
+        let (shutdown_tx, shutdown_rx) = oneshot::channel();
+        let walltime = Self {
+            inner: Arc::new(TraverseWallTimeInner {
+                // the restored tip is served until the next block
+                block_time_data: RwLock::new(history.back().map(|(_, data)| *data)),
+                history: RwLock::new(history),
+                log: Mutex::new(log),
+                writer: Mutex::new(writer),
+                shutdown: Mutex::new(Some(shutdown_tx)),
+                ..Default::default()
+            }),
+        };
         let listener = walltime.clone();
-        tokio::task::spawn(async move {
-            while let Some(notification) = st.next().await {
-                let tip = BlockTimeData {
-                    wall_time_ms: unix_epoch_ms(),
-                    block_timestamp: notification.tip().header().timestamp(),
-                };
-                *listener.inner.block_time_data.write().await = Some(tip);
+        let task = tokio::task::spawn(async move {
+            let mut st = st.take_until(shutdown_rx);
+            let mut age_interval = tokio::time::interval(LAST_UPDATE_AGE_INTERVAL);
+            loop {
+                tokio::select! {
+                    notification = st.next() => {
+                        let Some(notification) = notification else { break };
+                        let header = notification.tip().header();
+                        let reorg = matches!(notification, CanonStateNotification::Reorg { .. });
+                        listener.on_tip(header.number(), header.timestamp(), reorg).await;
+                    }
+                    _ = age_interval.tick() => listener.update_last_update_age().await,
+                }
             }
         });
+        *walltime.inner.task.lock() = Some(task);
         walltime
     }
 
+    /// Creates a new instance with the connected canonical state receiver.
+    ///
+    /// If the receiver lags behind, the skipped notifications are logged and the task continues
+    /// with the next notification.
+    ///
+    /// See [`Self::spawn`] for the persistence path.
+    pub fn spawn_from_canon_receiver<N>(
+        receiver: broadcast::Receiver<CanonStateNotification<N>>,
+        persistence: Option<PathBuf>,
+    ) -> Self
+    where
+        N: NodePrimitives,
+    {
+        let skipped = WallTimeMetrics::default().skipped_notifications;
+        Self::spawn(Box::pin(skip_lagged(receiver, skipped)), persistence)
+    }
+
+    /// Warns when `traverse_getWallTimeData` serves data whose last block is older than the given
+    /// threshold.
+    ///
+    /// The data is still returned, and the warning is logged at most once every
+    /// [`STALE_WARNING_INTERVAL`].
+    pub fn with_stale_warn_threshold(self, threshold: Duration) -> Self {
+        let threshold_ms = (threshold.as_millis() as u64).max(1);
+        self.inner.stale_warn_threshold_ms.store(threshold_ms, Ordering::Relaxed);
+        self
+    }
+
+    /// Emits a [`DriftAlert`] to `traverse_subscribeDriftAlerts` subscribers whenever the drift of
+    /// a new block crosses the given threshold.
+    ///
+    /// An alert is emitted when the absolute drift rises above the threshold, and once it falls
+    /// below the threshold minus the hysteresis again, so that a drift close to the threshold
+    /// does not cause a stream of alerts.
+    pub fn with_drift_alerts(self, threshold: Duration, hysteresis: Duration) -> Self {
+        let threshold_ms = (threshold.as_millis() as u64).max(1);
+        self.inner.drift.threshold_ms.store(threshold_ms, Ordering::Relaxed);
+        self.inner.drift.hysteresis_ms.store(hysteresis.as_millis() as u64, Ordering::Relaxed);
+        self
+    }
+
+    /// Configures whether notifications whose tip is the genesis block are recorded.
+    ///
+    /// By default, tips with block number or timestamp zero are skipped, so that the endpoint is
+    /// not seeded with genesis data on startup before real blocks arrive.
+    pub fn with_record_genesis(self, record_genesis: bool) -> Self {
+        self.inner.record_genesis.store(record_genesis, Ordering::Relaxed);
+        self
+    }
+
+    /// Stops consuming the stream and waits for the spawned task to terminate.
+    ///
+    /// If persistence is enabled, this also waits until the recorded samples are written to the
+    /// log. The last tracked [`BlockTimeData`] is still served after shutdown, but new blocks are
+    /// no longer persisted.
+    pub async fn shutdown(&self) {
+        if let Some(shutdown) = self.inner.shutdown.lock().take() {
+            let _ = shutdown.send(());
+        }
+        let task = self.inner.task.lock().take();
+        if let Some(task) = task {
+            let _ = task.await;
+        }
+
+        // closing the channel ends the writer once it wrote the pending samples
+        self.inner.log.lock().take();
+        let writer = self.inner.writer.lock().take();
+        if let Some(writer) = writer {
+            let _ = writer.await;
+        }
+    }
+
+    /// Records the wall time of the tip of a canonical state notification.
+    ///
+    /// Genesis tips are skipped unless configured otherwise, see [`Self::with_record_genesis`].
+    async fn on_tip(&self, block_number: u64, block_timestamp: u64, reorg: bool) {
+        if (block_number == 0 || block_timestamp == 0)
+            && !self.inner.record_genesis.load(Ordering::Relaxed)
+        {
+            debug!(
+                target: "rpc::walltime",
+                block_number,
+                block_timestamp,
+                "Skipping canonical state notification with a genesis tip"
+            );
+            return;
+        }
+
+        if reorg {
+            self.on_block(block_number, block_timestamp).await;
+        } else {
+            self.on_commit(block_number, block_timestamp).await;
+        }
+    }
+
+    /// Records the wall time of a newly committed canonical tip.
+    ///
+    /// Commits whose block timestamp is not after the timestamp of the current tip are out of
+    /// order, and are ignored so that time never goes backwards. Reorgs are recorded with
+    /// [`Self::on_block`] instead.
+    async fn on_commit(&self, block_number: u64, block_timestamp: u64) {
+        if let Some(current) = self.current_block_time().await {
+            if block_timestamp <= current.block_timestamp {
+                warn!(
+                    target: "rpc::walltime",
+                    block_number,
+                    block_timestamp,
+                    current_timestamp = current.block_timestamp,
+                    "Ignoring out of order canonical state notification"
+                );
+                return;
+            }
+        }
+        self.on_block(block_number, block_timestamp).await
+    }
+
+    /// Records the wall time of a new canonical tip.
+    async fn on_block(&self, block_number: u64, block_timestamp: u64) {
+        let tip = BlockTimeData { wall_time_ms: unix_epoch_ms(), block_timestamp };
+
+        record_block(&mut *self.inner.history.write().await, block_number, tip);
+        if let Some(log) = self.inner.log.lock().as_ref() {
+            let _ = log.send((block_number, tip));
+        }
+
+        *self.inner.block_time_data.write().await = Some(tip);
+
+        self.inner.drift.on_block(block_number, tip.drift_ms());
+        self.inner.metrics.notifications.increment(1);
+        self.inner.metrics.last_update_age_ms.set(0.0);
+    }
+
+    /// Updates the age of the last tracked [`BlockTimeData`].
+    async fn update_last_update_age(&self) {
+        if let Some(current) = self.current_block_time().await {
+            let age = unix_epoch_ms().saturating_sub(current.wall_time_ms);
+            self.inner.metrics.last_update_age_ms.set(age as f64);
+        }
+    }
+
+    /// Returns the [`BlockTimeData`] of the given block, if it is retained.
+    async fn block_time(&self, block_number: u64) -> Option<BlockTimeData> {
+        self.inner
+            .history
+            .read()
+            .await
+            .iter()
+            .find(|(number, _)| *number == block_number)
+            .map(|(_, data)| *data)
+    }
+
     /// Returns the currently tracked [`BlockTimeData`] if any.
     async fn current_block_time(&self) -> Option<BlockTimeData> {
         *self.inner.block_time_data.read().await
     }
+
+    /// Warns if the last block is older than the configured threshold, unless a warning was
+    /// logged within the last [`STALE_WARNING_INTERVAL`].
+    fn warn_if_stale(&self, current_wall_time_ms: u64, last_block_wall_time_ms: u64) {
+        let threshold_ms = self.inner.stale_warn_threshold_ms.load(Ordering::Relaxed);
+        let age_ms = current_wall_time_ms.saturating_sub(last_block_wall_time_ms);
+        if threshold_ms == 0 || age_ms <= threshold_ms {
+            return;
+        }
+
+        let last_warning_ms = self.inner.last_stale_warning_ms.load(Ordering::Relaxed);
+        if last_warning_ms != 0
+            && current_wall_time_ms.saturating_sub(last_warning_ms)
+                < STALE_WARNING_INTERVAL.as_millis() as u64
+        {
+            return;
+        }
+        // only one of concurrent callers logs the warning
+        if self
+            .inner
+            .last_stale_warning_ms
+            .compare_exchange(
+                last_warning_ms,
+                current_wall_time_ms,
+                Ordering::Relaxed,
+                Ordering::Relaxed,
+            )
+            .is_ok()
+        {
+            warn!(target: "rpc::walltime", age_ms, threshold_ms, "Serving stale wall time data");
+        }
+    }
+}
+
+/// How often the age of the last update is reported.
+const LAST_UPDATE_AGE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The minimum interval between two warnings about stale wall time data, see
+/// [`TraverseWallTime::with_stale_warn_threshold`].
+pub const STALE_WARNING_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The number of recent blocks whose [`BlockTimeData`] is retained.
+pub const BLOCK_TIME_HISTORY: usize = 256;
+
+/// The number of samples written to the walltime log before it is rotated.
+///
+/// Together with the rotated file, this bounds the log to twice as many samples, and makes sure
+/// that the last [`BLOCK_TIME_HISTORY`] samples can always be restored.
+pub const PERSISTED_SAMPLES_PER_FILE: usize = BLOCK_TIME_HISTORY;
+
+/// The number of [`DriftAlert`]s buffered for each subscriber.
+pub const DRIFT_ALERT_CAPACITY: usize = 16;
+
+/// Whether the drift of a block crossed above or below the threshold of a [`DriftAlert`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum DriftDirection {
+    /// The absolute drift rose above the threshold.
+    Above,
+    /// The absolute drift fell below the threshold, minus the hysteresis.
+    Below,
+}
+
+/// An alert that the drift between wall time and chain time crossed the configured threshold,
+/// see [`TraverseWallTime::with_drift_alerts`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DriftAlert {
+    /// The number of the block whose drift crossed the threshold.
+    pub block_number: u64,
+    /// The wall time at which the block was received, minus its timestamp, in milliseconds.
+    pub drift_ms: i64,
+    /// The threshold of the absolute drift, in milliseconds.
+    pub threshold_ms: u64,
+    /// The direction in which the drift crossed the threshold.
+    pub direction: DriftDirection,
+}
+
+/// Tracks the drift of new blocks, and emits [`DriftAlert`]s when it crosses the threshold.
+#[derive(Debug)]
+struct DriftAlerts {
+    /// The threshold of the absolute drift in milliseconds, `0` if alerts are disabled.
+    threshold_ms: AtomicU64,
+    /// How far the absolute drift has to fall below the threshold to end an alert.
+    hysteresis_ms: AtomicU64,
+    /// Whether the drift is currently above the threshold.
+    alerting: AtomicBool,
+    /// Sender of [`DriftAlert`]s to subscribers.
+    sender: broadcast::Sender<DriftAlert>,
+}
+
+impl Default for DriftAlerts {
+    fn default() -> Self {
+        Self {
+            threshold_ms: AtomicU64::new(0),
+            hysteresis_ms: AtomicU64::new(0),
+            alerting: AtomicBool::new(false),
+            sender: broadcast::channel(DRIFT_ALERT_CAPACITY).0,
+        }
+    }
+}
+
+impl DriftAlerts {
+    /// Emits an alert if the drift of the new block crossed the threshold.
+    fn on_block(&self, block_number: u64, drift_ms: i64) {
+        let threshold_ms = self.threshold_ms.load(Ordering::Relaxed);
+        if threshold_ms == 0 {
+            return;
+        }
+
+        let hysteresis_ms = self.hysteresis_ms.load(Ordering::Relaxed);
+        let magnitude = drift_ms.unsigned_abs();
+        let direction = if self.alerting.load(Ordering::Relaxed) {
+            if magnitude >= threshold_ms.saturating_sub(hysteresis_ms) {
+                return;
+            }
+            DriftDirection::Below
+        } else {
+            if magnitude <= threshold_ms {
+                return;
+            }
+            DriftDirection::Above
+        };
+        self.alerting.store(direction == DriftDirection::Above, Ordering::Relaxed);
+
+        warn!(
+            target: "rpc::walltime",
+            block_number,
+            drift_ms,
+            ?direction,
+            "Drift crossed threshold"
+        );
+        // notify subscribers, if there are any
+        let _ = self.sender.send(DriftAlert { block_number, drift_ms, threshold_ms, direction });
+    }
 }
 
 /// Implementation of the Traverse `traverse_getWallTimeData` endpoint.
@@ -55,6 +412,38 @@ impl TraverseWallTime {
 struct TraverseWallTimeInner {
     /// Tracks the recent blocktime data
     block_time_data: RwLock<Option<BlockTimeData>>,
+    /// The blocktime data of the last [`BLOCK_TIME_HISTORY`] blocks, by block number.
+    history: RwLock<VecDeque<(u64, BlockTimeData)>>,
+    /// Signals the spawned task to stop consuming the stream.
+    shutdown: Mutex<Option<oneshot::Sender<()>>>,
+    /// The handle of the spawned task.
+    task: Mutex<Option<JoinHandle<()>>>,
+    /// Sender of the blocktime data to the writer of the log, if persistence is enabled.
+    log: Mutex<Option<mpsc::UnboundedSender<(u64, BlockTimeData)>>>,
+    /// The handle of the blocking task that writes the log.
+    writer: Mutex<Option<JoinHandle<()>>>,
+    /// The age in milliseconds past which serving the last block is logged, `0` if disabled.
+    stale_warn_threshold_ms: AtomicU64,
+    /// The wall time in milliseconds of the last stale data warning, `0` if none was logged.
+    last_stale_warning_ms: AtomicU64,
+    /// Whether tips with block number or timestamp zero are recorded.
+    record_genesis: AtomicBool,
+    /// Alerts about the drift of new blocks.
+    drift: DriftAlerts,
+    /// Metrics of the canonical state stream.
+    metrics: WallTimeMetrics,
+}
+
+/// Metrics of the canonical state stream of [`TraverseWallTime`].
+#[derive(Metrics)]
+#[metrics(scope = "walltime")]
+struct WallTimeMetrics {
+    /// Number of processed canonical state notifications
+    notifications: Counter,
+    /// Milliseconds since the last canonical state notification was processed
+    last_update_age_ms: Gauge,
+    /// Number of canonical state notifications that were skipped, because the receiver lagged
+    skipped_notifications: Counter,
 }
 
 /// Data about the current time and the last block's.
@@ -75,6 +464,23 @@ pub trait TraverseWallTimeRpcApi {
     /// Return the wall time and block timestamp of the latest block.
     #[method(name = "getWallTimeData")]
     async fn get_timedata(&self) -> RpcResult<WallTimeData>;
+
+    /// Return the wall time and block timestamp of the given block.
+    ///
+    /// Only the last [`BLOCK_TIME_HISTORY`] blocks are retained.
+    #[method(name = "getBlockWallTimeData")]
+    async fn get_block_timedata(&self, block_number: u64) -> RpcResult<BlockTimeData>;
+
+    /// Subscribe to alerts about the drift between wall time and chain time, see [`DriftAlert`].
+    ///
+    /// Alerts are only emitted if they are enabled with [`TraverseWallTime::with_drift_alerts`].
+    /// Subscribers that fall behind by more than [`DRIFT_ALERT_CAPACITY`] alerts are dropped.
+    #[subscription(
+        name = "subscribeDriftAlerts",
+        unsubscribe = "unsubscribeDriftAlerts",
+        item = DriftAlert
+    )]
+    async fn subscribe_drift_alerts(&self) -> SubscriptionResult;
 }
 
 #[async_trait]
@@ -83,12 +489,50 @@ impl TraverseWallTimeRpcApiServer for TraverseWallTime {
         let Some(current) = self.current_block_time().await else {
             return Err(ErrorObject::owned(INTERNAL_ERROR_CODE, "node is not synced", None::<()>));
         };
+        let current_wall_time_ms = unix_epoch_ms();
+        self.warn_if_stale(current_wall_time_ms, current.wall_time_ms);
         Ok(WallTimeData {
-            current_wall_time_ms: unix_epoch_ms(),
+            current_wall_time_ms,
             last_block_wall_time_ms: current.wall_time_ms,
             last_block_timestamp: current.block_timestamp,
         })
     }
+
+    async fn get_block_timedata(&self, block_number: u64) -> RpcResult<BlockTimeData> {
+        self.block_time(block_number).await.ok_or_else(|| {
+            ErrorObject::owned(
+                INVALID_PARAMS_CODE,
+                format!("block {block_number} is outside the retained window"),
+                None::<()>,
+            )
+        })
+    }
+
+    async fn subscribe_drift_alerts(&self, pending: PendingSubscriptionSink) -> SubscriptionResult {
+        let mut alerts = self.inner.drift.sender.subscribe();
+        let sink = pending.accept().await?;
+
+        loop {
+            tokio::select! {
+                _ = sink.closed() => break,
+                alert = alerts.recv() => match alert {
+                    Ok(alert) => sink.send(SubscriptionMessage::from_json(&alert)?).await?,
+                    // the subscriber is too slow, drop it instead of buffering indefinitely
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(
+                            target: "rpc::walltime",
+                            skipped,
+                            "Dropping lagging drift subscriber"
+                        );
+                        break
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            }
+        }
+
+        Ok(())
+    }
 }
 
 /// Time data about the last block.
@@ -100,11 +544,472 @@ pub struct BlockTimeData {
     block_timestamp: u64,
 }
 
+impl BlockTimeData {
+    /// Returns the wall time of the block minus its timestamp, in milliseconds.
+    ///
+    /// This is positive if the block was received after its timestamp.
+    const fn drift_ms(&self) -> i64 {
+        self.wall_time_ms as i64 - self.block_timestamp.saturating_mul(1000) as i64
+    }
+}
+
+/// Records the blocktime data of a new canonical tip in the history, dropping the blocks that
+/// were reorged out, and the oldest block if the history is full.
+fn record_block(history: &mut VecDeque<(u64, BlockTimeData)>, number: u64, data: BlockTimeData) {
+    while history.back().is_some_and(|(retained, _)| *retained >= number) {
+        history.pop_back();
+    }
+    if history.len() == BLOCK_TIME_HISTORY {
+        history.pop_front();
+    }
+    history.push_back((number, data));
+}
+
+/// An append-only log of the recorded [`BlockTimeData`].
+///
+/// Every sample is written as a line of the block number, the wall time and the block timestamp.
+/// Once the log holds [`PERSISTED_SAMPLES_PER_FILE`] samples, it is moved to a rotated file with
+/// a `.1` suffix, replacing the previously rotated one.
+#[derive(Debug)]
+struct WallTimeLog {
+    /// The path of the current log.
+    path: PathBuf,
+    /// The current log, opened for appending.
+    file: File,
+    /// The number of samples in the current log.
+    samples: usize,
+}
+
+impl WallTimeLog {
+    /// Opens the log at the given path, creating it if it does not exist, and returns the history
+    /// restored from the rotated and the current log.
+    fn open(path: &Path) -> io::Result<(Self, VecDeque<(u64, BlockTimeData)>)> {
+        let mut history = VecDeque::new();
+        Self::replay(&rotated_path(path), &mut history)?;
+        let samples = Self::replay(path, &mut history)?;
+
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok((Self { path: path.to_path_buf(), file, samples }, history))
+    }
+
+    /// Records the samples of the log at the given path in the history, and returns how many
+    /// were read.
+    ///
+    /// A missing log is empty, and malformed lines, e.g. a line truncated by a crash, are skipped.
+    fn replay(path: &Path, history: &mut VecDeque<(u64, BlockTimeData)>) -> io::Result<usize> {
+        let file = match File::open(path) {
+            Ok(file) => file,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err),
+        };
+
+        let mut samples = 0;
+        for line in BufReader::new(file).lines() {
+            let line = line?;
+            samples += 1;
+            let mut fields = line.split_whitespace().map(str::parse::<u64>);
+            let (Some(Ok(number)), Some(Ok(wall_time_ms)), Some(Ok(block_timestamp)), None) =
+                (fields.next(), fields.next(), fields.next(), fields.next())
+            else {
+                warn!(target: "rpc::walltime", ?path, %line, "Skipping malformed walltime sample");
+                continue;
+            };
+            record_block(history, number, BlockTimeData { wall_time_ms, block_timestamp });
+        }
+        Ok(samples)
+    }
+
+    /// Appends the received samples to the log, until the channel is closed.
+    ///
+    /// This blocks on the disk, and must be run on a blocking task.
+    fn write_all(mut self, mut samples: mpsc::UnboundedReceiver<(u64, BlockTimeData)>) {
+        while let Some((number, data)) = samples.blocking_recv() {
+            if let Err(err) = self.append(number, data) {
+                warn!(target: "rpc::walltime", number, %err, "Failed to persist wall time");
+            }
+        }
+    }
+
+    /// Appends a sample to the log, rotating it first if it is full.
+    fn append(&mut self, number: u64, data: BlockTimeData) -> io::Result<()> {
+        if self.samples >= PERSISTED_SAMPLES_PER_FILE {
+            fs::rename(&self.path, rotated_path(&self.path))?;
+            self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+            self.samples = 0;
+        }
+        writeln!(self.file, "{number} {} {}", data.wall_time_ms, data.block_timestamp)?;
+        self.samples += 1;
+        Ok(())
+    }
+}
+
+/// Returns the path of the rotated walltime log.
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut rotated = OsString::from(path.as_os_str());
+    rotated.push(".1");
+    rotated.into()
+}
+
+/// Turns the broadcast receiver into a stream that skips over lagged items.
+///
+/// The stream ends once the channel is closed.
+///
+/// The number of skipped items is added to the given counter.
+fn skip_lagged<T>(
+    receiver: broadcast::Receiver<T>,
+    skipped_items: Counter,
+) -> impl Stream<Item = T> + Send
+where
+    T: Clone + Send + 'static,
+{
+    futures::stream::unfold(receiver, move |mut receiver| {
+        let skipped_items = skipped_items.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(item) => return Some((item, receiver)),
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!(target: "rpc::walltime", skipped, "Canonical state receiver lagged");
+                        skipped_items.increment(skipped);
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    })
+}
+
 /// Returns the current unix epoch in milliseconds.
+///
+/// See [`unix_epoch_ms_at`].
 pub fn unix_epoch_ms() -> u64 {
-    use std::time::SystemTime;
-    let now = SystemTime::now();
-    now.duration_since(SystemTime::UNIX_EPOCH)
-        .unwrap_or_else(|err| panic!("Current time {now:?} is invalid: {err:?}"))
-        .as_millis() as u64
+    unix_epoch_ms_at(SystemTime::now())
+}
+
+/// Returns the unix epoch of the given time in milliseconds.
+///
+/// A time before the epoch, which happens if the system clock is misconfigured, saturates to 0
+/// with a warning instead of panicking.
+pub fn unix_epoch_ms_at(time: SystemTime) -> u64 {
+    match time.duration_since(SystemTime::UNIX_EPOCH) {
+        Ok(elapsed) => elapsed.as_millis() as u64,
+        Err(err) => {
+            warn!(target: "rpc::walltime", ?time, %err, "System time is before the unix epoch");
+            0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::{server::Server, ws_client::WsClientBuilder};
+    use metrics_util::debugging::{DebugValue, DebuggingRecorder};
+    use reth_primitives::EthPrimitives;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn shutdown_terminates_task() {
+        let walltime = TraverseWallTime::spawn(
+            futures::stream::pending::<CanonStateNotification<EthPrimitives>>(),
+            None,
+        );
+
+        tokio::time::timeout(Duration::from_secs(1), walltime.shutdown())
+            .await
+            .expect("task should terminate");
+        assert!(walltime.inner.task.lock().is_none());
+
+        // shutting down again is a no-op
+        walltime.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn skip_lagged_continues() {
+        let (tx, rx) = broadcast::channel(1);
+        let mut st = Box::pin(skip_lagged(rx, Counter::noop()));
+
+        // the first item is overwritten, so the receiver lags
+        tx.send(1).unwrap();
+        tx.send(2).unwrap();
+        assert_eq!(st.next().await, Some(2));
+
+        tx.send(3).unwrap();
+        assert_eq!(st.next().await, Some(3));
+
+        drop(tx);
+        assert_eq!(st.next().await, None);
+    }
+
+    #[tokio::test]
+    async fn spawn_from_canon_receiver_terminates() {
+        let (tx, rx) = broadcast::channel::<CanonStateNotification<EthPrimitives>>(1);
+        let walltime = TraverseWallTime::spawn_from_canon_receiver(rx, None);
+
+        // the task ends once the channel is closed
+        drop(tx);
+        tokio::time::timeout(Duration::from_secs(1), walltime.shutdown())
+            .await
+            .expect("task should terminate");
+    }
+
+    #[tokio::test]
+    async fn block_wall_time_data() {
+        let walltime = TraverseWallTime::spawn(
+            futures::stream::pending::<CanonStateNotification<EthPrimitives>>(),
+            None,
+        );
+        for number in 1..=5 {
+            walltime.on_block(number, number * 2).await;
+        }
+
+        let data = walltime.get_block_timedata(3).await.unwrap();
+        assert_eq!(data.block_timestamp, 6);
+        assert!(walltime.get_block_timedata(6).await.is_err());
+
+        // a reorg replaces the reorged blocks
+        walltime.on_block(3, 7).await;
+        assert_eq!(walltime.get_block_timedata(3).await.unwrap().block_timestamp, 7);
+        assert!(walltime.get_block_timedata(4).await.is_err());
+
+        // old blocks are dropped from the window
+        for number in 4..4 + BLOCK_TIME_HISTORY as u64 {
+            walltime.on_block(number, number * 2).await;
+        }
+        assert!(walltime.get_block_timedata(3).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn persisted_history_restored() {
+        let dir = std::env::temp_dir().join(format!("walltime-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("walltime.log");
+        let _ = fs::remove_file(&path);
+        let _ = fs::remove_file(rotated_path(&path));
+
+        let spawn = || {
+            TraverseWallTime::spawn(
+                futures::stream::pending::<CanonStateNotification<EthPrimitives>>(),
+                Some(path.clone()),
+            )
+        };
+
+        let walltime = spawn();
+        // enough samples to rotate the log
+        let last = PERSISTED_SAMPLES_PER_FILE as u64 + 10;
+        for number in 1..=last {
+            walltime.on_block(number, number * 2).await;
+        }
+        // a reorg is persisted as well
+        walltime.on_block(last, last * 2 + 1).await;
+        let expected = walltime.inner.history.read().await.clone();
+        walltime.shutdown().await;
+        drop(walltime);
+
+        // the log is bounded by the rotation
+        assert!(rotated_path(&path).exists());
+        let lines = fs::read_to_string(&path).unwrap().lines().count();
+        assert!(lines <= PERSISTED_SAMPLES_PER_FILE);
+
+        // restarting restores the retained blocks
+        let walltime = spawn();
+        assert_eq!(*walltime.inner.history.read().await, expected);
+        assert_eq!(walltime.get_block_timedata(last).await.unwrap().block_timestamp, last * 2 + 1);
+        assert!(walltime.get_block_timedata(10).await.is_err());
+        // the restored tip is served before the next block
+        let data = walltime.get_timedata().await.unwrap();
+        assert_eq!(data.last_block_timestamp, last * 2 + 1);
+        assert_eq!(data.last_block_wall_time_ms, expected.back().unwrap().1.wall_time_ms);
+
+        // new blocks keep being appended to the restored log
+        walltime.on_block(last + 1, last * 2 + 2).await;
+        walltime.shutdown().await;
+        drop(walltime);
+        let walltime = spawn();
+        assert!(walltime.get_block_timedata(last + 1).await.is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    /// Captures the logs written by the subscriber.
+    #[derive(Clone, Default)]
+    struct CapturedLogs(Arc<Mutex<Vec<u8>>>);
+
+    impl CapturedLogs {
+        fn count(&self, message: &str) -> usize {
+            String::from_utf8_lossy(&self.0.lock()).matches(message).count()
+        }
+    }
+
+    impl std::io::Write for CapturedLogs {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn stale_data_warning() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt().with_writer(move || writer.clone()).finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let walltime = TraverseWallTime::spawn(
+            futures::stream::pending::<CanonStateNotification<EthPrimitives>>(),
+            None,
+        )
+        .with_stale_warn_threshold(Duration::from_secs(60));
+        walltime.on_block(1, 2).await;
+
+        // fresh data is served without a warning
+        walltime.get_timedata().await.unwrap();
+        assert_eq!(logs.count("Serving stale wall time data"), 0);
+
+        // stale data is still served, with a rate-limited warning
+        walltime.inner.block_time_data.write().await.as_mut().unwrap().wall_time_ms -= 120_000;
+        walltime.get_timedata().await.unwrap();
+        walltime.get_timedata().await.unwrap();
+        assert_eq!(logs.count("Serving stale wall time data"), 1);
+    }
+
+    #[test]
+    fn pre_epoch_time_saturates() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt().with_writer(move || writer.clone()).finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        assert_eq!(unix_epoch_ms_at(SystemTime::UNIX_EPOCH + Duration::from_millis(1500)), 1500);
+        assert_eq!(logs.count("System time is before the unix epoch"), 0);
+
+        assert_eq!(unix_epoch_ms_at(SystemTime::UNIX_EPOCH - Duration::from_secs(1)), 0);
+        assert_eq!(logs.count("System time is before the unix epoch"), 1);
+    }
+
+    #[tokio::test]
+    async fn genesis_tip_skipped() {
+        let logs = CapturedLogs::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_max_level(tracing::Level::DEBUG)
+            .with_writer(move || writer.clone())
+            .finish();
+        let _guard = tracing::subscriber::set_default(subscriber);
+
+        let walltime = TraverseWallTime::spawn(
+            futures::stream::pending::<CanonStateNotification<EthPrimitives>>(),
+            None,
+        );
+
+        // genesis tips do not seed the endpoint
+        walltime.on_tip(0, 1_700_000_000, false).await;
+        walltime.on_tip(1, 0, true).await;
+        assert!(walltime.get_timedata().await.is_err());
+        assert!(walltime.get_block_timedata(0).await.is_err());
+        assert_eq!(logs.count("Skipping canonical state notification with a genesis tip"), 2);
+
+        // the next real block is recorded
+        walltime.on_tip(1, 2, false).await;
+        assert_eq!(walltime.get_timedata().await.unwrap().last_block_timestamp, 2);
+
+        // genesis tips are recorded if configured
+        let walltime = TraverseWallTime::spawn(
+            futures::stream::pending::<CanonStateNotification<EthPrimitives>>(),
+            None,
+        )
+        .with_record_genesis(true);
+        walltime.on_tip(0, 1_700_000_000, false).await;
+        assert_eq!(walltime.get_block_timedata(0).await.unwrap().block_timestamp, 1_700_000_000);
+    }
+
+    #[tokio::test]
+    async fn out_of_order_commits_ignored() {
+        let walltime = TraverseWallTime::spawn(
+            futures::stream::pending::<CanonStateNotification<EthPrimitives>>(),
+            None,
+        );
+        walltime.on_commit(2, 4).await;
+
+        // an older commit does not move time backwards
+        walltime.on_commit(1, 2).await;
+        let data = walltime.get_timedata().await.unwrap();
+        assert_eq!(data.last_block_timestamp, 4);
+        assert!(walltime.get_block_timedata(1).await.is_err());
+        // neither does a commit with the same timestamp
+        walltime.on_commit(3, 4).await;
+        assert!(walltime.get_block_timedata(3).await.is_err());
+
+        // a newer commit is recorded
+        walltime.on_commit(3, 6).await;
+        let data = walltime.get_timedata().await.unwrap();
+        assert_eq!(data.last_block_timestamp, 6);
+        assert_eq!(walltime.get_block_timedata(3).await.unwrap().block_timestamp, 6);
+    }
+
+    #[tokio::test]
+    async fn drift_alerts() {
+        let walltime = TraverseWallTime::spawn(
+            futures::stream::pending::<CanonStateNotification<EthPrimitives>>(),
+            None,
+        )
+        .with_drift_alerts(Duration::from_secs(5), Duration::from_secs(1));
+
+        let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let _handle = server.start(walltime.clone().into_rpc());
+        let client = WsClientBuilder::default().build(format!("ws://{addr}")).await.unwrap();
+        let mut subscription = client.subscribe_drift_alerts().await.unwrap();
+
+        let now = unix_epoch_ms() / 1000;
+        // within the threshold
+        walltime.on_block(1, now).await;
+        // the block is received 10s after its timestamp
+        walltime.on_block(2, now - 10).await;
+        // still above the threshold, no new alert
+        walltime.on_block(3, now - 8).await;
+        // back within the threshold
+        walltime.on_block(4, now).await;
+
+        let alert = subscription.next().await.unwrap().unwrap();
+        assert_eq!(alert.block_number, 2);
+        assert_eq!(alert.direction, DriftDirection::Above);
+        assert!(alert.drift_ms >= 10_000);
+        assert_eq!(alert.threshold_ms, 5_000);
+
+        let alert = subscription.next().await.unwrap().unwrap();
+        assert_eq!(alert.block_number, 4);
+        assert_eq!(alert.direction, DriftDirection::Below);
+        assert!(alert.drift_ms < 4_000);
+    }
+
+    #[tokio::test]
+    async fn notifications_metric() {
+        let recorder = DebuggingRecorder::new();
+        let snapshotter = recorder.snapshotter();
+        let walltime = metrics::with_local_recorder(&recorder, || {
+            TraverseWallTime::spawn(
+                futures::stream::pending::<CanonStateNotification<EthPrimitives>>(),
+                None,
+            )
+        });
+
+        let notifications = || {
+            snapshotter.snapshot().into_vec().into_iter().find_map(|(key, _, _, value)| {
+                match (key.key().name(), value) {
+                    ("walltime.notifications", DebugValue::Counter(count)) => Some(count),
+                    _ => None,
+                }
+            })
+        };
+
+        walltime.on_block(1, 2).await;
+        assert_eq!(notifications(), Some(1));
+        walltime.on_block(2, 4).await;
+        assert_eq!(notifications(), Some(2));
+    }
 }